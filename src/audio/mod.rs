@@ -0,0 +1,3 @@
+mod pcm;
+
+pub use pcm::*;
@@ -0,0 +1,171 @@
+use std::io::{Error, ErrorKind, Result, Seek, SeekFrom, Write};
+
+use crate::reader::{ReadBytesExt, Reader};
+
+/// Uncompressed, interleaved audio samples.
+///
+/// Samples are stored the way a WAV file expects them: 8-bit samples are
+/// unsigned, wider samples are signed little-endian.
+#[derive(Debug, Clone)]
+pub struct Pcm {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+    pub samples: Vec<u8>,
+}
+
+impl Pcm {
+    pub fn frame_count(&self) -> usize {
+        let frame_size = self.channels as usize * self.bits_per_sample.div_ceil(8) as usize;
+        self.samples
+            .len()
+            .checked_div(frame_size)
+            .unwrap_or_default()
+    }
+
+    /// Converts samples stored as signed big-endian, as on the Mac, to the
+    /// layout described above.
+    pub fn from_be_samples(
+        sample_rate: u32,
+        channels: u16,
+        bits_per_sample: u16,
+        samples: &[u8],
+    ) -> Self {
+        let samples = match bits_per_sample {
+            8 => samples.iter().map(|&s| s ^ 0x80).collect(),
+            bits => {
+                let sample_size = bits.div_ceil(8) as usize;
+                samples
+                    .chunks_exact(sample_size)
+                    .flat_map(|sample| sample.iter().rev().copied())
+                    .collect()
+            }
+        };
+
+        Pcm {
+            sample_rate,
+            channels,
+            bits_per_sample,
+            samples,
+        }
+    }
+
+    /// Reads the samples of an uncompressed AIFF or AIFF-C file.
+    pub fn from_aiff(data: &[u8]) -> Result<Self> {
+        let mut r = Reader::new(data);
+
+        let form = r.read_be_u32()?;
+        let _form_size = r.read_be_u32()?;
+        let form_type = r.read_be_u32()?;
+
+        if form != u32::from_be_bytes(*b"FORM")
+            || (form_type != u32::from_be_bytes(*b"AIFF")
+                && form_type != u32::from_be_bytes(*b"AIFC"))
+        {
+            return Err(Error::new(ErrorKind::InvalidData, "Not an AIFF file"));
+        }
+
+        let mut format = None;
+        let mut sound_data = None;
+
+        while let Ok(chunk_type) = r.read_be_u32() {
+            let chunk_size = r.read_be_u32()? as usize;
+            let chunk_start = r.stream_position()? as usize;
+
+            match &chunk_type.to_be_bytes() {
+                b"COMM" => {
+                    let channels = r.read_be_u16()?;
+                    let _frame_count = r.read_be_u32()?;
+                    let bits_per_sample = r.read_be_u16()?;
+                    let sample_rate = r.read_be_f80()? as u32;
+
+                    let compression = if form_type == u32::from_be_bytes(*b"AIFC") {
+                        r.read_be_u32()?.to_be_bytes()
+                    } else {
+                        *b"NONE"
+                    };
+
+                    format = Some((channels, bits_per_sample, sample_rate, compression));
+                }
+                b"SSND" => {
+                    let offset = r.read_be_u32()? as usize;
+                    let _block_size = r.read_be_u32()?;
+                    let start = chunk_start + 8 + offset;
+                    let end = (chunk_start + chunk_size).min(data.len());
+                    sound_data = data.get(start..end);
+                }
+                _ => {}
+            }
+
+            r.seek(SeekFrom::Start(
+                (chunk_start + chunk_size.next_multiple_of(2)) as u64,
+            ))?;
+        }
+
+        let Some((channels, bits_per_sample, sample_rate, compression)) = format else {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "AIFF file has no COMM chunk",
+            ));
+        };
+
+        let sound_data = sound_data.unwrap_or_default();
+
+        match &compression {
+            b"NONE" | b"twos" => Ok(Pcm::from_be_samples(
+                sample_rate,
+                channels,
+                bits_per_sample,
+                sound_data,
+            )),
+            b"sowt" => Ok(Pcm {
+                sample_rate,
+                channels,
+                bits_per_sample,
+                samples: sound_data.to_vec(),
+            }),
+            _ => Err(Error::new(
+                ErrorKind::Unsupported,
+                format!(
+                    "Unsupported AIFF-C compression '{}'",
+                    String::from_utf8_lossy(&compression)
+                ),
+            )),
+        }
+    }
+
+    pub fn write_wav<W: Write>(&self, w: &mut W) -> Result<()> {
+        let block_align = self.channels * self.bits_per_sample.div_ceil(8);
+        let byte_rate = self.sample_rate * block_align as u32;
+        let data_len = self.samples.len() as u32;
+
+        w.write_all(b"RIFF")?;
+        w.write_all(&(36 + data_len.next_multiple_of(2)).to_le_bytes())?;
+        w.write_all(b"WAVE")?;
+
+        w.write_all(b"fmt ")?;
+        w.write_all(&16u32.to_le_bytes())?;
+        w.write_all(&1u16.to_le_bytes())?;
+        w.write_all(&self.channels.to_le_bytes())?;
+        w.write_all(&self.sample_rate.to_le_bytes())?;
+        w.write_all(&byte_rate.to_le_bytes())?;
+        w.write_all(&block_align.to_le_bytes())?;
+        w.write_all(&self.bits_per_sample.to_le_bytes())?;
+
+        w.write_all(b"data")?;
+        w.write_all(&data_len.to_le_bytes())?;
+        w.write_all(&self.samples)?;
+        if !data_len.is_multiple_of(2) {
+            w.write_all(&[0])?;
+        }
+
+        Ok(())
+    }
+
+    pub fn to_wav(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(44 + self.samples.len());
+        self.write_wav(&mut buf)
+            .expect("Writing to a Vec cannot fail");
+        buf
+    }
+}
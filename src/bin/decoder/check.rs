@@ -0,0 +1,38 @@
+use std::path::Path;
+
+use anyhow::{Result, bail};
+use director_decoder::riff::{Issue, IssueKind, Movie, OpenOptions};
+
+/// Checks the movie at `path` for damage, printing one issue per line as its
+/// kind, chunk id and message separated by tabs, or a JSON report. Exits with
+/// status 1 if there are any issues, so that batches of files can be sorted.
+pub fn check(path: &Path, options: OpenOptions, json: bool) -> Result<()> {
+    let options = OpenOptions {
+        headers_only: true,
+        ..options
+    };
+
+    let issues = match Movie::open_with(path, options) {
+        Ok(movie) => match movie.riff() {
+            Some(riff) => riff.validate(),
+            None => bail!("Checking Director 3 movies is not supported"),
+        },
+        Err(err) => vec![Issue::new(IssueKind::Unreadable, None, err.to_string())],
+    };
+
+    #[cfg(feature = "serde")]
+    if json {
+        crate::json::write_check_report(path, &issues)?;
+    }
+    if !json {
+        for issue in &issues {
+            println!("{issue}");
+        }
+    }
+
+    if !issues.is_empty() {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
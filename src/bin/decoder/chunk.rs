@@ -0,0 +1,162 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result, bail};
+use director_decoder::{
+    reader::Reader,
+    riff::{
+        RiffFile,
+        chunks::{self, EntryState, MemoryMapEntry},
+        tags::{self, Tag},
+    },
+};
+use thousands::Separable;
+
+/// Dumps the chunk `chunk` names, a memory map id or a tag for every chunk
+/// with that tag. Without `out` each chunk is shown as an annotated hex dump.
+/// With it, the contents of a chunk id are written to the file `out`, and
+/// those of a tag to a directory for the tag in `out`.
+pub fn dump(riff: &RiffFile, movie: &Path, chunk: &str, out: Option<&Path>) -> Result<()> {
+    if let Ok(id) = chunk.parse::<u32>() {
+        let entry = riff
+            .mmap()
+            .entry_by_index(id)
+            .with_context(|| format!("There is no chunk {id} in the memory map"))?;
+
+        return match out {
+            Some(out) => write_chunk(riff, entry, out),
+            None => show_chunk(riff, entry),
+        };
+    }
+
+    let tag =
+        parse_tag(chunk).with_context(|| format!("{chunk} is neither a chunk id nor a tag"))?;
+    let entries: Vec<_> = riff
+        .mmap()
+        .entries()
+        .iter()
+        .filter(|entry| entry.tag() == tag)
+        .collect();
+    if entries.is_empty() {
+        bail!("There are no '{tag}' chunks in the movie");
+    }
+
+    dump_entries(riff, movie, &entries, out)
+}
+
+/// Dumps every chunk whose tag none of the `Chunk` types parse, grouped by
+/// tag. With `out`, each tag gets a directory in it, which the chunks of
+/// other movies can be dumped to as well to compare them.
+pub fn dump_unknown(riff: &RiffFile, movie: &Path, out: Option<&Path>) -> Result<()> {
+    let mut entries: Vec<_> = riff
+        .mmap()
+        .entries()
+        .iter()
+        .filter(|entry| {
+            entry.state() == EntryState::Live
+                && !matches!(entry.tag(), tags::TAG_RIFX | tags::TAG_XFIR)
+                && !chunks::is_parsed_tag(entry.tag())
+        })
+        .collect();
+    entries.sort_by_key(|entry| (entry.tag(), entry.id()));
+
+    if entries.is_empty() {
+        println!("Every chunk in the movie has a known tag");
+        return Ok(());
+    }
+
+    dump_entries(riff, movie, &entries, out)
+}
+
+fn dump_entries(
+    riff: &RiffFile,
+    movie: &Path,
+    entries: &[&MemoryMapEntry],
+    out: Option<&Path>,
+) -> Result<()> {
+    let Some(out) = out else {
+        for group in entries.chunk_by(|a, b| a.tag() == b.tag()) {
+            println!("{:?} chunks: {}", group[0].tag(), group.len());
+            println!();
+            for entry in group {
+                show_chunk(riff, entry)?;
+            }
+        }
+        return Ok(());
+    };
+
+    let stem = movie
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+
+    for entry in entries {
+        let dir = out.join(tag_dir_name(entry.tag()));
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+        write_chunk(riff, entry, &dir.join(format!("{stem}_{}.bin", entry.id())))?;
+    }
+
+    Ok(())
+}
+
+fn write_chunk(riff: &RiffFile, entry: &MemoryMapEntry, path: &Path) -> Result<()> {
+    let data = read_chunk(riff, entry)?;
+    fs::write(path, &data).with_context(|| format!("Failed to write {}", path.display()))?;
+
+    println!("{:>10} bytes  {}", data.len(), path.display());
+
+    Ok(())
+}
+
+/// Shows where chunk `entry` is, what its header says, which can disagree
+/// with the memory map in damaged movies, and a hex dump of its contents.
+fn show_chunk(riff: &RiffFile, entry: &MemoryMapEntry) -> Result<()> {
+    println!("Chunk {} {:?}", entry.id(), entry.tag());
+    println!(
+        "  position:   0x{:x}, contents at 0x{:x}",
+        entry.pos(),
+        entry.pos() as u64 + 8
+    );
+    println!("  length:     {} bytes", entry.len().separate_with_commas());
+    match riff.chunk_header(entry.id()) {
+        Ok((tag, size)) => println!(
+            "  header:     {tag:?}, {} bytes",
+            size.separate_with_commas()
+        ),
+        Err(err) => println!("  header:     {err}"),
+    }
+    println!("  byte order: {:?}", riff.byte_order());
+    println!("  parsed:     {}", chunks::is_parsed_tag(entry.tag()));
+
+    Reader::new(&read_chunk(riff, entry)?).hex_dump()?;
+    println!();
+
+    Ok(())
+}
+
+fn read_chunk(riff: &RiffFile, entry: &MemoryMapEntry) -> Result<Vec<u8>> {
+    riff.read_chunk_data(entry.id())
+        .with_context(|| format!("Failed to read chunk {} {:?}", entry.id(), entry.tag()))
+}
+
+/// Reads a tag as it is displayed, with spaces or dots for the missing
+/// characters of tags like `snd `.
+fn parse_tag(text: &str) -> Option<Tag> {
+    if !text.is_ascii() || text.is_empty() || text.len() > 4 {
+        return None;
+    }
+
+    let mut bytes = [b' '; 4];
+    for (byte, c) in bytes.iter_mut().zip(text.bytes()) {
+        *byte = if c == b'.' { b' ' } else { c };
+    }
+
+    Some(Tag(i32::from_be_bytes(bytes)))
+}
+
+fn tag_dir_name(tag: Tag) -> String {
+    tag.to_string()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
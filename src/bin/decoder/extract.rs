@@ -0,0 +1,372 @@
+use std::{
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use director_decoder::{
+    audio::Pcm,
+    gfx,
+    riff::{
+        LinkedFileResolver, RiffFile,
+        cast_members::{
+            Bitmap, CastMember, CastMemberId, CastMemberKind, LinkedMedia, LinkedMediaFormat,
+            Palette, Sound, Text,
+        },
+        lingo,
+    },
+};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Manifest {
+    cast_members: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize)]
+struct ManifestEntry {
+    cast: u16,
+    id: i16,
+    name: Option<String>,
+    #[serde(rename = "type")]
+    cast_member_type: String,
+    reg_point: Option<gfx::Pos>,
+    files: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Writes every member of the cast to `out_dir`, along with a `manifest.json`
+/// describing them.
+pub fn extract(riff: &mut RiffFile, out_dir: &Path, linked_files: Option<&Path>) -> Result<()> {
+    fs::create_dir_all(out_dir)
+        .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+
+    if let Some(linked_files) = linked_files {
+        riff.set_linked_file_resolver(LinkedFileResolver::new(linked_files));
+    }
+
+    // Compiled scripts are only needed for members without source text.
+    if riff.read_lingo_context().is_ok() {
+        riff.read_lingo_names()?;
+    }
+
+    let ids: Vec<_> = riff.cast_table().cast_member_ids().collect();
+    let mut manifest = Vec::with_capacity(ids.len());
+
+    for id in ids {
+        let cast_member = match riff.load_cast_member(id) {
+            Ok(cast_member) => cast_member,
+            Err(err) => {
+                println!("Cast member {}: {err}", id.id());
+                manifest.push(ManifestEntry {
+                    cast: cast_lib(id),
+                    id: id.id(),
+                    name: None,
+                    cast_member_type: String::new(),
+                    reg_point: None,
+                    files: Vec::new(),
+                    error: Some(err.to_string()),
+                });
+                continue;
+            }
+        };
+
+        let mut extractor = Extractor {
+            riff,
+            out_dir,
+            id,
+            cast_member: &cast_member,
+            files: Vec::new(),
+        };
+
+        let error = extractor.extract().err().map(|err| {
+            println!("Cast member {}: {err:#}", id.id());
+            format!("{err:#}")
+        });
+        let files = extractor.files;
+
+        let reg_point = cast_member.as_bitmap().map(|bitmap| bitmap.info.reg);
+
+        manifest.push(ManifestEntry {
+            cast: cast_lib(id),
+            id: id.id(),
+            name: cast_member.name().map(str::to_owned),
+            cast_member_type: cast_member.cast_member_type().to_string(),
+            reg_point,
+            files,
+            error,
+        });
+    }
+
+    let manifest = Manifest {
+        cast_members: manifest,
+    };
+    let mut json = serde_json::to_string_pretty(&manifest)?;
+    json.push('\n');
+    fs::write(out_dir.join("manifest.json"), json)?;
+
+    println!(
+        "Extracted {} cast members to {}",
+        manifest.cast_members.len(),
+        out_dir.display()
+    );
+
+    Ok(())
+}
+
+/// The cast library of `id`. Members without one are in the movie's own
+/// cast, which is library 1.
+fn cast_lib(id: CastMemberId) -> u16 {
+    id.cast().unwrap_or(1)
+}
+
+struct Extractor<'a, 'r> {
+    riff: &'a RiffFile<'r>,
+    out_dir: &'a Path,
+    id: CastMemberId,
+    cast_member: &'a CastMember,
+    files: Vec<String>,
+}
+
+impl Extractor<'_, '_> {
+    fn extract(&mut self) -> Result<()> {
+        match &self.cast_member.kind {
+            CastMemberKind::Bitmap(bitmap) => self.extract_bitmap(bitmap)?,
+            CastMemberKind::Palette(palette) => self.extract_palette(palette)?,
+            CastMemberKind::Text(text) | CastMemberKind::Button(text) => self.extract_text(text)?,
+            CastMemberKind::Sound(sound) => self.extract_sound(sound)?,
+            CastMemberKind::DigitalVideo(video) => {
+                if let Some(linked) = &video.linked {
+                    self.copy_linked(linked)?;
+                }
+            }
+            _ => {}
+        }
+
+        self.extract_script()
+    }
+
+    /// The file name for one of this member's files, e.g. `01-0012_Logo.png`
+    /// for member 12 of cast library 1.
+    fn file_name(&self, extension: &str) -> String {
+        let mut file_name = format!("{:02}-{:04}", cast_lib(self.id), self.id.id());
+
+        if let Some(name) = self.cast_member.name().filter(|name| !name.is_empty()) {
+            file_name.push('_');
+            file_name.extend(name.chars().map(|c| match c {
+                c if c.is_alphanumeric() || c == '-' || c == '.' => c,
+                _ => '_',
+            }));
+        }
+
+        file_name.push('.');
+        file_name.push_str(extension);
+        file_name
+    }
+
+    fn write(&mut self, extension: &str, contents: impl AsRef<[u8]>) -> Result<()> {
+        let path = self.create(extension);
+        fs::write(&path, contents).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    fn create(&mut self, extension: &str) -> PathBuf {
+        let file_name = self.file_name(extension);
+        let path = self.out_dir.join(&file_name);
+        self.files.push(file_name);
+        path
+    }
+
+    fn copy_linked(&mut self, linked: &LinkedMedia) -> Result<()> {
+        let extension = linked
+            .path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("bin")
+            .to_ascii_lowercase();
+
+        let path = self.create(&extension);
+        fs::copy(&linked.path, &path)
+            .with_context(|| format!("Failed to copy {}", linked.path.display()))?;
+
+        Ok(())
+    }
+
+    fn extract_bitmap(&mut self, bitmap: &Bitmap) -> Result<()> {
+        if let Some(linked) = &bitmap.linked {
+            return self.copy_linked(linked);
+        }
+
+        let Some(image) = bitmap.image() else {
+            if bitmap.data.is_some() {
+                bail!(
+                    "The data of the {}-bit bitmap cannot be decoded",
+                    bitmap.info.bit_depth
+                );
+            }
+            return Ok(());
+        };
+
+        let image = crop(&image, bitmap.info.width() as usize);
+        let palette = self.bitmap_palette(bitmap);
+
+        let path = self.create("png");
+        image.save_to_png(&palette, &path)?;
+
+        Ok(())
+    }
+
+    /// Looks up the palette a bitmap was authored with. Built-in palettes
+    /// other than the Mac system palette are approximated by it.
+    fn bitmap_palette(&self, bitmap: &Bitmap) -> gfx::Palette {
+        match self.riff.load_bitmap_clut(&bitmap.info) {
+            Some(clut) => gfx::Palette::from_colors(&clut.colors),
+            None => gfx::Palette::mac_system(),
+        }
+    }
+
+    fn extract_palette(&mut self, palette: &Palette) -> Result<()> {
+        let Some(clut) = &palette.clut else {
+            return Ok(());
+        };
+
+        let colors: Vec<gfx::Rgb888> = clut.colors.iter().map(gfx::Rgb888::from).collect();
+
+        // Adobe Color Table: 256 RGB triplets, the number of colors in use
+        // and the transparent color index (none).
+        let mut act = vec![0; 256 * 3];
+        for (i, color) in colors.iter().take(256).enumerate() {
+            act[i * 3..i * 3 + 3].copy_from_slice(&[color.r, color.g, color.b]);
+        }
+        act.extend_from_slice(&(colors.len() as u16).to_be_bytes());
+        act.extend_from_slice(&0xffffu16.to_be_bytes());
+        self.write("act", act)?;
+
+        let mut gpl = String::from("GIMP Palette\n");
+        let _ = writeln!(gpl, "Name: {}", self.cast_member.name().unwrap_or_default());
+        gpl.push_str("Columns: 16\n#\n");
+        for (i, color) in colors.iter().enumerate() {
+            let _ = writeln!(gpl, "{:3} {:3} {:3}\tIndex {i}", color.r, color.g, color.b);
+        }
+        self.write("gpl", gpl)
+    }
+
+    fn extract_text(&mut self, text: &Text) -> Result<()> {
+        let Some(styled_text) = &text.styled_text else {
+            return Ok(());
+        };
+
+        let plain = styled_text.text();
+        self.write("txt", plain.replace('\r', "\n"))?;
+
+        let chars: Vec<char> = plain.chars().collect();
+        let runs = styled_text.style_runs();
+
+        let mut html = String::from("<!DOCTYPE html>\n<html>\n<body>\n<p>");
+        for (i, run) in runs.iter().enumerate() {
+            let start = (run.start_offset.max(0) as usize).min(chars.len());
+            let end = runs
+                .get(i + 1)
+                .map(|next| (next.start_offset.max(0) as usize).min(chars.len()))
+                .unwrap_or(chars.len())
+                .max(start);
+
+            let color = gfx::Rgb888::from(run.color);
+            let mut style = format!(
+                "font-size: {}pt; color: #{:02x}{:02x}{:02x};",
+                run.font_size, color.r, color.g, color.b
+            );
+            if run.is_bold() {
+                style.push_str(" font-weight: bold;");
+            }
+            if run.is_italic() {
+                style.push_str(" font-style: italic;");
+            }
+            if run.is_underline() {
+                style.push_str(" text-decoration: underline;");
+            }
+
+            let _ = write!(html, "<span style=\"{style}\">");
+            for &c in &chars[start..end] {
+                match c {
+                    '\r' => html.push_str("<br>\n"),
+                    '<' => html.push_str("&lt;"),
+                    '>' => html.push_str("&gt;"),
+                    '&' => html.push_str("&amp;"),
+                    c => html.push(c),
+                }
+            }
+            html.push_str("</span>");
+        }
+        html.push_str("</p>\n</body>\n</html>\n");
+
+        self.write("html", html)
+    }
+
+    fn extract_sound(&mut self, sound: &Sound) -> Result<()> {
+        if let Some(linked) = &sound.linked {
+            return match linked.format {
+                LinkedMediaFormat::Aiff => match Pcm::from_aiff(&linked.read()?) {
+                    Ok(pcm) => self.write("wav", pcm.to_wav()),
+                    Err(_) => self.copy_linked(linked),
+                },
+                _ => self.copy_linked(linked),
+            };
+        }
+
+        let Some(data) = &sound.data else {
+            return Ok(());
+        };
+
+        match data.pcm() {
+            Ok(pcm) => self.write("wav", pcm.to_wav()),
+            Err(err) => {
+                // Keep the resource so nothing is lost.
+                self.write("snd", data.buf())?;
+                Err(err.into())
+            }
+        }
+    }
+
+    fn extract_script(&mut self) -> Result<()> {
+        let info = &self.cast_member.info;
+
+        if let Some(script_text) = info.script_text.as_ref().filter(|s| !s.is_empty()) {
+            return self.write("ls", script_text.replace('\r', "\n"));
+        }
+
+        if info.script_id == 0 {
+            return Ok(());
+        }
+
+        let Some(script) = self.riff.load_lingo_script(info.script_id)? else {
+            return Ok(());
+        };
+
+        let listing = lingo::disassemble(&script, self.riff.lingo_names(), self.riff.version());
+        self.write(
+            "ls",
+            format!(
+                "-- Disassembled from compiled script {}\n{listing}",
+                info.script_id
+            ),
+        )
+    }
+}
+
+/// Drops the row padding 8-bit bitmaps are decoded with.
+fn crop(image: &gfx::IndexedImage, width: usize) -> gfx::IndexedImage {
+    if width == 0 || width >= image.width() {
+        return gfx::IndexedImage::new(image.width(), image.height(), image.get_data().to_vec());
+    }
+
+    let data = image
+        .get_data()
+        .chunks_exact(image.width())
+        .flat_map(|row| &row[..width])
+        .copied()
+        .collect();
+
+    gfx::IndexedImage::new(width, image.height(), data)
+}
@@ -0,0 +1,44 @@
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use director_decoder::{
+    gfx,
+    riff::{MovieWriter, RiffFile, cast_members::CastMemberId},
+};
+
+/// Replaces the image of bitmap cast member `id` with the PNG at `png` and
+/// writes the movie to `out`. Returns the size of the written file.
+pub fn import_png(
+    riff: &RiffFile,
+    id: CastMemberId,
+    png: &Path,
+    bit_depth: Option<u8>,
+    out: &Path,
+) -> Result<usize> {
+    let mut cast_member = riff
+        .load_cast_member(id)
+        .with_context(|| format!("Failed to load cast member {}", id.id()))?;
+
+    let Some(bitmap) = cast_member.as_bitmap_mut() else {
+        bail!("Cast member {} is not a bitmap", id.id());
+    };
+
+    let image = gfx::ImageBuffer::load_png(&png.to_string_lossy())
+        .with_context(|| format!("Failed to read {}", png.display()))?;
+
+    let (palette, color_count) = match riff.load_bitmap_clut(&bitmap.info) {
+        Some(clut) => (gfx::Palette::from_colors(&clut.colors), clut.colors.len()),
+        None => (gfx::Palette::mac_system(), 256),
+    };
+
+    let bit_depth = bit_depth.unwrap_or(bitmap.info.bit_depth);
+    bitmap.import(&image, &palette, color_count, bit_depth)?;
+
+    let mut writer = MovieWriter::new(riff)?;
+    writer.replace_cast_member(id, &cast_member)?;
+
+    let data = writer.write()?;
+    std::fs::write(out, &data).with_context(|| format!("Failed to write {}", out.display()))?;
+
+    Ok(data.len())
+}
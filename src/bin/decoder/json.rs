@@ -0,0 +1,126 @@
+use std::{io::Write, path::Path};
+
+use anyhow::Result;
+use director_decoder::riff::{
+    Issue, RiffFile, Version,
+    cast_members::{CastMember, CastMemberId},
+    chunks::{
+        CastTable, Config, FileInfo, FrameLabels, InitialMap, KeyTable, LingoContext, LingoNames,
+        LingoScript, MemoryMap, Score,
+    },
+    tags::Tag,
+};
+use serde::Serialize;
+
+use crate::scan::Summary;
+
+#[derive(Serialize)]
+struct Movie<'a> {
+    type_tag: Tag,
+    version: Version,
+    imap: &'a InitialMap,
+    mmap: &'a MemoryMap,
+    key_table: &'a KeyTable,
+    config: &'a Config,
+    file_info: Option<&'a FileInfo>,
+    cast_table: &'a CastTable,
+    cast_members: Vec<CastMemberEntry>,
+    frame_labels: &'a FrameLabels,
+    score: &'a Score,
+    lingo_context: Option<&'a LingoContext>,
+    lingo_names: Option<&'a LingoNames>,
+    lingo_scripts: Vec<LingoScript>,
+}
+
+#[derive(Serialize)]
+struct CastMemberEntry {
+    id: CastMemberId,
+    #[serde(flatten)]
+    result: CastMemberResult,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum CastMemberResult {
+    CastMember(Box<CastMember>),
+    Error(String),
+}
+
+/// Writes the whole parsed movie to stdout as a single JSON document. Cast
+/// members that fail to load are included with their error.
+pub fn write_json(riff: &mut RiffFile) -> Result<()> {
+    if riff.read_lingo_context().is_ok() {
+        riff.read_lingo_names()?;
+    }
+
+    let cast_members = riff
+        .cast_table()
+        .cast_member_ids()
+        .map(|id| CastMemberEntry {
+            id,
+            result: match riff.load_cast_member(id) {
+                Ok(cast_member) => CastMemberResult::CastMember(Box::new(cast_member)),
+                Err(err) => CastMemberResult::Error(err.to_string()),
+            },
+        })
+        .collect();
+
+    let script_count = riff
+        .lingo_context()
+        .map_or(0, |lingo_context| lingo_context.entries().len());
+    let mut lingo_scripts = Vec::new();
+    for script_number in 1..=script_count as u32 {
+        if let Some(script) = riff.load_lingo_script(script_number)? {
+            lingo_scripts.push(script);
+        }
+    }
+
+    let movie = Movie {
+        type_tag: riff.type_tag(),
+        version: riff.version(),
+        imap: riff.imap(),
+        mmap: riff.mmap(),
+        key_table: riff.key_table(),
+        config: riff.config(),
+        file_info: riff.file_info(),
+        cast_table: riff.cast_table(),
+        cast_members,
+        frame_labels: riff.frame_labels(),
+        score: riff.score(),
+        lingo_context: riff.lingo_context(),
+        lingo_names: riff.lingo_names(),
+        lingo_scripts,
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    serde_json::to_writer_pretty(&mut stdout, &movie)?;
+    writeln!(stdout)?;
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct CheckReport<'a> {
+    file: &'a Path,
+    issues: &'a [Issue],
+}
+
+/// Writes the issues `decoder check` found in the movie at `path` to stdout.
+pub fn write_check_report(path: &Path, issues: &[Issue]) -> Result<()> {
+    let report = CheckReport { file: path, issues };
+
+    let mut stdout = std::io::stdout().lock();
+    serde_json::to_writer_pretty(&mut stdout, &report)?;
+    writeln!(stdout)?;
+
+    Ok(())
+}
+
+/// Writes the summaries of `decoder scan` to stdout as a JSON array.
+pub fn write_scan_report(summaries: &[Summary]) -> Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    serde_json::to_writer_pretty(&mut stdout, summaries)?;
+    writeln!(stdout)?;
+
+    Ok(())
+}
@@ -1,14 +1,27 @@
-use std::{
-    fs::File,
-    io::{Read, Seek},
-};
+mod check;
+mod chunk;
+#[cfg(feature = "serde")]
+mod extract;
+mod import;
+#[cfg(feature = "serde")]
+mod json;
+mod scan;
+#[cfg(feature = "serde")]
+mod stats;
+mod translate;
+mod tree;
+mod unpack;
+
+use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
 use anyhow::Result;
 use director_decoder::{
-    reader::Reader,
-    riff::{Projector, RiffFile, tags},
+    reader::TextEncoding,
+    riff::{
+        Movie, MovieContent, MovieWriter, OpenOptions, ResourceMovie, cast_members::CastMemberId,
+    },
 };
 
 #[derive(Debug, Parser)]
@@ -27,72 +40,228 @@ struct Cli {
     show_frame_labels: bool,
     #[clap(long)]
     show_score: bool,
+    /// List the resources in the file's Mac resource fork
+    #[clap(long)]
+    show_resources: bool,
+    /// The encoding of the movie's text: latin1, mac-roman, windows-1252 or
+    /// shift-jis. Detected from the movie by default
+    #[clap(long)]
+    encoding: Option<TextEncoding>,
+    /// Read chunks that are truncated or disagree with the memory map as far
+    /// as they go, rather than fail
+    #[clap(long)]
+    tolerant: bool,
+    /// Print the parsed movie as a single JSON document instead
+    #[cfg(feature = "serde")]
+    #[clap(long)]
+    json: bool,
     filename: String,
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+impl Cli {
+    fn json(&self) -> bool {
+        #[cfg(feature = "serde")]
+        return self.json;
+        #[cfg(not(feature = "serde"))]
+        return false;
+    }
+
+    /// The JSON document is all that may go to stdout, so the tables of the
+    /// --show-* options and the output of most subcommands cannot go with it.
+    fn check_json(&self) -> Result<()> {
+        if !self.json() {
+            return Ok(());
+        }
+
+        if self.show_mmap
+            || self.show_key_table
+            || self.show_config
+            || self.show_file_info
+            || self.show_cast_table
+            || self.show_frame_labels
+            || self.show_score
+            || self.show_resources
+        {
+            anyhow::bail!("--json cannot be combined with the --show-* options");
+        }
+
+        match &self.command {
+            None | Some(Command::Check | Command::Scan { .. }) => Ok(()),
+            Some(_) => anyhow::bail!("--json only works with the check and scan subcommands"),
+        }
+    }
+}
+
+/// Progress output, which is left out when stdout is a JSON document.
+macro_rules! status {
+    ($cli:expr) => {
+        if !$cli.json() {
+            println!();
+        }
+    };
+    ($cli:expr, $($arg:tt)*) => {
+        if !$cli.json() {
+            println!($($arg)*);
+        }
+    };
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Write every cast member to files in OUT_DIR, along with a manifest.json
+    #[cfg(feature = "serde")]
+    Extract {
+        out_dir: PathBuf,
+        /// Where to look for the media of linked cast members, defaults to the
+        /// directory of the movie
+        #[clap(long)]
+        linked_files: Option<PathBuf>,
+    },
+    /// Write the movie back out to OUT, rebuilding its memory map
+    Rewrite { out: PathBuf },
+    /// Write every movie, cast and Xtra embedded in a projector to OUT_DIR
+    Unpack { out_dir: PathBuf },
+    /// Replace the image of bitmap cast member MEMBER with PNG, remapped to
+    /// the member's palette, and write the movie to OUT
+    ImportPng {
+        member: i16,
+        png: PathBuf,
+        out: PathBuf,
+        /// Bits per pixel of the new image (1, 2, 4 or 8), defaults to that of
+        /// the member
+        #[clap(long)]
+        bit_depth: Option<u8>,
+    },
+    /// Check the movie's chunks, memory map lists, key table, cast members
+    /// and score for damage, printing one issue per line, or a JSON report
+    /// with --json. Exits with status 1 if anything is wrong
+    Check,
+    /// Show an annotated hex dump of chunk CHUNK, or write its contents to a
+    /// file with --out
+    Chunk {
+        /// A memory map id, or a tag such as VWtc for every chunk with that tag
+        #[clap(required_unless_present = "all_unknown")]
+        chunk: Option<String>,
+        /// The file to write the contents of a chunk id to, or the directory
+        /// to write a directory per tag to
+        #[clap(long)]
+        out: Option<PathBuf>,
+        /// Dump every chunk with a tag the decoder does not parse, grouped by
+        /// tag
+        #[clap(long, conflicts_with = "chunk")]
+        all_unknown: bool,
+    },
+    /// Read every movie in the directory tree FILENAME in parallel and print
+    /// the version, stage size, frame count, cast member counts by type and
+    /// errors of each, or a JSON array with --json. Other files are skipped
+    Scan {
+        /// How many movies to read at once, defaults to one per CPU
+        #[clap(long)]
+        threads: Option<usize>,
+    },
+    /// Read every movie in the directory tree FILENAME in parallel and write
+    /// counts of their chunk tags by version, cast member types, bitmap
+    /// depths, sprite inks, tempos, Lingo opcodes and errors to OUT_DIR, as
+    /// stats.json and a table in stats.txt
+    #[cfg(feature = "serde")]
+    Stats {
+        out_dir: PathBuf,
+        /// How many movies to read at once, defaults to one per CPU
+        #[clap(long)]
+        threads: Option<usize>,
+    },
+    /// Print the chunks of the movie as the tree of owners the key table, cast
+    /// table and Lingo context make, with their tags, sizes and cast member
+    /// names
+    Tree,
+    /// Replace the text of text and button members with their translations
+    /// from TABLE, a PO file or a CSV file of source text and translation
+    /// columns, and write the movie to OUT
+    Translate { table: PathBuf, out: PathBuf },
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    cli.check_json()?;
 
     let filename = std::path::Path::new(&cli.filename);
 
-    let mut buf = Vec::new();
-    let mut file = File::open(filename)?;
-    file.read_to_end(&mut buf)?;
-    let mut reader = Reader::new(&buf);
-
-    let mut riff = if filename
-        .extension()
-        .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"))
-    {
-        let mut projector = Projector::read(reader.clone())?;
-
-        projector.display_header();
+    let options = OpenOptions {
+        encoding: cli.encoding,
+        tolerant: cli.tolerant,
+        ..Default::default()
+    };
 
-        let mut riff = projector.read_initial_riff()?;
+    if let Some(Command::Check) = &cli.command {
+        return check::check(filename, options, cli.json());
+    }
+    if let Some(Command::Scan { threads }) = &cli.command {
+        return scan::scan(filename, options, *threads, cli.json());
+    }
+    #[cfg(feature = "serde")]
+    if let Some(Command::Stats { out_dir, threads }) = &cli.command {
+        return stats::stats(filename, options, *threads, out_dir);
+    }
 
-        println!("initial RIFF:   {}", riff.type_tag());
-        println!();
+    let mut movie = Movie::open_with(filename, options)?;
 
-        if cli.show_mmap {
-            riff.mmap().display();
-        }
+    if let Some(mac_file) = movie.mac_file() {
+        status!(
+            cli,
+            "Mac file:       {} ({}/{})",
+            mac_file.name,
+            mac_file.file_type,
+            mac_file.creator
+        );
+    }
+    if cli.show_resources
+        && let Some(resource_fork) = movie.resource_fork()
+    {
+        resource_fork.display();
+    }
 
-        if riff.type_tag() == tags::TAG_APPL {
-            if let Some(file) = riff.mmap().first_entry_with_tag(tags::TAG_File) {
-                reader.seek(std::io::SeekFrom::Start(file.pos() as u64))?;
-                riff = RiffFile::new(reader)?;
-            }
+    if let Some(projector) = movie.projector_mut() {
+        if !cli.json() {
+            projector.display_header();
         }
 
-        println!("key_table");
-        riff.read_key_table()?;
-        println!("config");
-        if let Err(err) = riff.read_config() {
-            println!("{err}");
+        if let Some(Command::Unpack { out_dir }) = &cli.command {
+            return unpack::unpack(projector, out_dir);
         }
 
-        println!(
-            "Contained movie RIFF: {} {}\n",
-            riff.type_tag(),
-            riff.version()
-        );
+        let initial_riff = projector.read_initial_riff()?;
 
-        riff
-    } else {
-        let mut riff = RiffFile::new(reader)?;
+        status!(cli, "initial RIFF:   {}", initial_riff.type_tag());
+        status!(cli);
 
-        println!("key_table");
-        riff.read_key_table()?;
-        println!("config");
-        if let Err(err) = riff.read_config() {
-            println!("{err}");
+        if cli.show_mmap {
+            initial_riff.mmap().display();
         }
+    } else if let Some(Command::Unpack { .. }) = &cli.command {
+        anyhow::bail!("{} is not a projector", filename.display());
+    }
 
-        println!("Movie RIFF: {} {}\n", riff.type_tag(), riff.version());
+    for warning in movie.warnings() {
+        status!(cli, "{warning}");
+    }
 
-        riff
+    // Only the serde output and subcommands change the movie.
+    #[cfg_attr(not(feature = "serde"), allow(unused_mut))]
+    let mut riff = match movie.into_content() {
+        MovieContent::Riff(riff) => riff,
+        MovieContent::Resources(movie) => return show_resource_movie(&cli, &movie),
     };
 
+    status!(
+        cli,
+        "Movie RIFF: {} {} ({})\n",
+        riff.type_tag(),
+        riff.version(),
+        riff.encoding()
+    );
+
     if cli.show_mmap {
         riff.mmap().display();
     }
@@ -102,36 +271,137 @@ fn main() -> Result<()> {
     if cli.show_config {
         riff.config().display();
     }
-
-    println!("file_info");
-    riff.read_file_info()?;
-    if cli.show_file_info {
-        riff.file_info().unwrap().display();
+    if cli.show_file_info
+        && let Some(file_info) = riff.file_info()
+    {
+        file_info.display();
     }
-
-    println!("cast_table");
-    riff.read_cast_table()?;
     if cli.show_cast_table {
         riff.cast_table().display();
     }
+    if cli.show_frame_labels && !riff.frame_labels().is_empty() {
+        riff.frame_labels().display();
+    }
+    if cli.show_score {
+        for frame in riff.score().frames() {
+            frame.display();
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    if cli.json {
+        json::write_json(&mut riff)?;
+    }
 
-    println!("frame_labels");
-    match riff.read_frame_labels() {
-        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
-            println!("No frame labels found");
-            Ok(())
+    match &cli.command {
+        #[cfg(feature = "serde")]
+        Some(Command::Extract {
+            out_dir,
+            linked_files,
+        }) => {
+            let linked_files = linked_files
+                .as_deref()
+                .or_else(|| filename.parent())
+                .filter(|dir| !dir.as_os_str().is_empty());
+
+            extract::extract(&mut riff, out_dir, linked_files)?;
         }
-        v => v,
-    }?;
+        Some(Command::Rewrite { out }) => {
+            let data = MovieWriter::new(&riff)?.write()?;
+            std::fs::write(out, &data)?;
 
-    if cli.show_frame_labels && !riff.frame_labels().is_empty() {
-        riff.frame_labels().display();
+            status!(cli, "Wrote {} bytes to {}", data.len(), out.display());
+        }
+        Some(Command::ImportPng {
+            member,
+            png,
+            out,
+            bit_depth,
+        }) => {
+            let len = import::import_png(&riff, CastMemberId::new(*member), png, *bit_depth, out)?;
+
+            status!(cli, "Wrote {len} bytes to {}", out.display());
+        }
+        // Clap makes sure there is a chunk unless --all-unknown is given.
+        Some(Command::Chunk { chunk, out, .. }) => match chunk {
+            Some(chunk) => chunk::dump(&riff, filename, chunk, out.as_deref())?,
+            None => chunk::dump_unknown(&riff, filename, out.as_deref())?,
+        },
+        Some(Command::Tree) => tree::print_tree(&riff),
+        Some(Command::Translate { table, out }) => {
+            let table = translate::TranslationTable::load(table)?;
+            let translation = translate::translate(&riff, &table, out)?;
+
+            status!(cli, "Translated {} members", translation.translated.len());
+            for id in &translation.untranslated {
+                status!(cli, "No translation for cast member {}", id.id());
+            }
+            for (id, err) in &translation.failed {
+                status!(cli, "Cast member {} fails to load: {err}", id.id());
+            }
+            for id in &translation.rich_text {
+                status!(
+                    cli,
+                    "Cast member {} is rich text, which cannot be translated",
+                    id.id()
+                );
+            }
+            status!(cli, "Wrote {} bytes to {}", translation.len, out.display());
+        }
+        // Projectors are unpacked, movies checked and directories scanned
+        // before the movie is read.
+        Some(Command::Unpack { .. } | Command::Check | Command::Scan { .. }) => {}
+        #[cfg(feature = "serde")]
+        Some(Command::Stats { .. }) => {}
+        None => {}
     }
 
-    println!("score");
-    riff.read_score()?;
+    Ok(())
+}
+
+/// Displays a Director 3 movie. The subcommands and JSON output work on
+/// RIFX movies only.
+fn show_resource_movie(cli: &Cli, movie: &ResourceMovie) -> Result<()> {
+    if cli.json() || cli.command.is_some() {
+        anyhow::bail!("Director 3 movies can only be displayed");
+    }
+
+    status!(
+        cli,
+        "Resource movie: {} ({})\n",
+        movie.version(),
+        movie.encoding()
+    );
+
+    if cli.show_config {
+        movie.config().display();
+    }
+    if cli.show_file_info
+        && let Some(file_info) = movie.file_info()
+    {
+        file_info.display();
+    }
+    if cli.show_cast_table {
+        movie.cast_table().display();
+
+        for id in movie.cast_table().cast_member_ids() {
+            match movie.load_cast_member(id) {
+                Ok(cast_member) => println!(
+                    "{:5} {:12} {}",
+                    id.id(),
+                    cast_member.cast_member_type().to_string(),
+                    cast_member.name().unwrap_or_default()
+                ),
+                Err(err) => println!("{:5} {err}", id.id()),
+            }
+        }
+        println!();
+    }
+    if cli.show_frame_labels && !movie.frame_labels().is_empty() {
+        movie.frame_labels().display();
+    }
     if cli.show_score {
-        for frame in riff.score().frames() {
+        for frame in movie.score().frames() {
             frame.display();
         }
     }
@@ -0,0 +1,284 @@
+use std::{
+    any::Any,
+    collections::BTreeMap,
+    fs,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+use anyhow::{Context, Result};
+use director_decoder::riff::{
+    Movie, MovieContent, OpenOptions, Version,
+    cast_members::{CastMember, CastMemberId},
+    chunks::{Config, Score},
+};
+
+/// What `decoder scan` found out about one movie.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Summary {
+    pub path: PathBuf,
+    pub version: Option<Version>,
+    pub stage: Option<(u16, u16)>,
+    pub members: BTreeMap<String, usize>,
+    pub frames: Option<usize>,
+    pub errors: Vec<String>,
+    /// What was missing or damaged but could be read around.
+    pub warnings: Vec<String>,
+}
+
+impl Summary {
+    fn new(path: &Path) -> Self {
+        Summary {
+            path: path.to_path_buf(),
+            version: None,
+            stage: None,
+            members: BTreeMap::new(),
+            frames: None,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+/// Reads every movie in the directory tree at `dir` on `threads` threads,
+/// defaulting to one per CPU, and prints a summary of each in path order,
+/// or a JSON array of them. Files that are not movies are skipped.
+pub fn scan(dir: &Path, options: OpenOptions, threads: Option<usize>, json: bool) -> Result<()> {
+    let paths = collect_files(dir)?;
+    let summaries: Vec<_> = in_parallel(&paths, threads, |path| summarize(path, options))
+        .into_iter()
+        .zip(&paths)
+        .filter_map(|(result, path)| {
+            result.unwrap_or_else(|message| {
+                let mut summary = Summary::new(path);
+                summary
+                    .errors
+                    .push(format!("The decoder panicked: {message}"));
+                Some(summary)
+            })
+        })
+        .collect();
+
+    #[cfg(feature = "serde")]
+    if json {
+        crate::json::write_scan_report(&summaries)?;
+    }
+    if !json {
+        for summary in &summaries {
+            print_summary(summary);
+        }
+
+        let failed = summaries.iter().filter(|s| !s.errors.is_empty()).count();
+        println!(
+            "{} movies, {failed} with errors, {} other files skipped",
+            summaries.len(),
+            paths.len() - summaries.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Runs `f` on every path on `threads` threads, defaulting to one per CPU,
+/// and returns the results in the order of `paths`. A panic on one path is
+/// returned as its message in place of the result, so that one damaged
+/// movie does not stop the rest.
+pub fn in_parallel<T: Send>(
+    paths: &[PathBuf],
+    threads: Option<usize>,
+    f: impl Fn(&Path) -> T + Sync,
+) -> Vec<Result<T, String>> {
+    let threads = threads
+        .or_else(|| thread::available_parallelism().ok().map(|n| n.get()))
+        .unwrap_or(1)
+        .clamp(1, paths.len().max(1));
+
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(paths.len()));
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(path) = paths.get(index) else {
+                        break;
+                    };
+                    let result = panic::catch_unwind(AssertUnwindSafe(|| f(path)))
+                        .map_err(|payload| panic_message(payload.as_ref()));
+                    results.lock().unwrap().push((index, result));
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|&(index, _)| index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Unknown panic".to_string()
+    }
+}
+
+/// The files in the tree at `path` in path order, or `path` itself if it is
+/// a file.
+pub fn collect_files(path: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    walk(path, &mut paths)?;
+    paths.sort();
+
+    Ok(paths)
+}
+
+/// Symbolic links are not followed, so that the walk ends. The AppleDouble
+/// files that macOS leaves beside copied files, named `._` and the name of
+/// the file or kept in `__MACOSX`, are skipped, since they hold only the
+/// resource forks of the files beside them.
+fn walk(path: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    if !path.is_dir() {
+        paths.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let entries =
+        fs::read_dir(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    for entry in entries {
+        let entry = entry.with_context(|| format!("Failed to read {}", path.display()))?;
+        let file_type = entry.file_type()?;
+        let name = entry.file_name();
+        if name == "__MACOSX" || name.as_encoded_bytes().starts_with(b"._") {
+            continue;
+        }
+        if file_type.is_dir() {
+            walk(&entry.path(), paths)?;
+        } else if file_type.is_file() {
+            paths.push(entry.path());
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the movie at `path` and summarizes it, or returns `None` if the file
+/// is not a movie.
+fn summarize(path: &Path, options: OpenOptions) -> Option<Summary> {
+    let mut summary = Summary::new(path);
+
+    let movie = match Movie::open_with(path, options) {
+        Ok(movie) => movie,
+        Err(err) if Movie::is_not_a_movie(&err) => return None,
+        Err(err) => {
+            summary.errors.push(err.to_string());
+            return Some(summary);
+        }
+    };
+    summary
+        .warnings
+        .extend(movie.warnings().iter().map(|warning| warning.to_string()));
+
+    match movie.content() {
+        MovieContent::Riff(riff) => {
+            summary.version = Some(riff.version());
+            summarize_movie(&mut summary, riff.config(), riff.score());
+            // Only the CASt chunks are read, not the media of the members.
+            let cast_table = riff.cast_table();
+            count_members(
+                &mut summary,
+                cast_table.cast_member_ids().filter_map(|id| {
+                    let chunk_id = cast_table.cast_member_chunk_id(id)?;
+                    Some((id, riff.read_chunk_by_id::<CastMember>(chunk_id)))
+                }),
+            );
+        }
+        MovieContent::Resources(movie) => {
+            summary.version = Some(movie.version());
+            summarize_movie(&mut summary, movie.config(), movie.score());
+            count_members(
+                &mut summary,
+                movie
+                    .cast_table()
+                    .cast_member_ids()
+                    .map(|id| (id, movie.load_cast_member(id))),
+            );
+        }
+    }
+
+    Some(summary)
+}
+
+fn summarize_movie(summary: &mut Summary, config: &Config, score: &Score) {
+    summary.stage = Some((
+        config.movie_right.saturating_sub(config.movie_left),
+        config.movie_bottom.saturating_sub(config.movie_top),
+    ));
+    summary.frames = Some(score.frames().len());
+}
+
+fn count_members(
+    summary: &mut Summary,
+    cast_members: impl Iterator<Item = (CastMemberId, std::io::Result<CastMember>)>,
+) {
+    for (id, cast_member) in cast_members {
+        match cast_member {
+            Ok(cast_member) => {
+                *summary
+                    .members
+                    .entry(cast_member.cast_member_type().to_string())
+                    .or_default() += 1;
+            }
+            Err(err) => summary
+                .errors
+                .push(format!("Cast member {} fails to load: {err}", id.id())),
+        }
+    }
+}
+
+fn print_summary(summary: &Summary) {
+    let mut parts = Vec::new();
+    if let Some(version) = summary.version {
+        parts.push(version.to_string().trim_start().to_string());
+    }
+    if let Some((width, height)) = summary.stage {
+        parts.push(format!("{width}x{height}"));
+    }
+    if let Some(frames) = summary.frames {
+        parts.push(format!("{frames} frames"));
+    }
+    if summary.version.is_some() {
+        let count: usize = summary.members.values().sum();
+        let by_type = summary
+            .members
+            .iter()
+            .map(|(name, count)| format!("{name} {count}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        parts.push(if by_type.is_empty() {
+            format!("{count} members")
+        } else {
+            format!("{count} members ({by_type})")
+        });
+    }
+
+    if parts.is_empty() {
+        println!("{}", summary.path.display());
+    } else {
+        println!("{}: {}", summary.path.display(), parts.join(", "));
+    }
+    for error in &summary.errors {
+        println!("  error: {error}");
+    }
+    for warning in &summary.warnings {
+        println!("  warning: {warning}");
+    }
+}
@@ -0,0 +1,358 @@
+use std::{collections::BTreeMap, fmt::Write as _, fs, path::Path};
+
+use anyhow::{Context, Result};
+use director_decoder::riff::{
+    Movie, MovieContent, OpenOptions, ResourceMovie, RiffFile,
+    cast_members::CastMember,
+    chunks::{AnyChunk, LingoScript, Score},
+    lingo,
+};
+use serde::Serialize;
+
+use crate::scan;
+
+/// Counts of what a corpus of movies contains, to tell which features are
+/// worth implementing next.
+#[derive(Default, Serialize)]
+pub struct Stats {
+    pub movies: usize,
+    pub skipped_files: usize,
+    /// Chunks, or resources in Director 3 movies, by version and tag.
+    pub chunk_tags: BTreeMap<String, BTreeMap<String, usize>>,
+    pub cast_member_types: BTreeMap<String, usize>,
+    pub bitmap_depths: BTreeMap<u8, usize>,
+    /// Sprites in score frames by their raw ink byte.
+    pub inks: BTreeMap<u8, usize>,
+    /// Score frames by the tempo they set.
+    pub tempos: BTreeMap<String, usize>,
+    /// Lingo instructions by opcode, with the operand size bits removed.
+    pub opcodes: BTreeMap<String, usize>,
+    /// Errors with their numbers replaced by `N`, so that the same error in
+    /// different chunks or movies is counted together.
+    pub errors: BTreeMap<String, usize>,
+}
+
+impl Stats {
+    fn merge(&mut self, other: Stats) {
+        self.movies += other.movies;
+        self.skipped_files += other.skipped_files;
+        for (version, tags) in other.chunk_tags {
+            add_counts(self.chunk_tags.entry(version).or_default(), tags);
+        }
+        add_counts(&mut self.cast_member_types, other.cast_member_types);
+        add_counts(&mut self.bitmap_depths, other.bitmap_depths);
+        add_counts(&mut self.inks, other.inks);
+        add_counts(&mut self.tempos, other.tempos);
+        add_counts(&mut self.opcodes, other.opcodes);
+        add_counts(&mut self.errors, other.errors);
+    }
+
+    fn add_error(&mut self, error: impl ToString) {
+        *self
+            .errors
+            .entry(generalize(&error.to_string()))
+            .or_default() += 1;
+    }
+
+    fn add_cast_member(&mut self, cast_member: &CastMember) {
+        *self
+            .cast_member_types
+            .entry(cast_member.cast_member_type().to_string())
+            .or_default() += 1;
+
+        if let Some(bitmap) = cast_member.as_bitmap() {
+            *self.bitmap_depths.entry(bitmap.info.bit_depth).or_default() += 1;
+        }
+    }
+
+    fn add_score(&mut self, score: &Score) {
+        for frame in score.frames() {
+            if let Some(tempo) = frame.tempo {
+                *self.tempos.entry(format!("{tempo:?}")).or_default() += 1;
+            }
+            for (_, channel) in &frame.sprite_channels {
+                *self.inks.entry(channel.ink).or_default() += 1;
+            }
+        }
+    }
+
+    fn add_script(&mut self, script: &LingoScript) {
+        for handler in script.handlers() {
+            for instruction in lingo::decode(handler.bytecode()) {
+                let opcode = format!("{:02x} {}", instruction.base_opcode(), instruction.name());
+                *self.opcodes.entry(opcode).or_default() += 1;
+            }
+        }
+    }
+}
+
+/// Reads every movie in the directory tree at `dir` in parallel, and writes
+/// what they contain to `stats.json` and a table of it to `stats.txt` in
+/// `out_dir`.
+pub fn stats(
+    dir: &Path,
+    options: OpenOptions,
+    threads: Option<usize>,
+    out_dir: &Path,
+) -> Result<()> {
+    let paths = scan::collect_files(dir)?;
+
+    let mut stats = Stats::default();
+    for result in scan::in_parallel(&paths, threads, |path| movie_stats(path, options)) {
+        match result {
+            Ok(movie_stats) => stats.merge(movie_stats),
+            Err(message) => {
+                stats.movies += 1;
+                stats.add_error(format!("The decoder panicked: {message}"));
+            }
+        }
+    }
+
+    fs::create_dir_all(out_dir)
+        .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+
+    let json_path = out_dir.join("stats.json");
+    fs::write(&json_path, serde_json::to_string_pretty(&stats)?)
+        .with_context(|| format!("Failed to write {}", json_path.display()))?;
+
+    let text_path = out_dir.join("stats.txt");
+    fs::write(&text_path, report(&stats))
+        .with_context(|| format!("Failed to write {}", text_path.display()))?;
+
+    println!(
+        "{} movies, {} other files skipped",
+        stats.movies, stats.skipped_files
+    );
+    println!("Wrote {}", json_path.display());
+    println!("Wrote {}", text_path.display());
+
+    Ok(())
+}
+
+fn movie_stats(path: &Path, options: OpenOptions) -> Stats {
+    let mut stats = Stats::default();
+
+    let movie = match Movie::open_with(path, options) {
+        Ok(movie) => movie,
+        Err(err) if Movie::is_not_a_movie(&err) => {
+            stats.skipped_files = 1;
+            return stats;
+        }
+        Err(err) => {
+            stats.movies = 1;
+            stats.add_error(err);
+            return stats;
+        }
+    };
+
+    stats.movies = 1;
+    for warning in movie.warnings() {
+        stats.add_error(warning);
+    }
+
+    match movie.content() {
+        MovieContent::Riff(riff) => riff_stats(&mut stats, riff),
+        MovieContent::Resources(movie) => resource_movie_stats(&mut stats, movie),
+    }
+
+    stats
+}
+
+/// Parses every chunk of the movie, rather than only those the movie refers
+/// to, so that the errors of chunks the player never reads are counted too.
+fn riff_stats(stats: &mut Stats, riff: &RiffFile) {
+    let version = riff.version().to_string().trim_start().to_string();
+    for (entry, chunk) in riff.chunks() {
+        *stats
+            .chunk_tags
+            .entry(version.clone())
+            .or_default()
+            .entry(entry.tag().to_string())
+            .or_default() += 1;
+
+        match chunk {
+            Ok(AnyChunk::CastMember(cast_member)) => stats.add_cast_member(&cast_member),
+            Ok(AnyChunk::LingoScript(script)) => stats.add_script(&script),
+            Ok(_) => {}
+            Err(err) => stats.add_error(format!("{:?}: {err}", entry.tag())),
+        }
+    }
+
+    stats.add_score(riff.score());
+}
+
+fn resource_movie_stats(stats: &mut Stats, movie: &ResourceMovie) {
+    let version = movie.version().to_string().trim_start().to_string();
+    let tags = stats.chunk_tags.entry(version).or_default();
+    for resource in movie.resources() {
+        *tags.entry(resource.tag.to_string()).or_default() += 1;
+    }
+
+    for id in movie.cast_table().cast_member_ids() {
+        match movie.load_cast_member(id) {
+            Ok(cast_member) => stats.add_cast_member(&cast_member),
+            Err(err) => stats.add_error(format!("Cast member: {err}")),
+        }
+    }
+
+    stats.add_score(movie.score());
+}
+
+fn add_counts<K: Ord>(counts: &mut BTreeMap<K, usize>, other: BTreeMap<K, usize>) {
+    for (key, count) in other {
+        *counts.entry(key).or_default() += count;
+    }
+}
+
+/// Replaces the numbers in an error message, decimal or hexadecimal, with
+/// `N`.
+fn generalize(message: &str) -> String {
+    let mut generalized = String::with_capacity(message.len());
+    let mut chars = message.chars().peekable();
+    while let Some(c) = chars.next() {
+        if !c.is_ascii_digit() {
+            generalized.push(c);
+            continue;
+        }
+
+        let hex = c == '0' && chars.peek() == Some(&'x');
+        if hex {
+            chars.next();
+            generalized.push_str("0x");
+        }
+        while chars
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || (hex && c.is_ascii_hexdigit()))
+        {
+            chars.next();
+        }
+        generalized.push('N');
+    }
+
+    generalized
+}
+
+/// Lays the stats out as tables, each sorted by count unless its rows have
+/// an order of their own.
+fn report(stats: &Stats) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{} movies, {} other files skipped",
+        stats.movies, stats.skipped_files
+    );
+
+    let versions: Vec<_> = stats.chunk_tags.keys().collect();
+    let mut tags: Vec<_> = stats
+        .chunk_tags
+        .values()
+        .flat_map(|tags| tags.keys())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    let mut header = vec!["Tag".to_string()];
+    header.extend(versions.iter().map(|version| version.to_string()));
+    let rows = tags
+        .into_iter()
+        .map(|tag| {
+            let mut row = vec![tag.clone()];
+            row.extend(versions.iter().map(|version| {
+                let count = stats.chunk_tags[*version].get(tag).copied().unwrap_or(0);
+                count.to_string()
+            }));
+            row
+        })
+        .collect();
+    write_table(&mut out, "Chunk tags by version", header, rows);
+
+    write_counts(
+        &mut out,
+        "Cast member types",
+        "Type",
+        by_count(&stats.cast_member_types),
+    );
+    write_counts(
+        &mut out,
+        "Bitmap depths",
+        "Bits",
+        stats
+            .bitmap_depths
+            .iter()
+            .map(|(depth, &count)| (depth.to_string(), count))
+            .collect(),
+    );
+    write_counts(
+        &mut out,
+        "Sprite inks",
+        "Ink",
+        stats
+            .inks
+            .iter()
+            .map(|(ink, &count)| (format!("0x{ink:02x}"), count))
+            .collect(),
+    );
+    write_counts(&mut out, "Tempos", "Tempo", by_count(&stats.tempos));
+    write_counts(
+        &mut out,
+        "Lingo opcodes",
+        "Opcode",
+        by_count(&stats.opcodes),
+    );
+    write_counts(&mut out, "Errors", "Error", by_count(&stats.errors));
+
+    out
+}
+
+fn by_count(counts: &BTreeMap<String, usize>) -> Vec<(String, usize)> {
+    let mut rows: Vec<_> = counts
+        .iter()
+        .map(|(key, &count)| (key.clone(), count))
+        .collect();
+    rows.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    rows
+}
+
+fn write_counts(out: &mut String, title: &str, name: &str, counts: Vec<(String, usize)>) {
+    let rows = counts
+        .into_iter()
+        .map(|(key, count)| vec![count.to_string(), key])
+        .collect();
+    write_table(
+        out,
+        title,
+        vec!["Count".to_string(), name.to_string()],
+        rows,
+    );
+}
+
+/// Writes a table with a title, the columns padded to their widest cell.
+/// Columns of numbers are aligned to the right.
+fn write_table(out: &mut String, title: &str, header: Vec<String>, rows: Vec<Vec<String>>) {
+    let _ = writeln!(out, "\n{title}\n");
+    if rows.is_empty() {
+        let _ = writeln!(out, "  (none)");
+        return;
+    }
+
+    let mut widths: Vec<_> = header.iter().map(|cell| cell.chars().count()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let numeric: Vec<_> = (0..header.len())
+        .map(|i| rows.iter().all(|row| row[i].parse::<usize>().is_ok()))
+        .collect();
+
+    for row in std::iter::once(&header).chain(&rows) {
+        let mut line = String::from(" ");
+        for ((cell, &width), &numeric) in row.iter().zip(&widths).zip(&numeric) {
+            if numeric {
+                let _ = write!(line, " {cell:>width$}");
+            } else {
+                let _ = write!(line, " {cell:<width$}");
+            }
+        }
+        let _ = writeln!(out, "{}", line.trim_end());
+    }
+}
@@ -0,0 +1,346 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::Read,
+    path::Path,
+};
+
+use anyhow::{Context, Result, bail};
+use director_decoder::riff::{
+    MovieWriter, RiffFile,
+    cast_members::{CastMemberId, CastMemberKind},
+};
+
+/// Translations keyed by their source text, with `\n` line breaks.
+pub struct TranslationTable(HashMap<String, String>);
+
+impl TranslationTable {
+    /// Reads a gettext PO file, or a CSV file whose first two columns are the
+    /// source text and its translation, after a header row.
+    pub fn load(path: &Path) -> Result<Self> {
+        let is_po = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("po") || ext.eq_ignore_ascii_case("pot"));
+
+        let translations = if is_po {
+            let text = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            parse_po(&text).with_context(|| format!("Failed to parse {}", path.display()))?
+        } else {
+            let file =
+                File::open(path).with_context(|| format!("Failed to read {}", path.display()))?;
+            read_csv(file).with_context(|| format!("Failed to parse {}", path.display()))?
+        };
+
+        Ok(TranslationTable(
+            translations
+                .into_iter()
+                .filter(|(source, translation)| !source.is_empty() && !translation.is_empty())
+                .map(|(source, translation)| (normalize(&source), normalize(&translation)))
+                .collect(),
+        ))
+    }
+
+    fn get(&self, source: &str) -> Option<&str> {
+        self.0.get(&normalize(source)).map(String::as_str)
+    }
+}
+
+/// What `translate` changed.
+pub struct Translation {
+    pub translated: Vec<CastMemberId>,
+    /// Members with text that has no entry in the table.
+    pub untranslated: Vec<CastMemberId>,
+    /// Rich text members, whose text the decoder cannot read or replace.
+    pub rich_text: Vec<CastMemberId>,
+    /// Members that fail to load, with the error, which are left as they are.
+    pub failed: Vec<(CastMemberId, String)>,
+    pub len: usize,
+}
+
+/// Replaces the text of every text, field and button member that has an
+/// entry in `table`, and writes the movie to `out`. Style runs keep their
+/// relative positions.
+pub fn translate(riff: &RiffFile, table: &TranslationTable, out: &Path) -> Result<Translation> {
+    let mut writer = MovieWriter::new(riff)?;
+    let mut translated = Vec::new();
+    let mut untranslated = Vec::new();
+    let mut rich_text = Vec::new();
+    let mut failed = Vec::new();
+
+    for id in riff.cast_table().cast_member_ids() {
+        let mut cast_member = match riff.load_cast_member(id) {
+            Ok(cast_member) => cast_member,
+            Err(err) => {
+                failed.push((id, err.to_string()));
+                continue;
+            }
+        };
+
+        let text = match &mut cast_member.kind {
+            CastMemberKind::Text(text) | CastMemberKind::Button(text) => text,
+            CastMemberKind::RTE => {
+                rich_text.push(id);
+                continue;
+            }
+            _ => continue,
+        };
+
+        let Some(source) = text.text().filter(|source| !source.trim().is_empty()) else {
+            continue;
+        };
+
+        let Some(translation) = table.get(&source) else {
+            untranslated.push(id);
+            continue;
+        };
+
+        // Director separates lines with carriage returns.
+        text.set_text(&translation.replace('\n', "\r"))
+            .with_context(|| format!("Failed to translate cast member {}", id.id()))?;
+        writer.replace_cast_member(id, &cast_member)?;
+        translated.push(id);
+    }
+
+    let data = writer.write()?;
+    fs::write(out, &data).with_context(|| format!("Failed to write {}", out.display()))?;
+
+    Ok(Translation {
+        translated,
+        untranslated,
+        rich_text,
+        failed,
+        len: data.len(),
+    })
+}
+
+fn normalize(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+fn read_csv(csv: impl Read) -> Result<Vec<(String, String)>> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(csv);
+
+    let mut translations = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        if let (Some(source), Some(translation)) = (record.get(0), record.get(1)) {
+            translations.push((source.to_owned(), translation.to_owned()));
+        }
+    }
+
+    Ok(translations)
+}
+
+#[derive(Default)]
+struct PoEntry {
+    msgid: String,
+    msgstr: String,
+    has_msgstr: bool,
+    fuzzy: bool,
+}
+
+#[derive(Clone, Copy)]
+enum PoField {
+    Id,
+    Str,
+    Ignored,
+}
+
+/// Parses the entries of a PO file. Fuzzy entries are left out, and of
+/// plural entries only the singular form is used.
+fn parse_po(text: &str) -> Result<Vec<(String, String)>> {
+    let mut translations = Vec::new();
+    let mut entry = PoEntry::default();
+    let mut field = PoField::Ignored;
+
+    let mut finish = |entry: &mut PoEntry| {
+        let entry = std::mem::take(entry);
+        if !entry.fuzzy {
+            translations.push((entry.msgid, entry.msgstr));
+        }
+    };
+
+    for (line_number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(comment) = line.strip_prefix('#') {
+            if entry.has_msgstr {
+                finish(&mut entry);
+            }
+            if let Some(flags) = comment.strip_prefix(',') {
+                entry.fuzzy |= flags.split(',').any(|flag| flag.trim() == "fuzzy");
+            }
+            field = PoField::Ignored;
+            continue;
+        }
+
+        let (keyword, string) = match line.split_once(char::is_whitespace) {
+            _ if line.starts_with('"') => (None, line),
+            Some((keyword, string)) => (Some(keyword), string.trim()),
+            None => bail!("line {}: Expected a string", line_number + 1),
+        };
+
+        if let Some(keyword) = keyword {
+            if entry.has_msgstr && matches!(keyword, "msgctxt" | "msgid") {
+                finish(&mut entry);
+            }
+
+            field = match keyword {
+                "msgid" => PoField::Id,
+                "msgstr" | "msgstr[0]" => {
+                    entry.has_msgstr = true;
+                    PoField::Str
+                }
+                "msgctxt" | "msgid_plural" => PoField::Ignored,
+                _ if keyword.starts_with("msgstr[") => PoField::Ignored,
+                _ => bail!("line {}: Unknown keyword {keyword}", line_number + 1),
+            };
+        }
+
+        let Some(string) = string
+            .strip_prefix('"')
+            .and_then(|string| string.strip_suffix('"'))
+        else {
+            bail!("line {}: Expected a quoted string", line_number + 1);
+        };
+
+        match field {
+            PoField::Id => entry.msgid.push_str(&unescape(string)),
+            PoField::Str => entry.msgstr.push_str(&unescape(string)),
+            PoField::Ignored => {}
+        }
+    }
+
+    if entry.has_msgstr {
+        finish(&mut entry);
+    }
+
+    Ok(translations)
+}
+
+fn unescape(string: &str) -> String {
+    let mut unescaped = String::with_capacity(string.len());
+    let mut chars = string.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('t') => unescaped.push('\t'),
+            Some(c) => unescaped.push(c),
+            None => {}
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|&(source, translation)| (source.to_owned(), translation.to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_multi_line_po_strings() {
+        let po = r#"
+# A header, which has an empty msgid
+msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=UTF-8\n"
+
+#: Cast member 2
+msgid ""
+"Hello, \"world\"\n"
+"Second line"
+msgstr "Bonjour, "
+"\"monde\"\n"
+"Deuxième ligne"
+"#;
+
+        assert_eq!(
+            parse_po(po).unwrap(),
+            pairs(&[
+                ("", "Content-Type: text/plain; charset=UTF-8\n"),
+                (
+                    "Hello, \"world\"\nSecond line",
+                    "Bonjour, \"monde\"\nDeuxième ligne"
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn leaves_out_fuzzy_po_entries() {
+        let po = r#"
+#, fuzzy
+msgid "Start"
+msgstr "Démarrer"
+
+#, c-format, fuzzy
+msgid "Stop"
+msgstr "Arrêter"
+
+#, c-format
+msgid "Quit"
+msgstr "Quitter"
+"#;
+
+        assert_eq!(parse_po(po).unwrap(), pairs(&[("Quit", "Quitter")]));
+    }
+
+    #[test]
+    fn uses_the_singular_of_plural_po_entries() {
+        let po = r#"
+msgctxt "score"
+msgid "%d point"
+msgid_plural "%d points"
+msgstr[0] "%d punto"
+msgstr[1] "%d puntos"
+msgid "Next"
+msgstr "Siguiente"
+"#;
+
+        assert_eq!(
+            parse_po(po).unwrap(),
+            pairs(&[("%d point", "%d punto"), ("Next", "Siguiente")])
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_po_files() {
+        assert!(parse_po("msgid \"Start\"\nmsgtxt \"Démarrer\"\n").is_err());
+        assert!(parse_po("msgid Start\n").is_err());
+    }
+
+    #[test]
+    fn unescapes_po_strings() {
+        assert_eq!(unescape(r#"a\tb\r\n\"c\"\\"#), "a\tb\r\n\"c\"\\");
+        assert_eq!(unescape(r"trailing\"), "trailing");
+    }
+
+    #[test]
+    fn skips_the_csv_header_row() {
+        let csv = "source,translation,notes\n\
+                   Hello,Hallo\n\
+                   \"Two\nlines\",\"Zwei\nZeilen\",\n\
+                   Untranslated\n";
+
+        assert_eq!(
+            read_csv(csv.as_bytes()).unwrap(),
+            pairs(&[("Hello", "Hallo"), ("Two\nlines", "Zwei\nZeilen")])
+        );
+    }
+}
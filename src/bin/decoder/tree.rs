@@ -0,0 +1,54 @@
+use std::collections::HashSet;
+
+use director_decoder::riff::{RiffFile, cast_members::CastMember};
+use thousands::Separable;
+
+/// Prints the chunks of the movie as the tree `RiffFile::chunk_children`
+/// makes, from the chunks that have no owner down, with the tag and size of
+/// every chunk and the number and name of every cast member.
+pub fn print_tree(riff: &RiffFile) {
+    let mut visited = HashSet::new();
+    for root in riff.chunk_roots() {
+        print_chunk(riff, root, 0, &mut visited);
+    }
+}
+
+fn print_chunk(riff: &RiffFile, id: u32, depth: usize, visited: &mut HashSet<u32>) {
+    let indent = "  ".repeat(depth);
+
+    let Some(entry) = riff.mmap().entry_by_index(id) else {
+        println!("{indent}{id}");
+        print_children(riff, id, depth, visited);
+        return;
+    };
+
+    let mut line = format!(
+        "{indent}{} {id} ({} bytes)",
+        entry.tag(),
+        entry.len().separate_with_commas()
+    );
+    if let Some(member) = riff.cast_table().cast_member_with_chunk_id(id) {
+        line += &format!(", cast member {}", member.id());
+
+        let name = riff.read_chunk_by_id::<CastMember>(id).ok();
+        if let Some(name) = name.as_ref().and_then(|member| member.name())
+            && !name.is_empty()
+        {
+            line += &format!(" \"{name}\"");
+        }
+    }
+    println!("{line}");
+
+    print_children(riff, id, depth, visited);
+}
+
+fn print_children(riff: &RiffFile, id: u32, depth: usize, visited: &mut HashSet<u32>) {
+    // A damaged key table can make a chunk its own descendant.
+    if !visited.insert(id) {
+        return;
+    }
+
+    for child in riff.chunk_children(id) {
+        print_chunk(riff, child, depth + 1, visited);
+    }
+}
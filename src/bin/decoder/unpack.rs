@@ -0,0 +1,38 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use director_decoder::riff::{MovieWriter, Projector, ProjectorFileKind, RiffFile};
+
+/// Writes every file embedded in `projector` to `out_dir`. Movies and casts
+/// are rewritten so that they open on their own, other files are written as
+/// they are stored.
+pub fn unpack(projector: &mut Projector, out_dir: &Path) -> Result<()> {
+    fs::create_dir_all(out_dir)
+        .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+
+    for file in projector.files()? {
+        let data = match file.kind {
+            ProjectorFileKind::Movie | ProjectorFileKind::Cast => {
+                match RiffFile::new(file.reader()).and_then(|riff| MovieWriter::new(&riff)?.write())
+                {
+                    Ok(data) => data,
+                    Err(err) => {
+                        println!(
+                            "{}: Unable to rewrite, writing it as stored: {err}",
+                            file.name
+                        );
+                        file.read_data()?
+                    }
+                }
+            }
+            ProjectorFileKind::Xtra | ProjectorFileKind::Other => file.read_data()?,
+        };
+
+        let path = out_dir.join(&file.name);
+        fs::write(&path, &data).with_context(|| format!("Failed to write {}", path.display()))?;
+
+        println!("{:>10} bytes  {}", data.len(), path.display());
+    }
+
+    Ok(())
+}
@@ -1,21 +1,11 @@
 mod player;
 
-use std::{
-    fs::File,
-    io::{Read, Seek},
-    num::NonZeroU32,
-    ops::DerefMut,
-    rc::Rc,
-};
+use std::{num::NonZeroU32, ops::DerefMut, rc::Rc};
 
 use clap::Parser;
 
 use anyhow::Result;
-use director_decoder::{
-    gfx,
-    reader::Reader,
-    riff::{Projector, RiffFile, tags},
-};
+use director_decoder::{gfx, riff::Movie};
 use player::{DisplayList, Player};
 use winit::{
     application::ApplicationHandler,
@@ -140,59 +130,19 @@ fn main() -> Result<()> {
 
     let filename = std::path::Path::new(&cli.filename);
 
-    let mut buf = Vec::new();
-    let mut file = File::open(filename)?;
-    file.read_to_end(&mut buf)?;
-    let mut reader = Reader::new(&buf);
-
-    let mut riff = if filename
-        .extension()
-        .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"))
-    {
-        let mut projector = Projector::read(reader.clone())?;
+    let movie = Movie::open(filename)?;
 
+    if let Some(projector) = movie.projector() {
         projector.display_header();
+    }
+    for warning in movie.warnings() {
+        println!("{warning}");
+    }
 
-        let mut riff = projector.read_initial_riff()?;
-
-        if riff.type_tag() == tags::TAG_APPL {
-            if let Some(file) = riff.mmap().first_entry_with_tag(tags::TAG_File) {
-                reader.seek(std::io::SeekFrom::Start(file.pos() as u64))?;
-                riff = RiffFile::new(reader)?;
-            }
-        }
-
-        riff.read_key_table()?;
-        if let Err(err) = riff.read_config() {
-            println!("{err}");
-        }
-
-        riff
-    } else {
-        let mut riff = RiffFile::new(reader)?;
-
-        riff.read_key_table()?;
-        if let Err(err) = riff.read_config() {
-            println!("{err}");
-        }
-
-        riff
+    let Some(riff) = movie.into_riff() else {
+        anyhow::bail!("Director 3 movies cannot be played yet");
     };
 
-    riff.read_file_info()?;
-
-    riff.read_cast_table()?;
-
-    match riff.read_frame_labels() {
-        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
-            println!("No frame labels found");
-            Ok(())
-        }
-        v => v,
-    }?;
-
-    riff.read_score()?;
-
     let event_loop = EventLoop::new().expect("Failed to create event loop");
     event_loop.set_control_flow(ControlFlow::Poll);
 
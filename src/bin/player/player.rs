@@ -1,4 +1,4 @@
-use std::{collections::HashMap, rc::Rc};
+use std::sync::Arc;
 
 use director_decoder::{
     gfx,
@@ -14,7 +14,7 @@ pub enum DisplayObject {
     Bitmap {
         id: cast_members::CastMemberId,
         rect: gfx::Rect,
-        image: Rc<gfx::IndexedImage>,
+        image: Arc<gfx::IndexedImage>,
         draw_mode: DrawMode,
     },
 }
@@ -43,8 +43,6 @@ pub struct Player<'a> {
 
     current_frame_time: std::time::Instant,
     next_frame_time: std::time::Instant,
-
-    cast_members: HashMap<CastMemberId, cast_members::CastMember>,
 }
 
 impl<'a> Player<'a> {
@@ -59,8 +57,6 @@ impl<'a> Player<'a> {
 
             current_frame_time: std::time::Instant::now(),
             next_frame_time: std::time::Instant::now(),
-
-            cast_members: HashMap::new(),
         }
     }
 
@@ -99,50 +95,52 @@ impl<'a> Player<'a> {
         }
 
         // If the current frame has a palette, update the global palette
-        if let Some(palette_id) = frame.palette_id {
-            if let Some(palette) = self.cast_members.get(&palette_id) {
-                if let Some(palette) = palette.as_palette() {
-                    if let Some(clut) = &palette.clut {
-                        for (i, color) in clut.colors.iter().enumerate() {
-                            let color = gfx::Rgb161616 {
-                                r: color.r,
-                                g: color.g,
-                                b: color.b,
-                            };
-                            self.palette[i] = color;
-                        }
-                    }
-                }
+        if let Some(palette_id) = frame.palette_id
+            && let Ok(palette) = self.riff.cached_cast_member(palette_id)
+            && let Some(palette) = palette.as_palette()
+            && let Some(clut) = &palette.clut
+        {
+            for (i, color) in clut.colors.iter().enumerate() {
+                let color = gfx::Rgb161616 {
+                    r: color.r,
+                    g: color.g,
+                    b: color.b,
+                };
+                self.palette[i] = color;
             }
         }
 
         let mut display_list = DisplayList::new();
         for (_, channel) in &frame.sprite_channels {
-            if channel.sprite_type == 1 {
-                if let Some(cast_member_id) = channel.cast_member_id {
-                    let cast_member = self.cast_members.get(&cast_member_id).unwrap();
-                    let bitmap_cast_member = cast_member.as_bitmap().unwrap();
-                    let image = bitmap_cast_member.image().unwrap();
-
-                    let rect = bitmap_cast_member
-                        .info
-                        .rect
-                        .translate(-bitmap_cast_member.info.reg)
-                        .translate(channel.position);
-
-                    let draw_mode = match channel.ink {
-                        0 => DrawMode::Copy,
-                        36 => DrawMode::TransparentColorIndex(channel.back_color),
-                        _ => DrawMode::Copy,
-                    };
-
-                    display_list.push(DisplayObject::Bitmap {
-                        id: cast_member_id,
-                        rect,
-                        image: Rc::new(image),
-                        draw_mode,
-                    });
-                }
+            if channel.sprite_type == 1
+                && let Some(cast_member_id) = channel.cast_member_id
+            {
+                let cast_member = self.riff.cached_cast_member(cast_member_id).unwrap();
+                let bitmap_cast_member = cast_member.as_bitmap().unwrap();
+                let image = self
+                    .riff
+                    .cached_bitmap_image(cast_member_id)
+                    .unwrap()
+                    .unwrap();
+
+                let rect = bitmap_cast_member
+                    .info
+                    .rect
+                    .translate(-bitmap_cast_member.info.reg)
+                    .translate(channel.position);
+
+                let draw_mode = match channel.ink {
+                    0 => DrawMode::Copy,
+                    36 => DrawMode::TransparentColorIndex(channel.back_color),
+                    _ => DrawMode::Copy,
+                };
+
+                display_list.push(DisplayObject::Bitmap {
+                    id: cast_member_id,
+                    rect,
+                    image,
+                    draw_mode,
+                });
             }
         }
 
@@ -177,16 +175,10 @@ impl<'a> Player<'a> {
         }
     }
 
+    /// Loads a cast member into the movie's cache, unless it is there.
     pub fn preload_cast_member(&mut self, cast_member_id: CastMemberId) {
-        if self.cast_members.contains_key(&cast_member_id) {
-            return;
-        }
-
-        let cast_member = self
-            .riff
-            .load_cast_member(cast_member_id)
+        self.riff
+            .cached_cast_member(cast_member_id)
             .expect("Failed to load cast member");
-
-        self.cast_members.insert(cast_member_id, cast_member);
     }
 }
@@ -1,4 +1,7 @@
-use std::ops::{Deref, DerefMut};
+use std::{
+    ops::{Deref, DerefMut},
+    path::Path,
+};
 
 use super::rgb888::Rgb888;
 
@@ -61,7 +64,7 @@ where
         Some(Rgb888::from_u32(data[index]))
     }
 
-    pub fn save_to_png(&self, path: &str) -> Result<(), png::EncodingError> {
+    pub fn save_to_png(&self, path: &Path) -> Result<(), png::EncodingError> {
         use png::{self, Encoder};
         use std::fs::File;
         use std::io::BufWriter;
@@ -104,3 +107,42 @@ where
         data[index] = color.to_u32();
     }
 }
+
+impl ImageBuffer<Vec<u32>> {
+    /// Loads a PNG, flattening any transparency onto white.
+    pub fn load_png(path: &str) -> Result<Self, png::DecodingError> {
+        use png::{self, ColorType, Decoder, Transformations};
+        use std::fs::File;
+        use std::io::BufReader;
+
+        let file = File::open(path)?;
+        let mut decoder = Decoder::new(BufReader::new(file));
+        decoder.set_transformations(Transformations::normalize_to_color8());
+
+        let mut reader = decoder.read_info()?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf)?;
+
+        let (width, height) = (info.width as usize, info.height as usize);
+        let channels = info.color_type.samples();
+
+        let data = buf
+            .chunks_exact(info.line_size)
+            .take(height)
+            .flat_map(|row| row.chunks_exact(channels).take(width))
+            .map(|pixel| {
+                let (rgb, alpha) = match info.color_type {
+                    ColorType::Grayscale => ([pixel[0]; 3], 255),
+                    ColorType::GrayscaleAlpha => ([pixel[0]; 3], pixel[1]),
+                    ColorType::Rgba => ([pixel[0], pixel[1], pixel[2]], pixel[3]),
+                    _ => ([pixel[0], pixel[1], pixel[2]], 255),
+                };
+                let [r, g, b] = rgb
+                    .map(|c| ((c as u32 * alpha as u32 + 255 * (255 - alpha as u32)) / 255) as u8);
+                Rgb888 { r, g, b }.to_u32()
+            })
+            .collect();
+
+        Ok(ImageBuffer::new(width, height, data))
+    }
+}
@@ -1,4 +1,7 @@
-use std::ops::{Deref, DerefMut};
+use std::{
+    ops::{Deref, DerefMut},
+    path::Path,
+};
 
 use super::Palette;
 
@@ -90,7 +93,7 @@ where
         writer.write_image_data(self.get_data())
     }
 
-    pub fn save_to_png(&self, palette: &Palette, path: &str) -> Result<(), png::EncodingError> {
+    pub fn save_to_png(&self, palette: &Palette, path: &Path) -> Result<(), png::EncodingError> {
         use png::{self, Encoder};
         use std::fs::File;
         use std::io::BufWriter;
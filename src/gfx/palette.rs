@@ -23,6 +23,73 @@ impl Palette {
     pub fn get_rgb888(&self, index: u8) -> Option<Rgb888> {
         self.0.get(index as usize).map(Rgb888::from)
     }
+
+    /// Builds a palette from a list of colors, leaving any remaining entries
+    /// black.
+    pub fn from_colors(colors: &[Rgb161616]) -> Self {
+        let mut palette = Palette::new();
+        for (i, color) in colors.iter().take(256).enumerate() {
+            palette.0[i] = *color;
+        }
+        palette
+    }
+
+    /// Returns the entry among `indices` closest to `color`.
+    pub fn nearest_index(&self, color: Rgb888, indices: impl IntoIterator<Item = u8>) -> u8 {
+        let distance = |index: u8| {
+            let entry = Rgb888::from(self.0[index as usize]);
+            let dr = entry.r as i32 - color.r as i32;
+            let dg = entry.g as i32 - color.g as i32;
+            let db = entry.b as i32 - color.b as i32;
+            dr * dr + dg * dg + db * db
+        };
+
+        indices
+            .into_iter()
+            .min_by_key(|&i| distance(i))
+            .unwrap_or(0)
+    }
+
+    /// The standard 8-bit Macintosh system palette, Director's default.
+    ///
+    /// Entries 0..215 are a 6x6x6 color cube running from white to just
+    /// above black, followed by ten step ramps of red, green, blue and gray
+    /// using the levels that are not multiples of 0x33, and finally black.
+    pub fn mac_system() -> Self {
+        const RAMP: [u8; 10] = [0xee, 0xdd, 0xbb, 0xaa, 0x88, 0x77, 0x55, 0x44, 0x22, 0x11];
+
+        let mut palette = Palette::new();
+        let mut index = 0;
+
+        for i in 0..215 {
+            let r = 0xff - 0x33 * (i / 36) as u8;
+            let g = 0xff - 0x33 * (i / 6 % 6) as u8;
+            let b = 0xff - 0x33 * (i % 6) as u8;
+            palette.0[index] = (r, g, b).into();
+            index += 1;
+        }
+
+        for channel in 0..4 {
+            for level in RAMP {
+                palette.0[index] = match channel {
+                    0 => (level, 0, 0),
+                    1 => (0, level, 0),
+                    2 => (0, 0, level),
+                    _ => (level, level, level),
+                }
+                .into();
+                index += 1;
+            }
+        }
+
+        palette.0[index] = (0u8, 0u8, 0u8).into();
+
+        palette
+    }
+
+    pub fn colors(&self) -> &[Rgb161616] {
+        &self.0
+    }
 }
 
 impl Index<usize> for Palette {
@@ -1,6 +1,7 @@
 use std::ops::Neg;
 
 #[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Pos {
     pub y: i16,
     pub x: i16,
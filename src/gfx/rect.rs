@@ -1,6 +1,7 @@
 use super::Pos;
 
 #[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Rect {
     pub y0: i16,
     pub x0: i16,
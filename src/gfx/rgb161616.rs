@@ -1,6 +1,7 @@
 use super::Rgb888;
 
 #[derive(Debug, Default, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Rgb161616 {
     pub r: u16,
     pub g: u16,
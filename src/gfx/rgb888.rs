@@ -1,6 +1,7 @@
 use super::rgb161616::Rgb161616;
 
 #[derive(Debug, Default, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Rgb888 {
     pub r: u8,
     pub g: u8,
@@ -1,4 +1,5 @@
 #[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Size {
     pub h: i16,
     pub w: i16,
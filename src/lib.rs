@@ -2,6 +2,9 @@
 #![feature(iter_map_windows)]
 #![feature(seek_stream_len)]
 
+pub mod audio;
 pub mod gfx;
+pub mod mac;
 pub mod reader;
 pub mod riff;
+pub mod writer;
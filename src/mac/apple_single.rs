@@ -0,0 +1,172 @@
+use std::io::{Error, ErrorKind, Result};
+
+use crate::{reader::TextEncoding, riff::tags::Tag};
+
+use super::MacFile;
+
+const APPLE_SINGLE_MAGIC: u32 = 0x0005_1600;
+const APPLE_DOUBLE_MAGIC: u32 = 0x0005_1607;
+
+const ENTRY_DATA_FORK: u32 = 1;
+const ENTRY_RESOURCE_FORK: u32 = 2;
+const ENTRY_REAL_NAME: u32 = 3;
+const ENTRY_FINDER_INFO: u32 = 9;
+
+/// Whether `buf` is an AppleSingle file, which holds both forks, or an
+/// AppleDouble header file, which holds the resource fork of a file stored
+/// next to it.
+pub fn is_apple_single(buf: &[u8]) -> bool {
+    buf.len() >= 26
+        && matches!(
+            read_u32(buf, 0),
+            Some(APPLE_SINGLE_MAGIC | APPLE_DOUBLE_MAGIC)
+        )
+}
+
+pub fn is_apple_double(buf: &[u8]) -> bool {
+    buf.len() >= 26 && read_u32(buf, 0) == Some(APPLE_DOUBLE_MAGIC)
+}
+
+/// Reads an AppleSingle or AppleDouble file. The data fork of an AppleDouble
+/// file is empty unless it is filled in from the file it belongs to.
+pub fn read(buf: &[u8]) -> Result<MacFile> {
+    if !is_apple_single(buf) {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "Not an AppleSingle or AppleDouble file".to_string(),
+        ));
+    }
+
+    let mut file = MacFile::default();
+
+    let entry_count = u16::from_be_bytes([buf[24], buf[25]]) as usize;
+    for i in 0..entry_count {
+        let position = 26 + i * 12;
+        let (Some(id), Some(offset), Some(len)) = (
+            read_u32(buf, position),
+            read_u32(buf, position + 4),
+            read_u32(buf, position + 8),
+        ) else {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                format!("AppleSingle entry {i} is truncated"),
+            ));
+        };
+
+        let data = buf
+            .get(offset as usize..offset as usize + len as usize)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::UnexpectedEof,
+                    format!("AppleSingle entry {id} extends past the end of the file"),
+                )
+            })?;
+
+        match id {
+            ENTRY_DATA_FORK => file.data_fork = data.into(),
+            ENTRY_RESOURCE_FORK => file.resource_fork = data.into(),
+            ENTRY_REAL_NAME => file.name = TextEncoding::MacRoman.decode(data),
+            ENTRY_FINDER_INFO if data.len() >= 8 => {
+                file.file_type = Tag(i32::from_be_bytes(data[0..4].try_into().unwrap()));
+                file.creator = Tag(i32::from_be_bytes(data[4..8].try_into().unwrap()));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(file)
+}
+
+fn read_u32(buf: &[u8], position: usize) -> Option<u32> {
+    buf.get(position..position + 4)
+        .map(|bytes| u32::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An AppleSingle or AppleDouble file with the given entries, laid out in
+    /// order after the entry table.
+    fn apple_single(magic: u32, entries: &[(u32, &[u8])]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend(magic.to_be_bytes());
+        buf.extend(0x0002_0000u32.to_be_bytes());
+        buf.extend([0; 16]);
+        buf.extend((entries.len() as u16).to_be_bytes());
+
+        let mut offset = 26 + 12 * entries.len();
+        for (id, data) in entries {
+            buf.extend(id.to_be_bytes());
+            buf.extend((offset as u32).to_be_bytes());
+            buf.extend((data.len() as u32).to_be_bytes());
+            offset += data.len();
+        }
+        for (_, data) in entries {
+            buf.extend(*data);
+        }
+        buf
+    }
+
+    #[test]
+    fn reads_apple_single_entries() {
+        let mut finder_info = [0u8; 32];
+        finder_info[..8].copy_from_slice(b"MV93MD93");
+        let buf = apple_single(
+            APPLE_SINGLE_MAGIC,
+            &[
+                (ENTRY_REAL_NAME, b"Movie"),
+                (ENTRY_FINDER_INFO, &finder_info),
+                (ENTRY_DATA_FORK, b"data fork"),
+                (ENTRY_RESOURCE_FORK, b"resource fork"),
+            ],
+        );
+        assert!(is_apple_single(&buf));
+        assert!(!is_apple_double(&buf));
+
+        let file = read(&buf).unwrap();
+        assert_eq!(file.name, "Movie");
+        assert_eq!(file.file_type, Tag(i32::from_be_bytes(*b"MV93")));
+        assert_eq!(file.creator, Tag(i32::from_be_bytes(*b"MD93")));
+        assert_eq!(&*file.data_fork, b"data fork");
+        assert_eq!(&*file.resource_fork, b"resource fork");
+    }
+
+    #[test]
+    fn reads_apple_double_headers() {
+        let buf = apple_single(APPLE_DOUBLE_MAGIC, &[(ENTRY_RESOURCE_FORK, b"fork")]);
+        assert!(is_apple_single(&buf));
+        assert!(is_apple_double(&buf));
+
+        let file = read(&buf).unwrap();
+        assert!(file.data_fork.is_empty());
+        assert_eq!(&*file.resource_fork, b"fork");
+    }
+
+    #[test]
+    fn rejects_an_entry_past_the_end_of_the_file() {
+        let mut buf = apple_single(APPLE_DOUBLE_MAGIC, &[(ENTRY_RESOURCE_FORK, b"fork")]);
+        buf.truncate(buf.len() - 1);
+        assert_eq!(read(&buf).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+
+        let mut buf = apple_single(APPLE_DOUBLE_MAGIC, &[(ENTRY_RESOURCE_FORK, b"fork")]);
+        buf[30..34].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert_eq!(read(&buf).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn rejects_a_truncated_entry_table() {
+        let buf = apple_single(APPLE_DOUBLE_MAGIC, &[(ENTRY_RESOURCE_FORK, b"fork")]);
+        assert_eq!(
+            read(&buf[..30]).unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(!is_apple_single(&[0; 26]));
+        assert!(!is_apple_single(&APPLE_SINGLE_MAGIC.to_be_bytes()));
+        assert_eq!(read(&[0; 26]).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+}
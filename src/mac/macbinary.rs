@@ -0,0 +1,178 @@
+use std::io::{Error, ErrorKind, Result};
+
+use crate::{reader::TextEncoding, riff::tags::Tag};
+
+use super::MacFile;
+
+const HEADER_LEN: usize = 128;
+
+/// Whether `buf` starts with a MacBinary I, II or III header.
+///
+/// MacBinary has no magic number in its first two versions, so this checks
+/// the zero bytes and name length the format requires, the CRC of MacBinary
+/// II, the `mBIN` signature of MacBinary III, and that the forks fit in the
+/// file.
+pub fn is_macbinary(buf: &[u8]) -> bool {
+    if buf.len() < HEADER_LEN || buf[0] != 0 || buf[74] != 0 || !(1..=63).contains(&buf[1]) {
+        return false;
+    }
+
+    let has_signature = &buf[102..106] == b"mBIN";
+    let has_crc = u16::from_be_bytes([buf[124], buf[125]]) == crc16(&buf[..124]);
+    if !has_signature && !has_crc && buf[82] != 0 {
+        return false;
+    }
+
+    fork_layout(buf).is_some_and(|(_, _, end)| end <= buf.len())
+}
+
+pub fn read(buf: &[u8]) -> Result<MacFile> {
+    if !is_macbinary(buf) {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "Not a MacBinary file".to_string(),
+        ));
+    }
+
+    let (data, resource, _) = fork_layout(buf).unwrap();
+
+    Ok(MacFile {
+        name: TextEncoding::MacRoman.decode(&buf[2..2 + buf[1] as usize]),
+        file_type: Tag(i32::from_be_bytes(buf[65..69].try_into().unwrap())),
+        creator: Tag(i32::from_be_bytes(buf[69..73].try_into().unwrap())),
+        data_fork: buf[data].into(),
+        resource_fork: buf[resource].into(),
+    })
+}
+
+/// The ranges of the data and resource forks, and the end of the last one.
+/// Each part of the file is padded to a multiple of 128 bytes.
+fn fork_layout(buf: &[u8]) -> Option<(std::ops::Range<usize>, std::ops::Range<usize>, usize)> {
+    let data_len = u32::from_be_bytes(buf[83..87].try_into().unwrap());
+    let resource_len = u32::from_be_bytes(buf[87..91].try_into().unwrap());
+    let secondary_header_len = u16::from_be_bytes([buf[120], buf[121]]);
+    if data_len > 0x7fff_ffff || resource_len > 0x7fff_ffff {
+        return None;
+    }
+
+    let data_start = HEADER_LEN + padded(secondary_header_len as usize);
+    let resource_start = data_start + padded(data_len as usize);
+    let end = resource_start + resource_len as usize;
+
+    Some((
+        data_start..data_start + data_len as usize,
+        resource_start..end,
+        end,
+    ))
+}
+
+fn padded(len: usize) -> usize {
+    len.next_multiple_of(HEADER_LEN)
+}
+
+/// CRC-16/XMODEM, which MacBinary II stores over the first 124 bytes of the
+/// header.
+fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in bytes {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A MacBinary II file named `Movie` with the given forks.
+    fn macbinary(data: &[u8], resource: &[u8]) -> Vec<u8> {
+        let mut header = [0u8; HEADER_LEN];
+        header[1] = 5;
+        header[2..7].copy_from_slice(b"Movie");
+        header[65..69].copy_from_slice(b"MV93");
+        header[69..73].copy_from_slice(b"MD93");
+        header[83..87].copy_from_slice(&(data.len() as u32).to_be_bytes());
+        header[87..91].copy_from_slice(&(resource.len() as u32).to_be_bytes());
+        header[122] = 129;
+        header[123] = 129;
+        let crc = crc16(&header[..124]);
+        header[124..126].copy_from_slice(&crc.to_be_bytes());
+
+        let mut buf = header.to_vec();
+        buf.extend(data);
+        buf.resize(HEADER_LEN + padded(data.len()), 0);
+        buf.extend(resource);
+        buf
+    }
+
+    #[test]
+    fn reads_both_forks() {
+        let buf = macbinary(b"data fork", b"resource fork");
+        assert!(is_macbinary(&buf));
+
+        let file = read(&buf).unwrap();
+        assert_eq!(file.name, "Movie");
+        assert_eq!(file.file_type, Tag(i32::from_be_bytes(*b"MV93")));
+        assert_eq!(file.creator, Tag(i32::from_be_bytes(*b"MD93")));
+        assert_eq!(&*file.data_fork, b"data fork");
+        assert_eq!(&*file.resource_fork, b"resource fork");
+    }
+
+    #[test]
+    fn skips_the_secondary_header() {
+        let mut buf = macbinary(b"data fork", b"");
+        buf[120..122].copy_from_slice(&3u16.to_be_bytes());
+        let crc = crc16(&buf[..124]);
+        buf[124..126].copy_from_slice(&crc.to_be_bytes());
+        buf.splice(HEADER_LEN..HEADER_LEN, [0xaa; HEADER_LEN]);
+
+        assert_eq!(&*read(&buf).unwrap().data_fork, b"data fork");
+    }
+
+    #[test]
+    fn rejects_a_truncated_fork() {
+        let mut buf = macbinary(b"data fork", b"resource fork");
+        buf.truncate(buf.len() - 1);
+
+        assert!(!is_macbinary(&buf));
+        assert_eq!(read(&buf).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_forks_larger_than_the_format_allows() {
+        let mut buf = macbinary(b"", b"");
+        buf[87..91].copy_from_slice(&u32::MAX.to_be_bytes());
+        let crc = crc16(&buf[..124]);
+        buf[124..126].copy_from_slice(&crc.to_be_bytes());
+
+        assert!(!is_macbinary(&buf));
+    }
+
+    #[test]
+    fn rejects_headers_that_break_the_format() {
+        let buf = macbinary(b"data fork", b"");
+
+        let mut name_len = buf.clone();
+        name_len[1] = 64;
+        assert!(!is_macbinary(&name_len));
+
+        let mut zero_byte = buf.clone();
+        zero_byte[74] = 1;
+        assert!(!is_macbinary(&zero_byte));
+
+        // Without a valid CRC or signature, byte 82 must be zero as in
+        // MacBinary I.
+        let mut bad_crc = buf.clone();
+        bad_crc[82] = 1;
+        assert!(!is_macbinary(&bad_crc));
+
+        assert!(!is_macbinary(&buf[..HEADER_LEN - 1]));
+    }
+}
@@ -0,0 +1,122 @@
+//! Classic Mac OS files, whose resource forks survive on other systems only
+//! inside MacBinary, AppleSingle or AppleDouble containers.
+
+mod apple_single;
+mod macbinary;
+mod resource_fork;
+
+pub use apple_single::{is_apple_double, is_apple_single};
+pub use macbinary::is_macbinary;
+pub use resource_fork::{Resource, ResourceFork};
+
+use std::{
+    fs,
+    io::Result,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{reader::Reader, riff::tags::Tag};
+
+/// A file with its Mac OS forks and Finder type and creator. The forks are
+/// shared, so that readers over them can outlive the file.
+#[derive(Debug, Clone, Default)]
+pub struct MacFile {
+    pub name: String,
+    pub file_type: Tag,
+    pub creator: Tag,
+    pub data_fork: Arc<[u8]>,
+    pub resource_fork: Arc<[u8]>,
+}
+
+impl MacFile {
+    /// Unwraps a MacBinary or AppleSingle file, or returns `None` if `buf` is
+    /// neither.
+    pub fn read(buf: &[u8]) -> Result<Option<Self>> {
+        if macbinary::is_macbinary(buf) {
+            macbinary::read(buf).map(Some)
+        } else if apple_single::is_apple_single(buf) {
+            apple_single::read(buf).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn read_macbinary(buf: &[u8]) -> Result<Self> {
+        macbinary::read(buf)
+    }
+
+    pub fn read_apple_single(buf: &[u8]) -> Result<Self> {
+        apple_single::read(buf)
+    }
+
+    /// Opens the file at `path`, unwrapping it if it is a MacBinary or
+    /// AppleSingle file. Any other file becomes the data fork, with the
+    /// resource fork taken from its AppleDouble header file if there is one,
+    /// either `._name` next to it or the copy under `__MACOSX` that macOS
+    /// adds to zip archives.
+    pub fn open(path: &Path) -> Result<Self> {
+        let buf = fs::read(path)?;
+
+        if let Some(file) = Self::read(&buf)? {
+            return Ok(file);
+        }
+
+        let mut file = Self::open_apple_double(path)?;
+        file.data_fork = buf.into();
+
+        Ok(file)
+    }
+
+    /// Reads the forks of `path` other than its data fork, from its
+    /// AppleDouble header file if it has one, leaving the data fork empty.
+    pub fn open_apple_double(path: &Path) -> Result<Self> {
+        let mut file = match apple_double_path(path).map(fs::read).transpose()? {
+            Some(header) if apple_single::is_apple_double(&header) => apple_single::read(&header)?,
+            _ => MacFile::default(),
+        };
+
+        if file.name.is_empty() {
+            file.name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+        }
+
+        Ok(file)
+    }
+
+    pub fn has_resource_fork(&self) -> bool {
+        !self.resource_fork.is_empty()
+    }
+
+    pub fn data_fork_reader(&self) -> Reader<'static> {
+        Reader::from_shared(self.data_fork.clone())
+    }
+
+    pub fn read_resource_fork(&self) -> Result<ResourceFork<'static>> {
+        ResourceFork::read(Reader::from_shared(self.resource_fork.clone()))
+    }
+}
+
+/// Finds the AppleDouble header file of `path`.
+fn apple_double_path(path: &Path) -> Option<PathBuf> {
+    let name = format!("._{}", path.file_name()?.to_string_lossy());
+    let dir = path.parent().unwrap_or(Path::new(""));
+
+    let sibling = dir.join(&name);
+    if sibling.is_file() {
+        return Some(sibling);
+    }
+
+    // `__MACOSX` sits at the root of the archive and mirrors its tree.
+    for ancestor in dir.ancestors() {
+        let relative = dir.strip_prefix(ancestor).ok()?;
+        let candidate = ancestor.join("__MACOSX").join(relative).join(&name);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+    }
+
+    None
+}
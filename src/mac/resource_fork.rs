@@ -0,0 +1,259 @@
+use std::io::{Error, ErrorKind, Result, Seek, SeekFrom};
+
+use crate::{
+    reader::{ReadBytesExt, Reader, TextEncoding},
+    riff::tags::Tag,
+};
+
+/// A resource in a Mac resource fork.
+#[derive(Debug, Clone)]
+pub struct Resource<'a> {
+    pub tag: Tag,
+    pub id: i16,
+    pub name: Option<String>,
+    reader: Reader<'a>,
+}
+
+impl<'a> Resource<'a> {
+    pub(crate) fn new(tag: Tag, id: i16, name: Option<String>, reader: Reader<'a>) -> Self {
+        Self {
+            tag,
+            id,
+            name,
+            reader,
+        }
+    }
+
+    /// A reader over the contents of the resource.
+    pub fn reader(&self) -> Reader<'a> {
+        self.reader.clone()
+    }
+
+    pub fn data(&self) -> Result<&[u8]> {
+        self.reader.as_slice()
+    }
+
+    pub fn len(&self) -> usize {
+        self.reader.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reader.is_empty()
+    }
+}
+
+/// The resources of a Mac resource fork, in the order of its map.
+#[derive(Debug, Clone)]
+pub struct ResourceFork<'a> {
+    resources: Vec<Resource<'a>>,
+}
+
+impl<'a> ResourceFork<'a> {
+    pub fn read(fork: Reader<'a>) -> Result<Self> {
+        let mut r = fork;
+        r.set_encoding(TextEncoding::MacRoman);
+
+        let data_offset = r.read_be_u32()? as usize;
+        let map_offset = r.read_be_u32()? as usize;
+        let _data_len = r.read_be_u32()?;
+        let _map_len = r.read_be_u32()?;
+
+        // The map starts with a copy of the header, a handle and a file
+        // reference number, which are only meaningful in memory.
+        let type_list_offset = map_offset + r.read_be_u16_at(map_offset as u64 + 24)? as usize;
+        let name_list_offset = map_offset + r.read_be_u16_at(map_offset as u64 + 26)? as usize;
+
+        let type_count = r.read_be_u16_at(type_list_offset as u64)?.wrapping_add(1);
+
+        let mut resources = Vec::new();
+        for i in 0..type_count as usize {
+            r.seek(SeekFrom::Start((type_list_offset + 2 + i * 8) as u64))?;
+            let tag = Tag(r.read_be_i32()?);
+            let count = r.read_be_u16()?.wrapping_add(1);
+            let ref_list_offset = type_list_offset + r.read_be_u16()? as usize;
+
+            for j in 0..count as usize {
+                r.seek(SeekFrom::Start((ref_list_offset + j * 12) as u64))?;
+                let id = r.read_be_i16()?;
+                let name_offset = r.read_be_u16()?;
+                let attributes_and_offset = r.read_be_u32()?;
+
+                let name = if name_offset == 0xffff {
+                    None
+                } else {
+                    r.seek(SeekFrom::Start(
+                        (name_list_offset + name_offset as usize) as u64,
+                    ))?;
+                    Some(r.read_pascal_str()?)
+                };
+
+                let position = data_offset + (attributes_and_offset & 0xff_ffff) as usize;
+                r.seek(SeekFrom::Start(position as u64))?;
+                let len = r.read_be_u32()? as usize;
+                if position + 4 + len > r.len() {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Resource '{tag}' {id} extends past the end of the fork"),
+                    ));
+                }
+                let reader = r.subset(position + 4, len)?;
+
+                resources.push(Resource::new(tag, id, name, reader));
+            }
+        }
+
+        Ok(ResourceFork { resources })
+    }
+
+    pub fn resources(&self) -> &[Resource<'a>] {
+        &self.resources
+    }
+
+    pub fn get(&self, tag: Tag, id: i16) -> Option<&Resource<'a>> {
+        self.resources
+            .iter()
+            .find(|resource| resource.tag == tag && resource.id == id)
+    }
+
+    pub fn first_with_tag(&self, tag: Tag) -> Option<&Resource<'a>> {
+        self.resources.iter().find(|resource| resource.tag == tag)
+    }
+
+    pub fn display(&self) {
+        println!("Resources:");
+        println!("==========================================");
+        println!("| tag    |     id |     size | name");
+        println!("+--------+--------+----------+------------");
+        for resource in &self.resources {
+            println!(
+                "| {:6} | {:6} | {:8} | {}",
+                resource.tag.to_string(),
+                resource.id,
+                resource.len(),
+                resource.name.as_deref().unwrap_or_default()
+            );
+        }
+        println!("+--------+--------+----------+------------");
+        println!();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TAG_STXT: Tag = Tag(i32::from_be_bytes(*b"STXT"));
+    const TAG_VWSC: Tag = Tag(i32::from_be_bytes(*b"VWSC"));
+
+    /// A resource fork with an `STXT` resource named `Greeting` and a
+    /// nameless `VWSC` resource.
+    fn resource_fork() -> Vec<u8> {
+        let mut data = Vec::new();
+        for contents in [&b"Hello"[..], b"score"] {
+            data.extend((contents.len() as u32).to_be_bytes());
+            data.extend(contents);
+        }
+
+        // The type list holds two types, whose reference lists follow it.
+        let mut type_list = Vec::new();
+        type_list.extend(1u16.to_be_bytes());
+        for (i, tag) in [TAG_STXT, TAG_VWSC].into_iter().enumerate() {
+            type_list.extend(tag.0.to_be_bytes());
+            type_list.extend(0u16.to_be_bytes());
+            type_list.extend((2 + 2 * 8 + i as u16 * 12).to_be_bytes());
+        }
+        let references = [(1025i16, 0u16, 0u32), (1024, 0xffff, 9)];
+        for (id, name_offset, data_offset) in references {
+            type_list.extend(id.to_be_bytes());
+            type_list.extend(name_offset.to_be_bytes());
+            type_list.extend(data_offset.to_be_bytes());
+            type_list.extend([0; 4]);
+        }
+
+        let mut name_list = vec![8];
+        name_list.extend(b"Greeting");
+
+        let map_offset = 16 + data.len();
+        let mut map = vec![0; 24];
+        map.extend(28u16.to_be_bytes());
+        map.extend((28 + type_list.len() as u16).to_be_bytes());
+        map.extend(type_list);
+        map.extend(name_list);
+
+        let mut fork = Vec::new();
+        fork.extend(16u32.to_be_bytes());
+        fork.extend((map_offset as u32).to_be_bytes());
+        fork.extend((data.len() as u32).to_be_bytes());
+        fork.extend((map.len() as u32).to_be_bytes());
+        fork.extend(data);
+        fork.extend(map);
+        fork
+    }
+
+    #[test]
+    fn reads_the_resources() {
+        let buf = resource_fork();
+        let fork = ResourceFork::read(Reader::new(&buf)).unwrap();
+
+        let resources: Vec<_> = fork
+            .resources()
+            .iter()
+            .map(|resource| {
+                (
+                    resource.tag,
+                    resource.id,
+                    resource.name.as_deref(),
+                    resource.data().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            resources,
+            [
+                (TAG_STXT, 1025, Some("Greeting"), &b"Hello"[..]),
+                (TAG_VWSC, 1024, None, b"score"),
+            ]
+        );
+
+        assert_eq!(fork.get(TAG_VWSC, 1024).unwrap().len(), 5);
+        assert!(fork.get(TAG_VWSC, 1025).is_none());
+        assert_eq!(fork.first_with_tag(TAG_STXT).unwrap().id, 1025);
+    }
+
+    #[test]
+    fn rejects_a_resource_past_the_end_of_the_fork() {
+        let mut buf = resource_fork();
+        // Make the length of the second resource run past the map.
+        buf[25..29].copy_from_slice(&1000u32.to_be_bytes());
+
+        let err = ResourceFork::read(Reader::new(&buf)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_a_data_offset_past_the_end_of_the_fork() {
+        let mut buf = resource_fork();
+        let len = buf.len() as u32;
+        buf[0..4].copy_from_slice(&len.to_be_bytes());
+
+        assert!(ResourceFork::read(Reader::new(&buf)).is_err());
+    }
+
+    #[test]
+    fn rejects_a_map_offset_past_the_end_of_the_fork() {
+        let mut buf = resource_fork();
+        let len = buf.len() as u32;
+        buf[4..8].copy_from_slice(&len.to_be_bytes());
+
+        assert!(ResourceFork::read(Reader::new(&buf)).is_err());
+    }
+
+    #[test]
+    fn rejects_a_truncated_map() {
+        let buf = resource_fork();
+        // Cut the name list short.
+        let buf = &buf[..buf.len() - 4];
+
+        assert!(ResourceFork::read(Reader::new(buf)).is_err());
+    }
+}
@@ -1,6 +1,6 @@
-use std::io::{Error, ErrorKind, Result};
+use std::io::Result;
 
-use encoding::Encoding;
+use super::TextEncoding;
 
 #[derive(Copy, Clone, Debug)]
 pub enum ByteOrder {
@@ -108,13 +108,63 @@ pub trait ReadBytesExt: std::io::Read {
         Ok(i32::from_be_bytes(buf))
     }
 
-    fn read_pascal_str(&mut self) -> Result<String> {
+    #[inline]
+    fn read_be_u64(&mut self) -> Result<u64> {
+        let mut buf = [0; 8];
+        self.read_exact(&mut buf)?;
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    /// Reads a big-endian 80-bit IEEE 754 extended precision float, as used
+    /// by the classic Mac OS, and converts it to an `f64`.
+    fn read_be_f80(&mut self) -> Result<f64> {
+        let sign_exponent = self.read_be_u16()?;
+        let mantissa = self.read_be_u64()?;
+
+        let sign = if sign_exponent & 0x8000 != 0 {
+            -1.0
+        } else {
+            1.0
+        };
+        let exponent = (sign_exponent & 0x7fff) as i32;
+
+        if exponent == 0 && mantissa == 0 {
+            return Ok(sign * 0.0);
+        }
+
+        if exponent == 0x7fff {
+            return Ok(if mantissa << 1 == 0 {
+                sign * f64::INFINITY
+            } else {
+                f64::NAN
+            });
+        }
+
+        Ok(sign * (mantissa as f64) * 2f64.powi(exponent - 16383 - 63))
+    }
+
+    /// Reads a big-endian variable-length integer with 7 bits per byte, as used
+    /// in Afterburner-compressed movies.
+    fn read_var_u32(&mut self) -> Result<u32> {
+        let mut value: u32 = 0;
+
+        loop {
+            let b = self.read_u8()?;
+            value = (value << 7) | (b & 0x7f) as u32;
+
+            if b & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn read_pascal_str(&mut self, encoding: TextEncoding) -> Result<String> {
         let len = self.read_u8()?;
 
-        self.read_fixed_str(len as usize)
+        self.read_fixed_str(len as usize, encoding)
     }
 
-    fn read_fixed_str(&mut self, len: usize) -> Result<String> {
+    fn read_fixed_str(&mut self, len: usize, encoding: TextEncoding) -> Result<String> {
         let mut bytes = Vec::with_capacity(len);
         let mut bytes_read = 0;
         let mut found_end_of_string = false;
@@ -131,11 +181,7 @@ pub trait ReadBytesExt: std::io::Read {
             }
         }
 
-        let s = encoding::all::ISO_8859_1
-            .decode(&bytes, encoding::DecoderTrap::Ignore)
-            .map_err(|_| Error::new(ErrorKind::InvalidData, "Unable to decode Pascal string"))?;
-
-        Ok(s)
+        Ok(encoding.decode(&bytes))
     }
 }
 
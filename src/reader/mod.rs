@@ -1,31 +1,92 @@
 mod bytes_ext;
+mod text_encoding;
+mod window;
 
 pub use bytes_ext::{ByteOrder, ReadBytesExt};
+pub use text_encoding::TextEncoding;
 
 use std::{
     fmt::Debug,
-    io::{Cursor, Read, Result, Seek, SeekFrom},
+    fs::File,
+    io::{Error, ErrorKind, Read, Result, Seek, SeekFrom},
+    path::Path,
+    sync::Arc,
 };
 
+use memmap2::Mmap;
+use window::{Data, Stream, Window};
+
+/// Reads from a buffer, a memory-mapped file or a stream. Subsets share what
+/// they read from rather than copy it, and subsets of a stream are only
+/// loaded once they are read.
 #[derive(Clone)]
 pub struct Reader<'a> {
-    inner: Cursor<&'a [u8]>,
+    window: Window<'a>,
+    position: u64,
     byte_order: ByteOrder,
+    encoding: TextEncoding,
 }
 
 impl Debug for Reader<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Reader")
             .field("byte_order", &self.byte_order)
+            .field("encoding", &self.encoding)
             .finish()
     }
 }
 
 impl<'a> Reader<'a> {
     pub fn new(buf: &'a [u8]) -> Self {
+        Self::from_window(Window::new(Data::Borrowed(buf), buf.len()))
+    }
+
+    /// A reader that keeps `buf` alive itself, so that it can outlive the
+    /// code that loaded it.
+    pub fn from_shared(buf: Arc<[u8]>) -> Self {
+        let len = buf.len();
+        Self::from_window(Window::new(Data::Shared(buf), len))
+    }
+
+    pub fn from_vec(buf: Vec<u8>) -> Self {
+        Self::from_shared(buf.into())
+    }
+
+    /// Maps the file at `path` into memory, so that only the parts that are
+    /// read are loaded, by the operating system, as they are read.
+    pub fn map_file(path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+
+        // SAFETY: Changing the file while it is mapped changes what the
+        // reader sees, and truncating it makes reads past the new end fault.
+        // Movies are not expected to change while they are being read.
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        let len = mmap.len();
+        Ok(Self::from_window(Window::new(
+            Data::Mapped(Arc::new(mmap)),
+            len,
+        )))
+    }
+
+    /// Reads from `source` as needed, for files that cannot be mapped. Small
+    /// subsets, like most chunks, are loaded whole the first time they are
+    /// read.
+    pub fn from_stream<R: Read + Seek + Send + 'static>(source: R) -> Result<Self> {
+        let (stream, len) = Stream::new(source)?;
+        let len = usize::try_from(len)
+            .map_err(|_| Error::new(ErrorKind::InvalidData, "The stream is too long to read"))?;
+
+        let data = Data::Streamed(Arc::new(stream), Arc::default());
+        Ok(Self::from_window(Window::new(data, len)))
+    }
+
+    fn from_window(window: Window<'a>) -> Self {
         Self {
-            inner: Cursor::new(buf),
+            window,
+            position: 0,
             byte_order: ByteOrder::LittleEndian,
+            encoding: TextEncoding::default(),
         }
     }
 
@@ -37,12 +98,60 @@ impl<'a> Reader<'a> {
         self.byte_order
     }
 
-    pub fn subset(&self, position: usize, size: usize) -> Self {
-        let sub_inner = &self.inner.get_ref()[position..position + size];
-        Self {
-            inner: Cursor::new(sub_inner),
-            byte_order: self.byte_order,
+    pub fn set_encoding(&mut self, encoding: TextEncoding) {
+        self.encoding = encoding;
+    }
+
+    pub fn encoding(&self) -> TextEncoding {
+        self.encoding
+    }
+
+    /// A reader over `size` bytes from `position`, which must be within the
+    /// data this reader sees.
+    pub fn subset(&self, position: usize, size: usize) -> Result<Self> {
+        if position
+            .checked_add(size)
+            .is_none_or(|end| end > self.len())
+        {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                format!(
+                    "{size} bytes at {position} extend past the end of the data ({} bytes)",
+                    self.len()
+                ),
+            ));
         }
+
+        Ok(Self {
+            window: self.window.subset(position, size),
+            position: 0,
+            byte_order: self.byte_order,
+            encoding: self.encoding,
+        })
+    }
+
+    /// The length of the data the reader sees.
+    pub fn len(&self) -> usize {
+        self.window.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The data the reader sees, regardless of its position. Readers over a
+    /// stream load all of it.
+    pub fn as_slice(&self) -> Result<&[u8]> {
+        self.window.load()
+    }
+
+    /// Up to `len` bytes from the start of the data, regardless of the
+    /// reader's position.
+    pub fn peek(&self, len: usize) -> Result<Vec<u8>> {
+        let mut buf = vec![0; len.min(self.len())];
+        self.subset(0, buf.len())?.read_exact(&mut buf)?;
+
+        Ok(buf)
     }
 
     pub fn stream_remain(&mut self) -> Result<u64> {
@@ -50,7 +159,7 @@ impl<'a> Reader<'a> {
     }
 
     pub fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Result<usize> {
-        self.inner.read_to_end(buf)
+        Read::read_to_end(self, buf)
     }
 
     pub fn read_u8_at(&mut self, position: u64) -> Result<u8> {
@@ -58,12 +167,19 @@ impl<'a> Reader<'a> {
         self.read_u8()
     }
 
+    pub fn read_i8_at(&mut self, position: u64) -> Result<i8> {
+        self.seek(SeekFrom::Start(position))?;
+        self.read_i8()
+    }
+
     pub fn read_u16(&mut self) -> Result<u16> {
-        self.inner.read_u16(self.byte_order)
+        let byte_order = self.byte_order;
+        ReadBytesExt::read_u16(self, byte_order)
     }
 
     pub fn read_i16(&mut self) -> Result<i16> {
-        self.inner.read_i16(self.byte_order)
+        let byte_order = self.byte_order;
+        ReadBytesExt::read_i16(self, byte_order)
     }
 
     pub fn read_i16_at(&mut self, position: u64) -> Result<i16> {
@@ -78,15 +194,27 @@ impl<'a> Reader<'a> {
 
     pub fn read_be_i16_at(&mut self, position: u64) -> Result<i16> {
         self.seek(SeekFrom::Start(position))?;
-        self.inner.read_i16(ByteOrder::BigEndian)
+        self.read_be_i16()
     }
 
     pub fn read_u32(&mut self) -> Result<u32> {
-        self.inner.read_u32(self.byte_order)
+        let byte_order = self.byte_order;
+        ReadBytesExt::read_u32(self, byte_order)
     }
 
     pub fn read_i32(&mut self) -> Result<i32> {
-        self.inner.read_i32(self.byte_order)
+        let byte_order = self.byte_order;
+        ReadBytesExt::read_i32(self, byte_order)
+    }
+
+    pub fn read_pascal_str(&mut self) -> Result<String> {
+        let encoding = self.encoding;
+        ReadBytesExt::read_pascal_str(self, encoding)
+    }
+
+    pub fn read_fixed_str(&mut self, len: usize) -> Result<String> {
+        let encoding = self.encoding;
+        ReadBytesExt::read_fixed_str(self, len, encoding)
     }
 
     pub fn hex_dump(&mut self) -> Result<()> {
@@ -123,12 +251,29 @@ impl<'a> Reader<'a> {
 
 impl Read for Reader<'_> {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
-        self.inner.read(buf)
+        let position = usize::try_from(self.position).unwrap_or(usize::MAX);
+        let len = self.window.read_at(position, buf)?;
+        self.position += len as u64;
+
+        Ok(len)
     }
 }
 
 impl Seek for Reader<'_> {
     fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
-        self.inner.seek(pos)
+        let (base, offset) = match pos {
+            SeekFrom::Start(position) => (position, 0),
+            SeekFrom::End(offset) => (self.len() as u64, offset),
+            SeekFrom::Current(offset) => (self.position, offset),
+        };
+
+        self.position = base.checked_add_signed(offset).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "Seek to a negative or overflowing position",
+            )
+        })?;
+
+        Ok(self.position)
     }
 }
@@ -0,0 +1,76 @@
+use std::{
+    fmt,
+    io::{Error, ErrorKind, Result},
+    str::FromStr,
+};
+
+use encoding::{DecoderTrap, EncoderTrap, Encoding, all};
+
+/// The character set of the strings in a movie, which is that of the system
+/// it was authored on.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TextEncoding {
+    #[default]
+    Latin1,
+    MacRoman,
+    Windows1252,
+    ShiftJis,
+}
+
+impl TextEncoding {
+    fn encoding(self) -> &'static dyn Encoding {
+        match self {
+            TextEncoding::Latin1 => all::ISO_8859_1,
+            TextEncoding::MacRoman => all::MAC_ROMAN,
+            TextEncoding::Windows1252 => all::WINDOWS_1252,
+            TextEncoding::ShiftJis => all::WINDOWS_31J,
+        }
+    }
+
+    /// Decodes `bytes`, leaving out any that do not form a character.
+    pub fn decode(self, bytes: &[u8]) -> String {
+        self.encoding()
+            .decode(bytes, DecoderTrap::Ignore)
+            .unwrap_or_default()
+    }
+
+    pub fn encode(self, s: &str) -> Result<Vec<u8>> {
+        self.encoding().encode(s, EncoderTrap::Strict).map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unable to encode string {s:?} as {self}"),
+            )
+        })
+    }
+}
+
+impl fmt::Display for TextEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TextEncoding::Latin1 => "latin1",
+            TextEncoding::MacRoman => "mac-roman",
+            TextEncoding::Windows1252 => "windows-1252",
+            TextEncoding::ShiftJis => "shift-jis",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for TextEncoding {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().replace('_', "-").as_str() {
+            "latin1" | "latin-1" | "iso-8859-1" => Ok(TextEncoding::Latin1),
+            "mac-roman" | "macroman" | "macintosh" => Ok(TextEncoding::MacRoman),
+            "windows-1252" | "cp1252" => Ok(TextEncoding::Windows1252),
+            "shift-jis" | "shiftjis" | "sjis" | "windows-31j" | "cp932" => {
+                Ok(TextEncoding::ShiftJis)
+            }
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown text encoding {s:?}"),
+            )),
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::{
+    io::{BufReader, Read, Result, Seek, SeekFrom},
+    ops::Range,
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+};
+
+use memmap2::Mmap;
+
+/// Windows over a stream up to this long are loaded whole the first time they
+/// are read. Longer ones, such as the whole file, are read piece by piece.
+const LOAD_LIMIT: usize = 1 << 20;
+
+trait Source: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> Source for T {}
+
+/// A stream shared by all the readers made from it. It remembers where it is,
+/// so that reading on from the last read does not throw its buffer away.
+pub(super) struct Stream {
+    state: Mutex<StreamState>,
+}
+
+struct StreamState {
+    source: Box<dyn Source>,
+    position: Option<u64>,
+}
+
+impl Stream {
+    /// Wraps `source`, returning it with its length.
+    pub(super) fn new<R: Read + Seek + Send + 'static>(source: R) -> Result<(Self, u64)> {
+        let mut source = BufReader::new(source);
+        let len = source.seek(SeekFrom::End(0))?;
+
+        let stream = Self {
+            state: Mutex::new(StreamState {
+                source: Box::new(source),
+                position: Some(len),
+            }),
+        };
+        Ok((stream, len))
+    }
+
+    fn read_exact_at(&self, position: u64, buf: &mut [u8]) -> Result<()> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+
+        match state.position {
+            Some(current) if current == position => {}
+            Some(current) => state
+                .source
+                .seek_relative(position as i64 - current as i64)?,
+            None => {
+                state.source.seek(SeekFrom::Start(position))?;
+            }
+        }
+
+        // After a failed read the stream could be anywhere.
+        state.position = None;
+        state.source.read_exact(buf)?;
+        state.position = Some(position + buf.len() as u64);
+
+        Ok(())
+    }
+}
+
+/// The bytes a reader reads from, shared between all the readers made from
+/// the same buffer, file or stream.
+#[derive(Clone)]
+pub(super) enum Data<'a> {
+    Borrowed(&'a [u8]),
+    Shared(Arc<[u8]>),
+    Mapped(Arc<Mmap>),
+    /// A stream, with the bytes of the window once they have been loaded.
+    Streamed(Arc<Stream>, Arc<OnceLock<Arc<[u8]>>>),
+}
+
+/// The part of the data a reader sees.
+#[derive(Clone)]
+pub(super) struct Window<'a> {
+    data: Data<'a>,
+    range: Range<usize>,
+}
+
+impl<'a> Window<'a> {
+    pub(super) fn new(data: Data<'a>, len: usize) -> Self {
+        Self {
+            data,
+            range: 0..len,
+        }
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.range.len()
+    }
+
+    /// The bytes of the window, loading them first if they come from a stream.
+    pub(super) fn load(&self) -> Result<&[u8]> {
+        match &self.data {
+            Data::Borrowed(data) => Ok(&data[self.range.clone()]),
+            Data::Shared(data) => Ok(&data[self.range.clone()]),
+            Data::Mapped(data) => Ok(&data[self.range.clone()]),
+            Data::Streamed(stream, loaded) => {
+                if let Some(bytes) = loaded.get() {
+                    return Ok(bytes);
+                }
+
+                let mut bytes = vec![0; self.range.len()];
+                stream.read_exact_at(self.range.start as u64, &mut bytes)?;
+                Ok(loaded.get_or_init(|| bytes.into()))
+            }
+        }
+    }
+
+    /// Reads as much of `buf` as the window holds from `position` on.
+    pub(super) fn read_at(&self, position: usize, buf: &mut [u8]) -> Result<usize> {
+        let len = buf.len().min(self.len().saturating_sub(position));
+        if len == 0 {
+            return Ok(0);
+        }
+
+        match &self.data {
+            Data::Streamed(stream, loaded) if loaded.get().is_none() && self.len() > LOAD_LIMIT => {
+                let start = (self.range.start + position) as u64;
+                stream.read_exact_at(start, &mut buf[..len])?;
+            }
+            _ => buf[..len].copy_from_slice(&self.load()?[position..position + len]),
+        }
+
+        Ok(len)
+    }
+
+    pub(super) fn subset(&self, position: usize, size: usize) -> Self {
+        assert!(position + size <= self.len());
+
+        // Once loaded, a window over a stream hands out its own bytes.
+        if let Data::Streamed(_, loaded) = &self.data
+            && let Some(bytes) = loaded.get()
+        {
+            return Self {
+                data: Data::Shared(bytes.clone()),
+                range: position..position + size,
+            };
+        }
+
+        let data = match &self.data {
+            Data::Streamed(stream, _) => Data::Streamed(stream.clone(), Arc::default()),
+            data => data.clone(),
+        };
+
+        let start = self.range.start + position;
+        Self {
+            data,
+            range: start..start + size,
+        }
+    }
+}
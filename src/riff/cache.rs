@@ -0,0 +1,335 @@
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind, Result},
+    sync::Arc,
+};
+
+use crate::gfx;
+
+use super::{
+    RiffFile,
+    cast_members::{CastMember, CastMemberId, PurgePriority},
+    chunks::AnyChunk,
+};
+
+/// How many bytes of decoded chunks and images a movie keeps by default.
+pub const DEFAULT_CACHE_BUDGET: usize = 64 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum CacheKey {
+    Chunk(u32),
+    CastMember(CastMemberId),
+    Image(CastMemberId),
+}
+
+#[derive(Debug, Clone)]
+enum Cached {
+    Chunk(Arc<AnyChunk>),
+    CastMember(Arc<CastMember>),
+    Image(Arc<gfx::IndexedImage>),
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    value: Cached,
+    size: usize,
+    purge_priority: PurgePriority,
+    last_used: u64,
+}
+
+/// Decoded chunks, cast members and images, up to a budget of bytes. When
+/// it is exceeded, entries are evicted by purge priority, the least recently
+/// used first, the way Director unloads cast members. Entries of members
+/// that are never purged go last, only once nothing else is left to evict,
+/// so that they cannot take the cache over its budget.
+#[derive(Debug)]
+pub(crate) struct ChunkCache {
+    entries: HashMap<CacheKey, CacheEntry>,
+    budget: usize,
+    size: usize,
+    clock: u64,
+}
+
+impl ChunkCache {
+    pub(crate) fn new(budget: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            budget,
+            size: 0,
+            clock: 0,
+        }
+    }
+
+    fn get(&mut self, key: CacheKey) -> Option<Cached> {
+        self.clock += 1;
+
+        let entry = self.entries.get_mut(&key)?;
+        entry.last_used = self.clock;
+
+        Some(entry.value.clone())
+    }
+
+    fn insert(&mut self, key: CacheKey, value: Cached, size: usize, purge_priority: PurgePriority) {
+        self.clock += 1;
+
+        let entry = CacheEntry {
+            value,
+            size,
+            purge_priority,
+            last_used: self.clock,
+        };
+        if let Some(old) = self.entries.insert(key, entry) {
+            self.size -= old.size;
+        }
+        self.size += size;
+
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.size > self.budget {
+            let victim = self
+                .entries
+                .iter()
+                .map(|(&key, entry)| {
+                    let rank = match entry.purge_priority {
+                        PurgePriority::Next => 0,
+                        PurgePriority::Normal => 1,
+                        PurgePriority::Last => 2,
+                        PurgePriority::Never => 3,
+                    };
+                    (key, (rank, entry.last_used))
+                })
+                .min_by_key(|&(_, order)| order)
+                .map(|(key, _)| key);
+
+            let Some(entry) = victim.and_then(|key| self.entries.remove(&key)) else {
+                break;
+            };
+            self.size -= entry.size;
+        }
+    }
+
+    pub(crate) fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+        self.evict();
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.size = 0;
+    }
+}
+
+impl RiffFile<'_> {
+    /// Sets how many bytes of decoded chunks and images the movie keeps,
+    /// evicting what no longer fits. Sizes are estimated from the chunks
+    /// things were read from, the linked files cast members loaded, and the
+    /// pixels of images.
+    pub fn set_cache_budget(&mut self, budget: usize) {
+        self.cache_mut().set_budget(budget);
+    }
+
+    pub fn cache_budget(&self) -> usize {
+        self.cache().budget
+    }
+
+    /// The estimated size of what the cache holds.
+    pub fn cached_bytes(&self) -> usize {
+        self.cache().size
+    }
+
+    /// Empties the cache. Handles given out before stay valid.
+    pub fn clear_cache(&self) {
+        self.cache().clear();
+    }
+
+    /// Chunk `id`, read with `read_any_chunk` the first time and shared from
+    /// the cache after that.
+    pub fn cached_chunk(&self, id: u32) -> Result<Arc<AnyChunk>> {
+        if let Some(Cached::Chunk(chunk)) = self.cache().get(CacheKey::Chunk(id)) {
+            return Ok(chunk);
+        }
+
+        let chunk = Arc::new(self.read_any_chunk(id)?);
+        let size = self.chunks_len([id]);
+        self.cache().insert(
+            CacheKey::Chunk(id),
+            Cached::Chunk(chunk.clone()),
+            size,
+            PurgePriority::Normal,
+        );
+
+        Ok(chunk)
+    }
+
+    /// Cast member `id` with its media, loaded with `load_cast_member` the
+    /// first time and shared from the cache after that.
+    pub fn cached_cast_member(&self, id: CastMemberId) -> Result<Arc<CastMember>> {
+        if let Some(Cached::CastMember(cast_member)) = self.cache().get(CacheKey::CastMember(id)) {
+            return Ok(cast_member);
+        }
+
+        let cast_member = Arc::new(self.load_cast_member(id)?);
+
+        // The member's CASt chunk, its media and whatever else it owns, and
+        // the linked file it loaded.
+        let chunks_len = match self.cast_table().cast_member_chunk_id(id) {
+            Some(chunk_id) => self.chunks_len(
+                std::iter::once(chunk_id).chain(
+                    self.key_table()
+                        .descendants(chunk_id)
+                        .into_iter()
+                        .map(|entry| entry.id()),
+                ),
+            ),
+            None => 0,
+        };
+        let linked_len = cast_member
+            .linked_media()
+            .and_then(|linked| linked.data.as_ref())
+            .map_or(0, Vec::len);
+        let size = chunks_len + linked_len;
+        self.cache().insert(
+            CacheKey::CastMember(id),
+            Cached::CastMember(cast_member.clone()),
+            size,
+            cast_member.info.purge_priority,
+        );
+
+        Ok(cast_member)
+    }
+
+    /// The decoded image of bitmap cast member `id`, decoded the first time
+    /// and shared from the cache after that. `None` if the member has no
+    /// image to decode.
+    pub fn cached_bitmap_image(&self, id: CastMemberId) -> Result<Option<Arc<gfx::IndexedImage>>> {
+        if let Some(Cached::Image(image)) = self.cache().get(CacheKey::Image(id)) {
+            return Ok(Some(image));
+        }
+
+        let cast_member = self.cached_cast_member(id)?;
+        let Some(bitmap) = cast_member.as_bitmap() else {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Cast member {} is not a bitmap", id.id()),
+            ));
+        };
+        let Some(image) = bitmap.image() else {
+            return Ok(None);
+        };
+
+        let image = Arc::new(image);
+        self.cache().insert(
+            CacheKey::Image(id),
+            Cached::Image(image.clone()),
+            image.get_data().len(),
+            cast_member.info.purge_priority,
+        );
+
+        Ok(Some(image))
+    }
+
+    fn chunks_len(&self, ids: impl IntoIterator<Item = u32>) -> usize {
+        ids.into_iter()
+            .filter_map(|id| self.mmap().entry_by_index(id))
+            .map(|entry| entry.len() as usize)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::riff::tags::Tag;
+
+    use super::*;
+
+    fn insert(cache: &mut ChunkCache, id: u32, size: usize, purge_priority: PurgePriority) {
+        let chunk = AnyChunk::Unknown {
+            tag: Tag(0),
+            bytes: Vec::new(),
+        };
+        cache.insert(
+            CacheKey::Chunk(id),
+            Cached::Chunk(Arc::new(chunk)),
+            size,
+            purge_priority,
+        );
+    }
+
+    fn ids(cache: &ChunkCache) -> Vec<u32> {
+        let mut ids: Vec<_> = cache
+            .entries
+            .keys()
+            .map(|key| match key {
+                CacheKey::Chunk(id) => *id,
+                _ => unreachable!(),
+            })
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_first() {
+        let mut cache = ChunkCache::new(30);
+        for id in 1..=3 {
+            insert(&mut cache, id, 10, PurgePriority::Normal);
+        }
+        assert!(cache.get(CacheKey::Chunk(1)).is_some());
+
+        insert(&mut cache, 4, 10, PurgePriority::Normal);
+        assert_eq!(ids(&cache), [1, 3, 4]);
+        assert_eq!(cache.size, 30);
+    }
+
+    #[test]
+    fn evicts_by_purge_priority() {
+        let mut cache = ChunkCache::new(30);
+        insert(&mut cache, 1, 10, PurgePriority::Last);
+        insert(&mut cache, 2, 10, PurgePriority::Normal);
+        insert(&mut cache, 3, 10, PurgePriority::Next);
+
+        insert(&mut cache, 4, 10, PurgePriority::Normal);
+        assert_eq!(ids(&cache), [1, 2, 4]);
+
+        insert(&mut cache, 5, 10, PurgePriority::Normal);
+        assert_eq!(ids(&cache), [1, 4, 5]);
+    }
+
+    #[test]
+    fn evicts_members_that_are_never_purged_last() {
+        let mut cache = ChunkCache::new(20);
+        insert(&mut cache, 1, 15, PurgePriority::Never);
+        insert(&mut cache, 2, 10, PurgePriority::Last);
+        assert_eq!(ids(&cache), [1]);
+
+        insert(&mut cache, 3, 10, PurgePriority::Never);
+        assert_eq!(ids(&cache), [3]);
+        assert_eq!(cache.size, 10);
+    }
+
+    #[test]
+    fn stays_within_the_budget() {
+        let mut cache = ChunkCache::new(100);
+        let priorities = [
+            PurgePriority::Never,
+            PurgePriority::Last,
+            PurgePriority::Next,
+            PurgePriority::Normal,
+        ];
+        for id in 0..50 {
+            let size = (id as usize * 7) % 40;
+            insert(&mut cache, id % 12, size, priorities[id as usize % 4]);
+
+            assert!(cache.size <= 100);
+            let sizes: usize = cache.entries.values().map(|entry| entry.size).sum();
+            assert_eq!(cache.size, sizes);
+        }
+
+        cache.set_budget(10);
+        assert!(cache.size <= 10);
+        insert(&mut cache, 99, 11, PurgePriority::Never);
+        assert_eq!(cache.size, 0);
+    }
+}
@@ -1,30 +1,43 @@
-use std::io::{Error, ErrorKind, Result, Seek};
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind, Result, Seek},
+    ops::Deref,
+};
 
 use crate::{
     gfx,
     reader::{ReadBytesExt, Reader},
-    riff::chunks::{BitmapData, Thumbnail},
+    riff::{
+        Version,
+        cast_members::LinkedMedia,
+        chunks::{BitmapData, Thumbnail},
+    },
 };
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Bitmap {
-    pub name: Option<String>,
     pub info: BitmapInfo,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub data: Option<BitmapData>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub thumbnail: Option<Thumbnail>,
+    pub linked: Option<LinkedMedia>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct BitmapInfo {
     pub pitch: u16,
     pub rect: gfx::Rect,
     pub reg: gfx::Pos,
     pub bit_depth: u8,
     pub palette_id: i16,
+    pub palette_cast_lib: Option<i16>,
 }
 
 impl BitmapInfo {
-    pub fn read(r: Reader, _id: u32) -> Result<Self> {
+    pub fn read(r: Reader, _id: u32, version: Version) -> Result<Self> {
         let mut r = r;
 
         let a = r.read_be_u16()?;
@@ -48,7 +61,14 @@ impl BitmapInfo {
         let _ = r.read_u8().unwrap_or_default();
 
         let bit_depth = r.read_u8().unwrap_or(1);
-        // let _ = r.read_be_i16().unwrap_or_default();
+
+        // Director 5 references the palette by cast library and member.
+        let palette_cast_lib = if version.major() >= 5 {
+            r.read_be_i16().ok()
+        } else {
+            None
+        };
+
         let palette_id = r.read_be_i16().unwrap_or(1) - 1;
 
         Ok(BitmapInfo {
@@ -57,9 +77,63 @@ impl BitmapInfo {
             reg,
             bit_depth,
             palette_id,
+            palette_cast_lib,
         })
     }
 
+    /// Writes the fields that differ from those in `data`, the bitmap's
+    /// specific data they were read from.
+    pub fn patch(&self, data: &mut Vec<u8>, version: Version) -> Result<()> {
+        let current = BitmapInfo::read(Reader::new(data), 0, version)?;
+
+        if current.pitch != self.pitch || current.bit_depth != self.bit_depth {
+            let mut flags = u16::from_be_bytes([data[0], data[1]]) & !0xfff;
+
+            // The top bit marks bitmaps that have a bit depth other than 1.
+            if self.bit_depth > 1 {
+                flags |= 0x8000;
+            } else {
+                flags &= !0x8000;
+            }
+            patch_be_i16(data, 0, (flags | (self.pitch & 0xfff)) as i16);
+        }
+
+        if current.rect != self.rect {
+            patch_be_i16(data, 2, self.rect.y0);
+            patch_be_i16(data, 4, self.rect.x0);
+            patch_be_i16(data, 6, self.rect.y1);
+            patch_be_i16(data, 8, self.rect.x1);
+        }
+
+        if current.reg != self.reg {
+            patch_be_i16(data, 18, self.reg.y);
+            patch_be_i16(data, 20, self.reg.x);
+        }
+
+        let palette_offset = if version.major() >= 5 { 26 } else { 24 };
+
+        if current.palette_id != self.palette_id {
+            patch_be_i16(data, palette_offset, self.palette_id + 1);
+        }
+
+        if current.palette_cast_lib != self.palette_cast_lib
+            && let Some(palette_cast_lib) = self.palette_cast_lib
+        {
+            patch_be_i16(data, 24, palette_cast_lib);
+        }
+
+        if current.bit_depth != self.bit_depth {
+            if data.len() < palette_offset + 2 {
+                // Bitmaps without a bit depth are 1-bit with the default
+                // palette, which has to be spelled out now.
+                patch_be_i16(data, palette_offset, self.palette_id + 1);
+            }
+            data[23] = self.bit_depth;
+        }
+
+        Ok(())
+    }
+
     pub fn width(&self) -> i16 {
         self.rect.width()
     }
@@ -70,31 +144,223 @@ impl BitmapInfo {
 }
 
 impl Bitmap {
+    /// Decodes the bitmap data stored in the movie. `None` if there is none,
+    /// if it is damaged, or if the bitmap has 16 or 32 bits per pixel, which
+    /// are colors rather than palette indices.
     pub fn image(&self) -> Option<gfx::IndexedImage> {
-        let mut image = gfx::IndexedImage::new_owned(
-            self.info.rect.width() as usize,
-            self.info.rect.height() as usize,
-        );
+        let width = self.info.rect.width() as usize;
+        let height = self.info.rect.height() as usize;
+        let pitch = self.info.pitch as usize;
+        if pitch == 0 {
+            return None;
+        }
+
+        let mut image = gfx::IndexedImage::new_owned(width, height);
 
-        let buf = self.data.as_ref().unwrap().buf();
+        let buf = self.data.as_ref()?.buf();
         let r = Reader::new(buf);
 
-        if self.info.bit_depth == 8 {
+        // Bitmaps that do not compress well are stored as is.
+        let pixels = if buf.len() == pitch * height {
+            buf.to_vec()
+        } else {
             let Ok(len) = decompress_len(r.clone()) else {
                 return None;
             };
 
-            let pitch = self.info.pitch as u32;
-            let height = len as u32 / pitch;
+            let mut pixels = vec![0; len];
+            decompress(r, &mut pixels).ok()?;
+            pixels
+        };
+
+        match self.info.bit_depth {
+            8 => {
+                let height = pixels.len() / pitch;
+
+                image = gfx::IndexedImage::new_owned(pitch, height);
+                let data = image.get_mut_data();
+                data.copy_from_slice(&pixels[..pitch * height]);
+            }
+            bit_depth @ (1 | 2 | 4) => {
+                let bit_depth = bit_depth as usize;
+                let mask = (1u8 << bit_depth) - 1;
+                let data = image.get_mut_data();
 
-            image = gfx::IndexedImage::new_owned(pitch as usize, height as usize);
-            let data = image.get_mut_data();
+                for (y, row) in pixels.chunks_exact(pitch).take(height).enumerate() {
+                    for x in 0..width.min(pitch * 8 / bit_depth) {
+                        let bit = x * bit_depth;
+                        let shift = 8 - bit_depth - bit % 8;
+                        let value = (row[bit / 8] >> shift) & mask;
 
-            decompress(r, data).unwrap();
+                        // 1-bit bitmaps are black on white, which are the
+                        // last and first entries of the system palette.
+                        data[y * width + x] = if bit_depth == 1 && value != 0 {
+                            0xff
+                        } else {
+                            value
+                        };
+                    }
+                }
+            }
+            _ => return None,
         }
 
         Some(image)
     }
+
+    /// Replaces the pixels with `image`, mapped to the closest of the first
+    /// `color_count` colors of `palette` at `bit_depth` bits per pixel.
+    ///
+    /// The bitmap keeps its top left corner and its registration point keeps
+    /// its relative position. 1-bit bitmaps are always black and white. The
+    /// thumbnail of the old image is dropped.
+    pub fn import<Data: Deref<Target = [u32]>>(
+        &mut self,
+        image: &gfx::ImageBuffer<Data>,
+        palette: &gfx::Palette,
+        color_count: usize,
+        bit_depth: u8,
+    ) -> Result<()> {
+        // The palette entries the pixel values of each depth stand for, see
+        // `Bitmap::image`.
+        let (palette, indices): (&gfx::Palette, Vec<u8>) = match bit_depth {
+            1 => (&gfx::Palette::mac_system(), vec![0, 0xff]),
+            2 | 4 => (palette, (0..1u8 << bit_depth).collect()),
+            8 => (
+                palette,
+                (0..color_count.clamp(1, 256)).map(|i| i as u8).collect(),
+            ),
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Bitmap::import: Unsupported bit depth {bit_depth}"),
+                ));
+            }
+        };
+
+        let width = image.width();
+        let height = image.height();
+        let pitch = (width * bit_depth as usize).div_ceil(16) * 2;
+        if width == 0 || height == 0 || pitch > 0xfff || height > i16::MAX as usize {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Bitmap::import: Unsupported image size {width}x{height}"),
+            ));
+        }
+
+        let mut nearest = HashMap::new();
+        let mut pixels = vec![0; pitch * height];
+        for (y, row) in pixels.chunks_exact_mut(pitch).enumerate() {
+            for x in 0..width {
+                let color = image.get_data()[y * width + x];
+                let index = *nearest.entry(color).or_insert_with(|| {
+                    palette.nearest_index(gfx::Rgb888::from_u32(color), indices.iter().copied())
+                });
+                let value = if bit_depth == 1 {
+                    (index != 0) as u8
+                } else {
+                    index
+                };
+
+                let bit = x * bit_depth as usize;
+                row[bit / 8] |= value << (8 - bit_depth as usize - bit % 8);
+            }
+        }
+
+        let old_rect = self.info.rect;
+        let rect = gfx::Rect::new(
+            old_rect.y0,
+            old_rect.x0,
+            old_rect.y0 + height as i16,
+            old_rect.x0 + width as i16,
+        );
+        let scale = |reg: i16, origin: i16, old_len: i16, len: i16| {
+            if old_len > 0 {
+                origin + ((reg - origin) as i32 * len as i32 / old_len as i32) as i16
+            } else {
+                origin + len / 2
+            }
+        };
+
+        self.info.reg = gfx::Pos {
+            y: scale(
+                self.info.reg.y,
+                old_rect.y0,
+                old_rect.height(),
+                rect.height(),
+            ),
+            x: scale(self.info.reg.x, old_rect.x0, old_rect.width(), rect.width()),
+        };
+        self.info.rect = rect;
+        self.info.pitch = pitch as u16;
+        self.info.bit_depth = bit_depth;
+
+        // Data the size of the uncompressed pixels is read as is.
+        let compressed = compress(&pixels, pitch);
+        let buf = if compressed.len() < pixels.len() {
+            compressed
+        } else {
+            pixels
+        };
+        self.data = Some(BitmapData::new(buf));
+        self.thumbnail = None;
+
+        Ok(())
+    }
+}
+
+fn patch_be_i16(data: &mut Vec<u8>, offset: usize, value: i16) {
+    if data.len() < offset + 2 {
+        data.resize(offset + 2, 0);
+    }
+    data[offset..offset + 2].copy_from_slice(&value.to_be_bytes());
+}
+
+/// Compresses `pixels` with the run length encoding `decompress` reads.
+///
+/// Like Director, each row of `pitch` bytes is packed on its own, so no run
+/// crosses the end of a row. Runs of two bytes are only used when they do
+/// not split a literal.
+pub fn compress(pixels: &[u8], pitch: usize) -> Vec<u8> {
+    let mut out = Vec::new();
+    for row in pixels.chunks(pitch.max(1)) {
+        compress_row(row, &mut out);
+    }
+    out
+}
+
+fn compress_row(row: &[u8], out: &mut Vec<u8>) {
+    let flush_literal = |out: &mut Vec<u8>, literal: &[u8]| {
+        if !literal.is_empty() {
+            out.push((literal.len() - 1) as u8);
+            out.extend_from_slice(literal);
+        }
+    };
+
+    let mut literal_start = 0;
+    let mut i = 0;
+    while i < row.len() {
+        let run = row[i..]
+            .iter()
+            .take(128)
+            .take_while(|&&b| b == row[i])
+            .count();
+
+        if run >= 3 || (run == 2 && literal_start == i) {
+            flush_literal(out, &row[literal_start..i]);
+            out.push((257 - run) as u8);
+            out.push(row[i]);
+            i += run;
+            literal_start = i;
+        } else {
+            i += 1;
+            if i - literal_start == 128 {
+                flush_literal(out, &row[literal_start..i]);
+                literal_start = i;
+            }
+        }
+    }
+    flush_literal(out, &row[literal_start..]);
 }
 
 pub fn decompress_len(r: Reader) -> Result<usize> {
@@ -163,3 +429,63 @@ pub fn decompress(r: Reader, buf: &mut [u8]) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    /// Rows of random bytes, biased towards runs.
+    fn bitmap() -> impl Strategy<Value = (Vec<u8>, usize)> {
+        (1..300usize, 0..20usize).prop_flat_map(|(pitch, height)| {
+            let byte = prop_oneof![Just(0u8), Just(0xff), any::<u8>()];
+            let chunk = (byte, 1..200usize).prop_map(|(b, len)| vec![b; len]);
+            prop::collection::vec(chunk, 0..40).prop_map(move |chunks| {
+                let mut pixels: Vec<u8> = chunks.concat();
+                pixels.resize(pitch * height, 0x55);
+                (pixels, pitch)
+            })
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn round_trips((pixels, pitch) in bitmap()) {
+            let compressed = compress(&pixels, pitch);
+
+            prop_assert_eq!(decompress_len(Reader::new(&compressed)).unwrap(), pixels.len());
+
+            let mut decompressed = vec![0; pixels.len()];
+            decompress(Reader::new(&compressed), &mut decompressed).unwrap();
+            prop_assert_eq!(decompressed, pixels);
+        }
+
+        #[test]
+        fn packs_rows_separately((pixels, pitch) in bitmap()) {
+            let rows: Vec<u8> = pixels
+                .chunks(pitch)
+                .flat_map(|row| compress(row, pitch))
+                .collect();
+
+            prop_assert_eq!(compress(&pixels, pitch), rows);
+        }
+
+        #[test]
+        fn grows_by_at_most_one_byte_per_128((pixels, pitch) in bitmap()) {
+            let height = pixels.len() / pitch;
+            let max_len = pixels.len() + height * pitch.div_ceil(128);
+
+            prop_assert!(compress(&pixels, pitch).len() <= max_len);
+        }
+    }
+
+    #[test]
+    fn matches_packbits_example() {
+        // The example from Apple's PackBits technical note.
+        let pixels = b"\xaa\xaa\xaa\x80\x00\x2a\xaa\xaa\xaa\xaa\x80\x00\x2a\x22\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa";
+        let packed = b"\xfe\xaa\x02\x80\x00\x2a\xfd\xaa\x03\x80\x00\x2a\x22\xf7\xaa";
+
+        assert_eq!(compress(pixels, pixels.len()), packed);
+    }
+}
@@ -1,120 +1,304 @@
-use std::io::{Error, ErrorKind, Result, Seek};
+use std::io::{Error, ErrorKind, Result, Seek, Write};
 
 use crate::{
     reader::{ReadBytesExt, Reader},
     riff::{
+        Version,
         cast_members::{BitmapInfo, CastMemberType},
-        chunks::Chunk,
+        chunks::{Chunk, RawBytes, WriteChunk},
         tags::{self, Tag},
-        vlist::VList,
     },
+    writer::{WriteBytesExt, Writer},
 };
 
-use super::{Bitmap, Palette, Script, Text};
+use super::{Bitmap, CastMemberInfo, DigitalVideo, LinkedMedia, Palette, Script, Sound, Text};
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CastMember {
+    pub info: CastMemberInfo,
+    pub kind: CastMemberKind,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    raw: CastMemberRaw,
+}
+
+/// The bytes a cast member was read from, so that it can be written back
+/// with only the parsed fields changed.
+#[derive(Debug, Default)]
+struct CastMemberRaw {
+    /// The flags byte that follows the type in Director 4.
+    flags: Option<u8>,
+    data: RawBytes,
+    info: RawBytes,
+}
 
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Debug)]
-pub enum CastMember {
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum CastMemberKind {
     Null,
     Bitmap(Bitmap),
     FilmLoop,
     Text(Text),
     Palette(Palette),
     Picture,
-    Sound,
-    Button,
+    Sound(Sound),
+    /// A button's label is styled text like a text member's.
+    Button(Text),
     Shape,
     Movie,
-    DigitalVideo,
+    DigitalVideo(DigitalVideo),
     Script(Script),
     RTE,
+    Transition,
+    Xtra,
 }
 
 impl Chunk for CastMember {
     const TAG: Tag = tags::TAG_CASt;
 
-    fn read(r: &mut Reader, id: u32) -> Result<Self> {
-        let mut data_len = r.read_be_u16()? as usize;
-        let vlist_byte_len = r.read_be_u32()? as usize;
+    fn read(r: &mut Reader, id: u32, version: Version) -> Result<Self> {
+        let (cast_member_type_id, flags, data_reader, vlist_reader) = if version.major() >= 5 {
+            // Director 5 and later: type, info length and specific data
+            // length, followed by the info and then the specific data.
+            let cast_member_type_id = r.read_be_u32()?;
+            let vlist_byte_len = r.read_be_u32()? as usize;
+            let data_len = r.read_be_u32()? as usize;
 
-        assert!(data_len > 0);
-        let cast_member_type_id = r.read_u8()?;
-        data_len -= 1;
+            let cast_member_type_id = u8::try_from(cast_member_type_id).map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Invalid CastMemberType value: {cast_member_type_id}"),
+                )
+            })?;
 
-        let cast_member_type = CastMemberType::try_from(cast_member_type_id)
-            .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{e}")))?;
+            let pos = r.stream_position()? as usize;
 
-        let mut _flags = 0;
-        if data_len > 1 {
-            _flags = r.read_u8()?;
+            let vlist_reader = r.subset(pos, vlist_byte_len)?;
+            let data_reader = r.subset(pos + vlist_byte_len, data_len)?;
+
+            (cast_member_type_id, None, data_reader, vlist_reader)
+        } else {
+            // Director 4: specific data length and info length, followed by
+            // the specific data, which starts with the type, and then the info.
+            let mut data_len = r.read_be_u16()? as usize;
+            let vlist_byte_len = r.read_be_u32()? as usize;
+
+            if data_len == 0 {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Cast member has no specific data",
+                ));
+            }
+
+            let cast_member_type_id = r.read_u8()?;
             data_len -= 1;
-        }
 
-        let pos = r.stream_position()? as usize;
+            let mut flags = None;
+            if data_len > 1 {
+                flags = Some(r.read_u8()?);
+                data_len -= 1;
+            }
 
-        let data_reader = r.subset(pos, data_len);
-        let mut vlist_reader = r.subset(pos + data_len, vlist_byte_len);
+            let pos = r.stream_position()? as usize;
 
-        let vlist = VList::read_u32(&mut vlist_reader)?;
-        let name = vlist.try_get_as_pascal_str(1)?;
+            let data_reader = r.subset(pos, data_len)?;
+            let vlist_reader = r.subset(pos + data_len, vlist_byte_len)?;
 
-        let cast_member = match cast_member_type {
-            CastMemberType::Null => CastMember::Null,
+            (cast_member_type_id, flags, data_reader, vlist_reader)
+        };
+
+        Self::read_parts(
+            cast_member_type_id,
+            flags,
+            data_reader,
+            vlist_reader,
+            id,
+            version,
+        )
+    }
+}
+
+impl CastMember {
+    /// Reads a Director 3 cast member from its record in the `VWCR` cast
+    /// table, which is laid out like the specific data of a Director 4 `CASt`
+    /// chunk, and its `VWCI` info resource, if it has one.
+    pub(crate) fn read_record(
+        cast_member_type_id: u8,
+        flags: Option<u8>,
+        data_reader: Reader,
+        info_reader: Option<Reader>,
+        id: u32,
+        version: Version,
+    ) -> Result<Self> {
+        let info_reader = info_reader.unwrap_or_else(|| Reader::new(&[]));
+        Self::read_parts(
+            cast_member_type_id,
+            flags,
+            data_reader,
+            info_reader,
+            id,
+            version,
+        )
+    }
+
+    fn read_parts(
+        cast_member_type_id: u8,
+        flags: Option<u8>,
+        mut data_reader: Reader,
+        vlist_reader: Reader,
+        id: u32,
+        version: Version,
+    ) -> Result<Self> {
+        let mut raw = CastMemberRaw {
+            flags,
+            ..CastMemberRaw::default()
+        };
+        data_reader.clone().read_to_end(&mut raw.data.0)?;
+        vlist_reader.clone().read_to_end(&mut raw.info.0)?;
+
+        let cast_member_type = CastMemberType::try_from(cast_member_type_id)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{e}")))?;
+
+        let info = if vlist_reader.clone().stream_len()? > 0 {
+            let mut vlist_reader = vlist_reader;
+            CastMemberInfo::read(&mut vlist_reader, version)?
+        } else {
+            CastMemberInfo::default()
+        };
+
+        let kind = match cast_member_type {
+            CastMemberType::Null => CastMemberKind::Null,
             CastMemberType::Bitmap => {
-                let bitmap_info = BitmapInfo::read(data_reader, id)?;
-                CastMember::Bitmap(Bitmap {
-                    name,
+                let bitmap_info = BitmapInfo::read(data_reader, id, version)?;
+                CastMemberKind::Bitmap(Bitmap {
                     info: bitmap_info,
                     thumbnail: None,
                     data: None,
+                    linked: None,
                 })
             }
-            CastMemberType::FilmLoop => CastMember::FilmLoop,
-            CastMemberType::Text => CastMember::Text(Text::default()),
-            CastMemberType::Palette => CastMember::Palette(Palette::default()),
-            CastMemberType::Picture => CastMember::Picture,
-            CastMemberType::Sound => CastMember::Sound,
-            CastMemberType::Button => CastMember::Button,
-            CastMemberType::Shape => CastMember::Shape,
-            CastMemberType::Movie => CastMember::Movie,
-            CastMemberType::DigitalVideo => CastMember::DigitalVideo,
-            CastMemberType::Script => CastMember::Script(Script::read(r)?),
-            CastMemberType::RTE => CastMember::RTE,
+            CastMemberType::FilmLoop => CastMemberKind::FilmLoop,
+            CastMemberType::Text => CastMemberKind::Text(Text::default()),
+            CastMemberType::Palette => CastMemberKind::Palette(Palette::default()),
+            CastMemberType::Picture => CastMemberKind::Picture,
+            CastMemberType::Sound => CastMemberKind::Sound(Sound::default()),
+            CastMemberType::Button => CastMemberKind::Button(Text::default()),
+            CastMemberType::Shape => CastMemberKind::Shape,
+            CastMemberType::Movie => CastMemberKind::Movie,
+            CastMemberType::DigitalVideo => CastMemberKind::DigitalVideo(DigitalVideo::default()),
+            CastMemberType::Script => CastMemberKind::Script(Script::read(&mut data_reader)?),
+            CastMemberType::RTE => CastMemberKind::RTE,
+            CastMemberType::Transition => CastMemberKind::Transition,
+            CastMemberType::Xtra => CastMemberKind::Xtra,
         };
 
-        Ok(cast_member)
+        Ok(CastMember { info, kind, raw })
+    }
+}
+
+impl WriteChunk for CastMember {
+    fn write(&self, w: &mut Writer, version: Version) -> Result<()> {
+        let mut data = self.raw.data.0.clone();
+        match &self.kind {
+            CastMemberKind::Bitmap(bitmap) => bitmap.info.patch(&mut data, version)?,
+            CastMemberKind::Script(script) if data.len() >= 2 => {
+                data[..2].copy_from_slice(&u16::from(script.r#type).to_be_bytes());
+            }
+            _ => {}
+        }
+
+        let info = self.info.write(&self.raw.info.0, version, w.encoding())?;
+        let cast_member_type_id = u8::from(self.cast_member_type());
+
+        if version.major() >= 5 {
+            w.write_be_u32(cast_member_type_id as u32)?;
+            w.write_be_u32(info.len() as u32)?;
+            w.write_be_u32(data.len() as u32)?;
+            w.write_all(&info)?;
+            w.write_all(&data)?;
+        } else {
+            let flags_len = self.raw.flags.map_or(0, |_| 1);
+            let data_len = u16::try_from(1 + flags_len + data.len()).map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Cast member data is too large: {} bytes", data.len()),
+                )
+            })?;
+
+            w.write_be_u16(data_len)?;
+            w.write_be_u32(info.len() as u32)?;
+            w.write_u8(cast_member_type_id)?;
+            if let Some(flags) = self.raw.flags {
+                w.write_u8(flags)?;
+            }
+            w.write_all(&data)?;
+            w.write_all(&info)?;
+        }
+
+        Ok(())
     }
 }
 
 impl CastMember {
+    pub fn name(&self) -> Option<&str> {
+        self.info.name.as_deref()
+    }
+
     pub fn cast_member_type(&self) -> CastMemberType {
-        match self {
-            CastMember::Null => CastMemberType::Null,
-            CastMember::Bitmap(_) => CastMemberType::Bitmap,
-            CastMember::FilmLoop => CastMemberType::FilmLoop,
-            CastMember::Text(_) => CastMemberType::Text,
-            CastMember::Palette(_) => CastMemberType::Palette,
-            CastMember::Picture => CastMemberType::Picture,
-            CastMember::Sound => CastMemberType::Sound,
-            CastMember::Button => CastMemberType::Button,
-            CastMember::Shape => CastMemberType::Shape,
-            CastMember::Movie => CastMemberType::Movie,
-            CastMember::DigitalVideo => CastMemberType::DigitalVideo,
-            CastMember::Script(_) => CastMemberType::Script,
-            CastMember::RTE => CastMemberType::RTE,
+        match &self.kind {
+            CastMemberKind::Null => CastMemberType::Null,
+            CastMemberKind::Bitmap(_) => CastMemberType::Bitmap,
+            CastMemberKind::FilmLoop => CastMemberType::FilmLoop,
+            CastMemberKind::Text(_) => CastMemberType::Text,
+            CastMemberKind::Palette(_) => CastMemberType::Palette,
+            CastMemberKind::Picture => CastMemberType::Picture,
+            CastMemberKind::Sound(_) => CastMemberType::Sound,
+            CastMemberKind::Button(_) => CastMemberType::Button,
+            CastMemberKind::Shape => CastMemberType::Shape,
+            CastMemberKind::Movie => CastMemberType::Movie,
+            CastMemberKind::DigitalVideo(_) => CastMemberType::DigitalVideo,
+            CastMemberKind::Script(_) => CastMemberType::Script,
+            CastMemberKind::RTE => CastMemberType::RTE,
+            CastMemberKind::Transition => CastMemberType::Transition,
+            CastMemberKind::Xtra => CastMemberType::Xtra,
         }
     }
 
     pub fn as_bitmap(&self) -> Option<&Bitmap> {
-        match self {
-            CastMember::Bitmap(bitmap) => Some(bitmap),
+        match &self.kind {
+            CastMemberKind::Bitmap(bitmap) => Some(bitmap),
+            _ => None,
+        }
+    }
+
+    pub fn as_bitmap_mut(&mut self) -> Option<&mut Bitmap> {
+        match &mut self.kind {
+            CastMemberKind::Bitmap(bitmap) => Some(bitmap),
             _ => None,
         }
     }
 
     pub fn as_palette(&self) -> Option<&Palette> {
-        match self {
-            CastMember::Palette(palette) => Some(palette),
+        match &self.kind {
+            CastMemberKind::Palette(palette) => Some(palette),
+            _ => None,
+        }
+    }
+
+    pub fn as_sound(&self) -> Option<&Sound> {
+        match &self.kind {
+            CastMemberKind::Sound(sound) => Some(sound),
+            _ => None,
+        }
+    }
+
+    /// The external file loaded for a linked member, if any.
+    pub fn linked_media(&self) -> Option<&LinkedMedia> {
+        match &self.kind {
+            CastMemberKind::Bitmap(bitmap) => bitmap.linked.as_ref(),
+            CastMemberKind::Sound(sound) => sound.linked.as_ref(),
+            CastMemberKind::DigitalVideo(video) => video.linked.as_ref(),
             _ => None,
         }
     }
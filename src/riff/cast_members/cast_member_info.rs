@@ -0,0 +1,192 @@
+use std::{fmt, io::Result};
+
+use crate::{
+    reader::{ReadBytesExt, Reader, TextEncoding},
+    riff::{
+        Version,
+        vlist::{VList, VListBuilder},
+    },
+    writer::WriteBytesExt,
+};
+
+/// How eagerly Director unloads a cast member when memory runs low.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum PurgePriority {
+    Never,
+    Last,
+    Next,
+    #[default]
+    Normal,
+}
+
+impl From<u32> for PurgePriority {
+    fn from(value: u32) -> Self {
+        match value & 3 {
+            0 => PurgePriority::Never,
+            1 => PurgePriority::Last,
+            2 => PurgePriority::Next,
+            _ => PurgePriority::Normal,
+        }
+    }
+}
+
+impl From<PurgePriority> for u32 {
+    fn from(value: PurgePriority) -> Self {
+        match value {
+            PurgePriority::Never => 0,
+            PurgePriority::Last => 1,
+            PurgePriority::Next => 2,
+            PurgePriority::Normal => 3,
+        }
+    }
+}
+
+impl fmt::Display for PurgePriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            PurgePriority::Never => "Never",
+            PurgePriority::Last => "Last",
+            PurgePriority::Next => "Next",
+            PurgePriority::Normal => "Normal",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// The info list stored alongside every cast member, regardless of its type.
+#[derive(Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CastMemberInfo {
+    pub flags: u32,
+    pub script_id: u32,
+    pub script_text: Option<String>,
+    pub name: Option<String>,
+    pub file_path: Option<String>,
+    pub file_name: Option<String>,
+    pub file_type: Option<String>,
+    pub comments: Option<String>,
+    pub created: Option<u32>,
+    pub modified: Option<u32>,
+    pub purge_priority: PurgePriority,
+}
+
+impl CastMemberInfo {
+    pub fn read(r: &mut Reader, version: Version) -> Result<Self> {
+        let vlist = VList::read_u32(r)?;
+
+        let flags = vlist.fixed_number(2).unwrap_or_default();
+        let script_id = vlist.fixed_number(3).unwrap_or_default();
+
+        let script_text = vlist.try_get_as_str(0)?;
+        let name = vlist.try_get_as_pascal_str(1)?;
+        let file_path = vlist.try_get_as_pascal_str(2)?;
+        let file_name = vlist.try_get_as_pascal_str(3)?;
+
+        // Director 5 moved the file type to the end of the list and reused
+        // its slot for the member comments.
+        let (file_type, comments, created, modified) = if version.major() >= 5 {
+            let comments = vlist.try_get_as_str(4)?;
+            let file_type = vlist.try_get_as_str(16)?;
+            let created = vlist.get(17).map(|mut r| r.read_be_u32()).transpose()?;
+            let modified = vlist.get(18).map(|mut r| r.read_be_u32()).transpose()?;
+
+            (file_type, comments, created, modified)
+        } else {
+            let file_type = vlist.try_get_as_pascal_str(4)?;
+
+            (file_type, None, None, None)
+        };
+
+        Ok(CastMemberInfo {
+            flags,
+            script_id,
+            script_text,
+            name,
+            file_path,
+            file_name,
+            file_type,
+            comments,
+            created,
+            modified,
+            purge_priority: PurgePriority::from(flags >> 2),
+        })
+    }
+
+    /// Writes the info list over `original`, the list it was read from, so
+    /// that entries this type does not know about are kept. Entries that did
+    /// not change keep their original bytes.
+    pub fn write(
+        &self,
+        original: &[u8],
+        version: Version,
+        encoding: TextEncoding,
+    ) -> Result<Vec<u8>> {
+        let (mut builder, current) = if original.is_empty() {
+            (VListBuilder::new(vec![0; 4]), CastMemberInfo::default())
+        } else {
+            let mut r = Reader::new(original);
+            r.set_encoding(encoding);
+            let vlist = VList::read_u32(&mut r.clone())?;
+            let builder = VListBuilder::from_vlist(&vlist)?;
+            let current = CastMemberInfo::read(&mut r, version)?;
+            (builder, current)
+        };
+
+        if *self == current {
+            return Ok(original.to_vec());
+        }
+
+        let flags = (self.flags & !0xc) | (u32::from(self.purge_priority) << 2);
+        builder.set_number(2, flags);
+        builder.set_number(3, self.script_id);
+
+        let mut set_str = |index: usize, new: &Option<String>, old: &Option<String>, pascal| {
+            if new == old {
+                return Ok(());
+            }
+
+            let mut entry = Vec::new();
+            if let Some(s) = new {
+                if pascal {
+                    entry.write_pascal_str(s, encoding)?;
+                } else {
+                    entry = encoding.encode(s)?;
+                }
+            }
+            builder.set_entry(index, entry);
+            Ok::<_, std::io::Error>(())
+        };
+
+        set_str(0, &self.script_text, &current.script_text, false)?;
+        set_str(1, &self.name, &current.name, true)?;
+        set_str(2, &self.file_path, &current.file_path, true)?;
+        set_str(3, &self.file_name, &current.file_name, true)?;
+
+        if version.major() >= 5 {
+            set_str(4, &self.comments, &current.comments, false)?;
+            set_str(16, &self.file_type, &current.file_type, false)?;
+
+            for (index, new, old) in [
+                (17, self.created, current.created),
+                (18, self.modified, current.modified),
+            ] {
+                if new != old {
+                    let entry = new.map(|v| v.to_be_bytes().to_vec()).unwrap_or_default();
+                    builder.set_entry(index, entry);
+                }
+            }
+        } else {
+            set_str(4, &self.file_type, &current.file_type, true)?;
+        }
+
+        let mut buf = Vec::new();
+        builder.write_u32(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Returns true if the member's media lives in an external file.
+    pub fn is_linked(&self) -> bool {
+        self.file_name.as_deref().is_some_and(|s| !s.is_empty())
+    }
+}
@@ -2,6 +2,7 @@ use std::fmt;
 
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum CastMemberType {
     Null,
     Bitmap,
@@ -16,6 +17,8 @@ pub enum CastMemberType {
     DigitalVideo,
     Script,
     RTE,
+    Transition,
+    Xtra,
 }
 
 #[derive(Debug, PartialEq)]
@@ -47,11 +50,35 @@ impl TryFrom<u8> for CastMemberType {
             10 => Ok(CastMemberType::DigitalVideo),
             11 => Ok(CastMemberType::Script),
             12 => Ok(CastMemberType::RTE),
+            14 => Ok(CastMemberType::Transition),
+            15 => Ok(CastMemberType::Xtra),
             _ => Err(InvalidCastMemberTypeError(value)),
         }
     }
 }
 
+impl From<CastMemberType> for u8 {
+    fn from(value: CastMemberType) -> Self {
+        match value {
+            CastMemberType::Null => 0,
+            CastMemberType::Bitmap => 1,
+            CastMemberType::FilmLoop => 2,
+            CastMemberType::Text => 3,
+            CastMemberType::Palette => 4,
+            CastMemberType::Picture => 5,
+            CastMemberType::Sound => 6,
+            CastMemberType::Button => 7,
+            CastMemberType::Shape => 8,
+            CastMemberType::Movie => 9,
+            CastMemberType::DigitalVideo => 10,
+            CastMemberType::Script => 11,
+            CastMemberType::RTE => 12,
+            CastMemberType::Transition => 14,
+            CastMemberType::Xtra => 15,
+        }
+    }
+}
+
 impl fmt::Display for CastMemberType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let s = match self {
@@ -68,6 +95,8 @@ impl fmt::Display for CastMemberType {
             CastMemberType::DigitalVideo => "DigitalVideo",
             CastMemberType::Script => "Script",
             CastMemberType::RTE => "RTE",
+            CastMemberType::Transition => "Transition",
+            CastMemberType::Xtra => "Xtra",
         };
         write!(f, "{}", s)
     }
@@ -0,0 +1,9 @@
+use super::LinkedMedia;
+
+/// A QuickTime or AVI movie. Director always keeps the video in a file of its
+/// own, which is loaded when the movie has a linked file resolver.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DigitalVideo {
+    pub linked: Option<LinkedMedia>,
+}
@@ -0,0 +1,150 @@
+use std::{
+    borrow::Cow,
+    fs::{self, File},
+    io::{Read, Result},
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum LinkedMediaFormat {
+    Pict,
+    Bmp,
+    Aiff,
+    Wav,
+    QuickTime,
+    Avi,
+    Unknown,
+}
+
+impl LinkedMediaFormat {
+    /// Identifies the format from the file contents, falling back to the Mac
+    /// file type recorded in the cast member info and the file extension.
+    pub fn detect(data: &[u8], file_type: Option<&str>, path: &Path) -> Self {
+        if data.starts_with(b"BM") {
+            return LinkedMediaFormat::Bmp;
+        }
+
+        if data.starts_with(b"FORM") && matches!(data.get(8..12), Some(b"AIFF" | b"AIFC")) {
+            return LinkedMediaFormat::Aiff;
+        }
+
+        if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WAVE") {
+            return LinkedMediaFormat::Wav;
+        }
+
+        if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"AVI ") {
+            return LinkedMediaFormat::Avi;
+        }
+
+        // QuickTime movies are a list of atoms, each a size and a type.
+        if matches!(
+            data.get(4..8),
+            Some(b"moov" | b"mdat" | b"ftyp" | b"wide" | b"free" | b"skip")
+        ) {
+            return LinkedMediaFormat::QuickTime;
+        }
+
+        // PICT files start with a 512 byte header that is not part of the
+        // picture, followed by the picture size, frame and version opcode.
+        if matches!(
+            data.get(522..526),
+            Some([0x00, 0x11, 0x02, 0xff] | [0x11, 0x01, _, _])
+        ) {
+            return LinkedMediaFormat::Pict;
+        }
+
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+
+        match (file_type.map(str::trim), extension.as_deref()) {
+            (Some("PICT"), _) | (_, Some("pct" | "pict" | "pic")) => LinkedMediaFormat::Pict,
+            (Some("BMP " | "BMPf"), _) | (_, Some("bmp" | "dib")) => LinkedMediaFormat::Bmp,
+            (Some("AIFF" | "AIFC"), _) | (_, Some("aif" | "aiff" | "aifc")) => {
+                LinkedMediaFormat::Aiff
+            }
+            (Some("WAVE"), _) | (_, Some("wav")) => LinkedMediaFormat::Wav,
+            (Some("MooV"), _) | (_, Some("mov" | "qt")) => LinkedMediaFormat::QuickTime,
+            (Some("VfW "), _) | (_, Some("avi")) => LinkedMediaFormat::Avi,
+            _ => LinkedMediaFormat::Unknown,
+        }
+    }
+}
+
+/// The contents of an external file referenced by a linked cast member, as
+/// they are in the file. Only AIFF and WAV sounds are decoded further, by
+/// `Pcm`. Linked PICT and BMP images are not: `Bitmap::image` renders only the
+/// bitmap data stored in the movie, and linked images are copied as they are
+/// by `decoder extract`, as are videos.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LinkedMedia {
+    pub path: PathBuf,
+    pub format: LinkedMediaFormat,
+    /// The contents of the file, or `None` for videos, which can be too large
+    /// to keep in memory. See `LinkedMedia::read`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub data: Option<Vec<u8>>,
+}
+
+impl LinkedMedia {
+    /// How much of a file `LinkedMediaFormat::detect` looks at.
+    const HEADER_LEN: u64 = 526;
+
+    pub fn load(path: &Path, file_type: Option<&str>) -> Result<Self> {
+        let mut header = Vec::new();
+        File::open(path)?
+            .take(Self::HEADER_LEN)
+            .read_to_end(&mut header)?;
+        let format = LinkedMediaFormat::detect(&header, file_type, path);
+
+        let data = match format {
+            LinkedMediaFormat::QuickTime | LinkedMediaFormat::Avi => None,
+            _ => Some(fs::read(path)?),
+        };
+
+        Ok(LinkedMedia {
+            path: path.to_path_buf(),
+            format,
+            data,
+        })
+    }
+
+    /// The contents of the file, read from it if they were not loaded.
+    pub fn read(&self) -> Result<Cow<'_, [u8]>> {
+        match &self.data {
+            Some(data) => Ok(Cow::Borrowed(data)),
+            None => fs::read(&self.path).map(Cow::Owned),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn keeps_only_the_path_of_videos() {
+        let dir = env::temp_dir().join(format!("linked_media_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let video = dir.join("intro.mov");
+        fs::write(&video, b"\0\0\0\x08moov").unwrap();
+        let media = LinkedMedia::load(&video, None).unwrap();
+        assert_eq!(media.format, LinkedMediaFormat::QuickTime);
+        assert!(media.data.is_none());
+        assert_eq!(&*media.read().unwrap(), b"\0\0\0\x08moov");
+
+        let image = dir.join("logo.bmp");
+        fs::write(&image, b"BM image").unwrap();
+        let media = LinkedMedia::load(&image, None).unwrap();
+        assert_eq!(media.format, LinkedMediaFormat::Bmp);
+        assert_eq!(media.data.as_deref(), Some(&b"BM image"[..]));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,20 +1,29 @@
 mod bitmap;
 mod cast_member;
+mod cast_member_info;
 mod cast_member_type;
+mod digital_video;
+mod linked_media;
 mod palette;
 mod script;
+mod sound;
 mod text;
 
 use std::fmt::Display;
 
 pub use bitmap::*;
 pub use cast_member::*;
+pub use cast_member_info::*;
 pub use cast_member_type::*;
+pub use digital_video::*;
+pub use linked_media::*;
 pub use palette::*;
 pub use script::*;
+pub use sound::*;
 pub use text::*;
 
 #[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct CastMemberId {
     id: i16,
     cast: Option<u16>,
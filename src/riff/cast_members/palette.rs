@@ -1,6 +1,7 @@
 use crate::riff::chunks::ColorLookupTable;
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Palette {
     pub clut: Option<ColorLookupTable>,
 }
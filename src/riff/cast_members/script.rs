@@ -1,12 +1,23 @@
 use crate::reader::{ReadBytesExt, Reader};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ScriptType {
     Score,
     Movie,
     Parent,
 }
 
+impl From<ScriptType> for u16 {
+    fn from(value: ScriptType) -> Self {
+        match value {
+            ScriptType::Score => 1,
+            ScriptType::Movie => 3,
+            ScriptType::Parent => 7,
+        }
+    }
+}
+
 pub struct InvalidScriptTypeValue(pub u16);
 
 impl TryFrom<u16> for ScriptType {
@@ -23,6 +34,7 @@ impl TryFrom<u16> for ScriptType {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Script {
     pub r#type: ScriptType,
 }
@@ -0,0 +1,11 @@
+use crate::riff::chunks::SoundData;
+
+use super::LinkedMedia;
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Sound {
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub data: Option<SoundData>,
+    pub linked: Option<LinkedMedia>,
+}
@@ -1,6 +1,34 @@
+use std::io::{Error, ErrorKind, Result};
+
 use crate::riff::chunks::StyledText;
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Text {
     pub styled_text: Option<StyledText>,
 }
+
+impl Text {
+    pub fn text(&self) -> Option<String> {
+        self.styled_text.as_ref().map(StyledText::text)
+    }
+
+    /// Replaces the text, see `StyledText::set_text`.
+    pub fn set_text(&mut self, text: &str) -> Result<()> {
+        self.styled_text_mut()?.set_text(text)
+    }
+
+    /// Replaces the text, see `StyledText::set_text_with_mapping`.
+    pub fn set_text_with_mapping(&mut self, text: &str, map: impl FnMut(i32) -> i32) -> Result<()> {
+        self.styled_text_mut()?.set_text_with_mapping(text, map)
+    }
+
+    fn styled_text_mut(&mut self) -> Result<&mut StyledText> {
+        self.styled_text.as_mut().ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                "Text::set_text: The member's text was not loaded",
+            )
+        })
+    }
+}
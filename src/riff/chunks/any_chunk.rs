@@ -0,0 +1,102 @@
+use std::io::Result;
+
+use crate::{
+    reader::Reader,
+    riff::{
+        Version,
+        cast_members::CastMember,
+        tags::{self, Tag},
+    },
+};
+
+use super::{
+    BitmapData, CastTable, Chunk, ColorLookupTable, Config, FileInfo, FrameLabels, InitialMap,
+    KeyTable, LingoContext, LingoNames, LingoScript, MemoryMap, Score, SoundData, StyledText,
+    Thumbnail,
+};
+
+/// A chunk of any of the types the decoder parses, or the bytes of one it
+/// does not.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum AnyChunk {
+    InitialMap(InitialMap),
+    MemoryMap(MemoryMap),
+    KeyTable(KeyTable),
+    /// A `VWCF` or `DRCF` config.
+    Config(Config),
+    CastTable(CastTable),
+    CastMember(CastMember),
+    BitmapData(BitmapData),
+    Thumbnail(Thumbnail),
+    StyledText(StyledText),
+    ColorLookupTable(ColorLookupTable),
+    SoundData(SoundData),
+    Score(Score),
+    FrameLabels(FrameLabels),
+    FileInfo(FileInfo),
+    LingoContext(LingoContext),
+    LingoNames(LingoNames),
+    LingoScript(LingoScript),
+    Unknown {
+        tag: Tag,
+        bytes: Vec<u8>,
+    },
+}
+
+/// Whether chunks tagged `tag` are parsed by one of the `Chunk` types, rather
+/// than only read as bytes. These are the tags `AnyChunk::read` knows.
+pub fn is_parsed_tag(tag: Tag) -> bool {
+    matches!(
+        tag,
+        tags::TAG_BITD
+            | tags::TAG_CAS_
+            | tags::TAG_CASt
+            | tags::TAG_CLUT
+            | tags::TAG_DRCF
+            | tags::TAG_KEY_
+            | tags::TAG_Lctx
+            | tags::TAG_Lnam
+            | tags::TAG_Lscr
+            | tags::TAG_STXT
+            | tags::TAG_THUM
+            | tags::TAG_VWCF
+            | tags::TAG_VWFI
+            | tags::TAG_VWLB
+            | tags::TAG_VWSC
+            | tags::TAG_imap
+            | tags::TAG_mmap
+            | tags::TAG_snd_
+    )
+}
+
+impl AnyChunk {
+    /// Reads the contents of chunk `id`, tagged `tag`, with the `Chunk` type
+    /// for the tag, or as bytes if there is none.
+    pub fn read(r: &mut Reader, tag: Tag, id: u32, version: Version) -> Result<Self> {
+        Ok(match tag {
+            tags::TAG_BITD => AnyChunk::BitmapData(Chunk::read(r, id, version)?),
+            tags::TAG_CAS_ => AnyChunk::CastTable(Chunk::read(r, id, version)?),
+            tags::TAG_CASt => AnyChunk::CastMember(Chunk::read(r, id, version)?),
+            tags::TAG_CLUT => AnyChunk::ColorLookupTable(Chunk::read(r, id, version)?),
+            tags::TAG_DRCF | tags::TAG_VWCF => AnyChunk::Config(Chunk::read(r, id, version)?),
+            tags::TAG_KEY_ => AnyChunk::KeyTable(Chunk::read(r, id, version)?),
+            tags::TAG_Lctx => AnyChunk::LingoContext(Chunk::read(r, id, version)?),
+            tags::TAG_Lnam => AnyChunk::LingoNames(Chunk::read(r, id, version)?),
+            tags::TAG_Lscr => AnyChunk::LingoScript(Chunk::read(r, id, version)?),
+            tags::TAG_STXT => AnyChunk::StyledText(Chunk::read(r, id, version)?),
+            tags::TAG_THUM => AnyChunk::Thumbnail(Chunk::read(r, id, version)?),
+            tags::TAG_VWFI => AnyChunk::FileInfo(Chunk::read(r, id, version)?),
+            tags::TAG_VWLB => AnyChunk::FrameLabels(Chunk::read(r, id, version)?),
+            tags::TAG_VWSC => AnyChunk::Score(Chunk::read(r, id, version)?),
+            tags::TAG_imap => AnyChunk::InitialMap(Chunk::read(r, id, version)?),
+            tags::TAG_mmap => AnyChunk::MemoryMap(Chunk::read(r, id, version)?),
+            tags::TAG_snd_ => AnyChunk::SoundData(Chunk::read(r, id, version)?),
+            _ => {
+                let mut bytes = Vec::new();
+                r.read_to_end(&mut bytes)?;
+                AnyChunk::Unknown { tag, bytes }
+            }
+        })
+    }
+}
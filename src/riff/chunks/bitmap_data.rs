@@ -1,11 +1,18 @@
-use std::{fmt::Debug, io::Result};
+use std::{
+    fmt::Debug,
+    io::{Result, Write},
+};
 
 use crate::{
     reader::Reader,
-    riff::tags::{self, Tag},
+    riff::{
+        Version,
+        tags::{self, Tag},
+    },
+    writer::Writer,
 };
 
-use super::Chunk;
+use super::{Chunk, WriteChunk};
 
 pub struct BitmapData {
     buf: Vec<u8>,
@@ -14,7 +21,7 @@ pub struct BitmapData {
 impl Chunk for BitmapData {
     const TAG: Tag = tags::TAG_BITD;
 
-    fn read(r: &mut Reader, _id: u32) -> Result<Self> {
+    fn read(r: &mut Reader, _id: u32, _version: Version) -> Result<Self> {
         let mut buf = Vec::new();
         r.read_to_end(&mut buf)?;
 
@@ -22,6 +29,12 @@ impl Chunk for BitmapData {
     }
 }
 
+impl WriteChunk for BitmapData {
+    fn write(&self, w: &mut Writer, _version: Version) -> Result<()> {
+        w.write_all(&self.buf)
+    }
+}
+
 impl Debug for BitmapData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("BitmapData").finish()
@@ -29,6 +42,10 @@ impl Debug for BitmapData {
 }
 
 impl BitmapData {
+    pub fn new(buf: Vec<u8>) -> Self {
+        Self { buf }
+    }
+
     pub fn buf(&self) -> &[u8] {
         &self.buf
     }
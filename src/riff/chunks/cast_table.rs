@@ -3,15 +3,18 @@ use std::io::Result;
 use crate::{
     reader::{ReadBytesExt, Reader},
     riff::{
+        Version,
         cast_members::CastMemberId,
         tags::{self, Tag},
     },
+    writer::{WriteBytesExt, Writer},
 };
 
-use super::Chunk;
+use super::{Chunk, WriteChunk};
 
 #[allow(dead_code)]
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct CastTable {
     cast_member_ids: Vec<(i16, u32)>,
 }
@@ -19,14 +22,14 @@ pub struct CastTable {
 impl Chunk for CastTable {
     const TAG: Tag = tags::TAG_CAS_;
 
-    fn read(r: &mut Reader, _id: u32) -> Result<Self> {
+    fn read(r: &mut Reader, _id: u32, _version: Version) -> Result<Self> {
         let mut cast_ids = Vec::new();
         let mut cast_member_id = 1;
         while let Ok(chunk_id) = r.read_be_u32() {
-            if chunk_id == 0 {
-                continue;
+            // Empty slots still take up a cast member number
+            if chunk_id != 0 {
+                cast_ids.push((cast_member_id, chunk_id));
             }
-            cast_ids.push((cast_member_id, chunk_id));
 
             cast_member_id += 1;
         }
@@ -38,6 +41,21 @@ impl Chunk for CastTable {
     }
 }
 
+impl WriteChunk for CastTable {
+    fn write(&self, w: &mut Writer, _version: Version) -> Result<()> {
+        let mut next_id = 1;
+        for &(id, chunk_id) in &self.cast_member_ids {
+            for _ in next_id..id {
+                w.write_be_u32(0)?;
+            }
+            w.write_be_u32(chunk_id)?;
+            next_id = id + 1;
+        }
+
+        Ok(())
+    }
+}
+
 impl CastTable {
     pub fn display(&self) {
         println!("Cast Table:");
@@ -51,6 +69,29 @@ impl CastTable {
         println!();
     }
 
+    /// The ids of all occupied cast member slots, in order.
+    pub fn cast_member_ids(&self) -> impl Iterator<Item = CastMemberId> + '_ {
+        self.cast_member_ids
+            .iter()
+            .map(|&(id, _)| CastMemberId::new(id))
+    }
+
+    /// Points cast member `id` at a CASt chunk, or empties its slot.
+    pub fn set_cast_member_chunk_id(&mut self, id: CastMemberId, chunk_id: Option<u32>) {
+        let index = self
+            .cast_member_ids
+            .binary_search_by_key(&id.id(), |&(id, _)| id);
+
+        match (index, chunk_id) {
+            (Ok(i), Some(chunk_id)) => self.cast_member_ids[i].1 = chunk_id,
+            (Ok(i), None) => {
+                self.cast_member_ids.remove(i);
+            }
+            (Err(i), Some(chunk_id)) => self.cast_member_ids.insert(i, (id.id(), chunk_id)),
+            (Err(_), None) => {}
+        }
+    }
+
     pub fn cast_member_chunk_id(&self, id: CastMemberId) -> Option<u32> {
         self.cast_member_ids
             .binary_search_by_key(&id.id(), |&(id, _)| id)
@@ -58,4 +99,12 @@ impl CastTable {
             .copied()
             .ok()
     }
+
+    /// The cast member whose `CASt` chunk is `chunk_id`.
+    pub fn cast_member_with_chunk_id(&self, chunk_id: u32) -> Option<CastMemberId> {
+        self.cast_member_ids
+            .iter()
+            .find(|&&(_, id)| id == chunk_id)
+            .map(|&(id, _)| CastMemberId::new(id))
+    }
 }
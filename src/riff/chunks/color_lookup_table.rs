@@ -1,14 +1,19 @@
-use std::io::{Result, Seek};
+use std::io::{Error, ErrorKind, Result, Seek};
 
 use crate::{
     gfx,
     reader::{ReadBytesExt, Reader},
-    riff::tags::{self, Tag},
+    riff::{
+        Version,
+        tags::{self, Tag},
+    },
+    writer::{WriteBytesExt, Writer},
 };
 
-use super::Chunk;
+use super::{Chunk, WriteChunk};
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ColorLookupTable {
     pub colors: Vec<gfx::Rgb161616>,
 }
@@ -16,12 +21,16 @@ pub struct ColorLookupTable {
 impl Chunk for ColorLookupTable {
     const TAG: Tag = tags::TAG_CLUT;
 
-    fn read(r: &mut Reader, _id: u32) -> Result<Self> {
+    fn read(r: &mut Reader, _id: u32, _version: Version) -> Result<Self> {
         let size = r.stream_len()? as usize;
-        assert!(size % 6 == 0);
+        if !size.is_multiple_of(6) || size / 6 > 256 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Palette of {size} bytes is not up to 256 colors of 6 bytes"),
+            ));
+        }
 
         let entries = size / 6;
-        assert!(entries <= 256);
 
         let mut colors = Vec::with_capacity(entries);
         for _ in 0..entries {
@@ -36,3 +45,15 @@ impl Chunk for ColorLookupTable {
         Ok(ColorLookupTable { colors })
     }
 }
+
+impl WriteChunk for ColorLookupTable {
+    fn write(&self, w: &mut Writer, _version: Version) -> Result<()> {
+        for color in &self.colors {
+            w.write_be_u16(color.r)?;
+            w.write_be_u16(color.g)?;
+            w.write_be_u16(color.b)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,14 +1,44 @@
-use std::io::Result;
+use std::io::{Result, Write};
 
 use crate::{
     reader::{ReadBytesExt, Reader},
-    riff::tags::{self, Tag},
+    riff::{
+        Version,
+        tags::{self, Tag},
+    },
+    writer::Writer,
 };
 
-use super::Chunk;
+use super::{Chunk, RawBytes, WriteChunk};
+
+/// The system a movie was last saved on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Platform {
+    Mac,
+    Windows,
+}
+
+impl Platform {
+    fn from_id(id: i16) -> Option<Self> {
+        match id {
+            -1 => Some(Platform::Mac),
+            1024 => Some(Platform::Windows),
+            _ => None,
+        }
+    }
+
+    fn id(self) -> i16 {
+        match self {
+            Platform::Mac => -1,
+            Platform::Windows => 1024,
+        }
+    }
+}
 
 #[allow(dead_code)]
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Config {
     pub len: u16,
     pub file_version: u16,
@@ -20,12 +50,19 @@ pub struct Config {
     pub max_member: u16,
     pub director_version: Option<u16>,
     pub default_palette_id: Option<i32>,
+    pub default_palette_cast_lib: Option<i16>,
+    pub platform: Option<Platform>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    raw: RawBytes,
 }
 
 impl Chunk for Config {
     const TAG: Tag = tags::TAG_VWCF;
 
-    fn read(r: &mut Reader, _id: u32) -> Result<Self> {
+    fn read(r: &mut Reader, _id: u32, version: Version) -> Result<Self> {
+        let mut raw = Vec::new();
+        r.clone().read_to_end(&mut raw)?;
+
         let len = r.read_be_u16()?;
         let file_version = r.read_be_u16()?;
         let movie_top = r.read_be_u16()?;
@@ -37,12 +74,28 @@ impl Chunk for Config {
 
         let director_version = r.read_be_u16_at(36).ok();
 
+        // The config knows its own version better than the caller does.
+        let version = director_version
+            .filter(|&v| v != 0)
+            .map(Version::new)
+            .unwrap_or(version);
+
+        // Director 5 added cast libraries, so the default palette gained a
+        // cast library number in what used to be an unused field.
+        let default_palette_cast_lib = if version.major() >= 5 {
+            r.read_be_i16_at(0x44).ok()
+        } else {
+            None
+        };
+
         let default_palette_id = r
             .read_be_i16_at(0x46)
             .ok()
             .map(|v| v as i32)
             .map(|v| if v <= 0 { v - 1 } else { v });
 
+        let platform = r.read_be_i16_at(56).ok().and_then(Platform::from_id);
+
         let config = Config {
             len,
             file_version,
@@ -54,12 +107,63 @@ impl Chunk for Config {
             max_member,
             director_version,
             default_palette_id,
+            default_palette_cast_lib,
+            platform,
+            raw: RawBytes(raw),
         };
 
         Ok(config)
     }
 }
 
+impl WriteChunk for Config {
+    /// Writes the config as it was read, with the fields that are parsed
+    /// replaced by their current values.
+    fn write(&self, w: &mut Writer, _version: Version) -> Result<()> {
+        let mut buf = self.raw.0.clone();
+
+        let mut patch = |offset: usize, value: u16| {
+            if buf.len() < offset + 2 {
+                buf.resize(offset + 2, 0);
+            }
+            buf[offset..offset + 2].copy_from_slice(&value.to_be_bytes());
+        };
+
+        patch(0, self.len);
+        patch(2, self.file_version);
+        patch(4, self.movie_top);
+        patch(6, self.movie_left);
+        patch(8, self.movie_bottom);
+        patch(10, self.movie_right);
+        patch(12, self.min_member);
+        patch(14, self.max_member);
+
+        if let Some(director_version) = self.director_version {
+            patch(36, director_version);
+        }
+
+        if let Some(cast_lib) = self.default_palette_cast_lib {
+            patch(0x44, cast_lib as u16);
+        }
+
+        if let Some(palette_id) = self.default_palette_id {
+            // Undo the adjustment of built-in palette ids made when reading.
+            let palette_id = if palette_id < 0 {
+                palette_id + 1
+            } else {
+                palette_id
+            };
+            patch(0x46, palette_id as i16 as u16);
+        }
+
+        if let Some(platform) = self.platform {
+            patch(56, platform.id() as u16);
+        }
+
+        w.write_all(&buf)
+    }
+}
+
 impl Config {
     pub fn display(&self) {
         println!("config = {:#?}\n", &self);
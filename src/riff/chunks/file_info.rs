@@ -3,6 +3,7 @@ use std::io::Result;
 use crate::{
     reader::Reader,
     riff::{
+        Version,
         tags::{self, Tag},
         vlist::VList,
     },
@@ -12,6 +13,7 @@ use super::Chunk;
 
 #[allow(dead_code)]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct FileInfo {
     pub unk0: u32,
     pub unk1: u32,
@@ -26,7 +28,7 @@ pub struct FileInfo {
 impl Chunk for FileInfo {
     const TAG: Tag = tags::TAG_VWFI;
 
-    fn read(r: &mut Reader, _id: u32) -> Result<Self> {
+    fn read(r: &mut Reader, _id: u32, _version: Version) -> Result<Self> {
         let vlist = VList::read_u32(r)?;
 
         let unk0 = vlist.fixed_number(0).unwrap_or_default();
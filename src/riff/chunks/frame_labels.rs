@@ -1,18 +1,24 @@
-use std::io::Result;
+use std::io::{Error, ErrorKind, Result, Write};
 
 use crate::{
     reader::{ReadBytesExt, Reader},
-    riff::tags::{self, Tag},
+    riff::{
+        Version,
+        tags::{self, Tag},
+    },
+    writer::{WriteBytesExt, Writer},
 };
 
-use super::Chunk;
+use super::{Chunk, WriteChunk};
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct FrameLabels {
     labels: Vec<FrameLabel>,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct FrameLabel {
     number: u16,
     text: String,
@@ -21,7 +27,7 @@ pub struct FrameLabel {
 impl Chunk for FrameLabels {
     const TAG: Tag = tags::TAG_VWLB;
 
-    fn read(r: &mut Reader, _id: u32) -> Result<Self> {
+    fn read(r: &mut Reader, _id: u32, _version: Version) -> Result<Self> {
         let label_count = r.read_be_u16()? as usize;
         let mut label_offsets = Vec::with_capacity(label_count + 1);
         for _ in 0..label_count + 1 {
@@ -35,8 +41,12 @@ impl Chunk for FrameLabels {
         for i in 0..label_count {
             let (number, offset0) = label_offsets[i];
             let (_, offset1) = label_offsets[i + 1];
-            assert!(offset1 >= offset0);
-            let len = (offset1 - offset0) as usize;
+            let len = offset1.checked_sub(offset0).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Frame label {i} ends at {offset1}, before its start at {offset0}"),
+                )
+            })? as usize;
             let text = r.read_fixed_str(len)?;
 
             labels.push(FrameLabel { number, text });
@@ -46,6 +56,33 @@ impl Chunk for FrameLabels {
     }
 }
 
+impl WriteChunk for FrameLabels {
+    fn write(&self, w: &mut Writer, _version: Version) -> Result<()> {
+        let texts = self
+            .labels
+            .iter()
+            .map(|label| w.encoding().encode(&label.text))
+            .collect::<Result<Vec<_>>>()?;
+
+        w.write_be_u16(self.labels.len() as u16)?;
+
+        let mut offset = 0;
+        for (label, text) in self.labels.iter().zip(&texts) {
+            w.write_be_u16(label.number)?;
+            w.write_be_u16(offset as u16)?;
+            offset += text.len();
+        }
+        w.write_be_u16(0)?;
+        w.write_be_u16(offset as u16)?;
+
+        for text in &texts {
+            w.write_all(text)?;
+        }
+
+        Ok(())
+    }
+}
+
 impl FrameLabels {
     pub fn is_empty(&self) -> bool {
         self.labels.is_empty()
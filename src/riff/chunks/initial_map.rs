@@ -1,30 +1,54 @@
-use std::io::Result;
+use std::io::{Result, Write};
 
 use crate::{
     reader::Reader,
-    riff::tags::{self, Tag},
+    riff::{
+        Version,
+        tags::{self, Tag},
+    },
+    writer::Writer,
 };
 
-use super::Chunk;
+use super::{Chunk, RawBytes, WriteChunk};
 
 #[allow(unused)]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct InitialMap {
     pub mmap_version: u32,
     pub mmap_offset: u32,
-    // pub director_version: u32,
+    pub director_version: u32,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rest: RawBytes,
 }
 
 impl Chunk for InitialMap {
     const TAG: Tag = tags::TAG_imap;
 
-    fn read(r: &mut Reader, _id: u32) -> Result<Self> {
+    fn read(r: &mut Reader, _id: u32, _version: Version) -> Result<Self> {
+        let mmap_version = r.read_u32()?;
+        let mmap_offset = r.read_u32()?;
+        let director_version = r.read_u32().unwrap_or_default();
+
+        let mut rest = Vec::new();
+        r.read_to_end(&mut rest)?;
+
         Ok(Self {
-            mmap_version: r.read_u32()?,
-            mmap_offset: r.read_u32()?,
-            // director_version: r.read_u32()?,
+            mmap_version,
+            mmap_offset,
+            director_version,
+            rest: RawBytes(rest),
         })
     }
 }
 
+impl WriteChunk for InitialMap {
+    fn write(&self, w: &mut Writer, _version: Version) -> Result<()> {
+        w.write_u32(self.mmap_version)?;
+        w.write_u32(self.mmap_offset)?;
+        w.write_u32(self.director_version)?;
+        w.write_all(&self.rest.0)
+    }
+}
+
 impl InitialMap {}
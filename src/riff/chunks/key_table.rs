@@ -1,16 +1,24 @@
-use std::io::Result;
+use std::{
+    collections::HashSet,
+    io::{Error, ErrorKind, Result},
+};
 
 use thousands::Separable;
 
 use crate::{
     reader::Reader,
-    riff::tags::{self, Tag},
+    riff::{
+        Version,
+        tags::{self, Tag},
+    },
+    writer::Writer,
 };
 
-use super::Chunk;
+use super::{Chunk, WriteChunk};
 
 #[allow(dead_code)]
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct KeyTable {
     header_size: u16,
     entry_size: u16,
@@ -22,7 +30,7 @@ pub struct KeyTable {
 impl Chunk for KeyTable {
     const TAG: Tag = tags::TAG_KEY_;
 
-    fn read(r: &mut Reader, _id: u32) -> Result<Self> {
+    fn read(r: &mut Reader, _id: u32, _version: Version) -> Result<Self> {
         let header_size = r.read_u16()?;
         let entry_size = r.read_u16()?;
         let max_key_count = r.read_u32()?;
@@ -33,13 +41,21 @@ impl Chunk for KeyTable {
             entries.push(KeyTableEntry::read(r)?);
         }
 
-        // TODO(madmoose): Return a proper error
-        assert!(entries.is_sorted_by_key(|e| (e.parent, e.tag)));
-        assert!(
-            entries
-                .iter()
-                .all(|e| e.id != u32::MAX && e.parent != u32::MAX)
-        );
+        if !entries.is_sorted_by_key(|e| (e.parent, e.tag)) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Key table entries are not sorted by parent and tag",
+            ));
+        }
+        if entries
+            .iter()
+            .any(|e| e.id == u32::MAX || e.parent == u32::MAX)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Key table has an entry without a chunk or parent",
+            ));
+        }
 
         Ok(KeyTable {
             header_size,
@@ -51,7 +67,50 @@ impl Chunk for KeyTable {
     }
 }
 
+impl WriteChunk for KeyTable {
+    fn write(&self, w: &mut Writer, _version: Version) -> Result<()> {
+        let used_key_count = self.entries.len() as u32;
+        let max_key_count = self.max_key_count.max(used_key_count);
+
+        w.write_u16(self.header_size)?;
+        w.write_u16(self.entry_size)?;
+        w.write_u32(max_key_count)?;
+        w.write_u32(used_key_count)?;
+        w.write_zeros((self.header_size as usize).saturating_sub(12));
+
+        for e in &self.entries {
+            w.write_u32(e.id)?;
+            w.write_u32(e.parent)?;
+            w.write_i32(e.tag.0)?;
+            w.write_zeros((self.entry_size as usize).saturating_sub(12));
+        }
+
+        let unused = (max_key_count - used_key_count) as usize;
+        w.write_zeros(unused * self.entry_size as usize);
+
+        Ok(())
+    }
+}
+
 impl KeyTable {
+    pub fn entries(&self) -> &[KeyTableEntry] {
+        &self.entries
+    }
+
+    /// Links chunk `id` to `parent`, keeping the table sorted for lookups.
+    pub fn insert(&mut self, id: u32, parent: u32, tag: Tag) {
+        let index = self
+            .entries
+            .partition_point(|e| (e.parent, e.tag) <= (parent, tag));
+        self.entries
+            .insert(index, KeyTableEntry { id, parent, tag });
+    }
+
+    /// Drops every link to or from chunk `id`.
+    pub fn remove(&mut self, id: u32) {
+        self.entries.retain(|e| e.id != id && e.parent != id);
+    }
+
     pub fn find_id_of_chunk_with_parent(&self, tag: Tag, parent: u32) -> Option<u32> {
         self.entries
             .binary_search_by_key(&(parent, tag), |e| (e.parent, e.tag))
@@ -59,7 +118,7 @@ impl KeyTable {
             .map(|r| self.entries[r].id)
     }
 
-    pub fn chunks_with_parent(&self, parent: u32) -> ChunksWithParent {
+    pub fn chunks_with_parent(&self, parent: u32) -> ChunksWithParent<'_> {
         let begin = self.entries.partition_point(|e| e.parent < parent);
         let end = self.entries.partition_point(|e| e.parent < parent + 1);
 
@@ -70,6 +129,67 @@ impl KeyTable {
         }
     }
 
+    /// The entry linking chunk `id` to the chunk that owns it, if any.
+    pub fn owner_of(&self, id: u32) -> Option<&KeyTableEntry> {
+        self.entries.iter().find(|e| e.id == id)
+    }
+
+    /// The owners of chunk `id`, from its parent up to the one that has no
+    /// owner itself, usually the movie's `1024`.
+    pub fn owners(&self, id: u32) -> Vec<u32> {
+        let mut owners = Vec::new();
+        let mut id = id;
+        while let Some(entry) = self.owner_of(id) {
+            // Damaged tables can link a chunk to itself through its owners.
+            if entry.parent == id || owners.contains(&entry.parent) {
+                break;
+            }
+            owners.push(entry.parent);
+            id = entry.parent;
+        }
+
+        owners
+    }
+
+    /// The chunks under `parent`, their children and so on, each followed by
+    /// its own children.
+    pub fn descendants(&self, parent: u32) -> Vec<&KeyTableEntry> {
+        let mut descendants = Vec::new();
+        let mut visited = HashSet::from([parent]);
+        self.collect_descendants(parent, &mut visited, &mut descendants);
+
+        descendants
+    }
+
+    fn collect_descendants<'a>(
+        &'a self,
+        parent: u32,
+        visited: &mut HashSet<u32>,
+        descendants: &mut Vec<&'a KeyTableEntry>,
+    ) {
+        for entry in self.chunks_with_parent(parent) {
+            descendants.push(entry);
+            if visited.insert(entry.id) {
+                self.collect_descendants(entry.id, visited, descendants);
+            }
+        }
+    }
+
+    /// The ids the tree of chunks hangs from: parents that are not linked to
+    /// an owner themselves.
+    pub fn roots(&self) -> Vec<u32> {
+        let mut roots: Vec<u32> = self
+            .entries
+            .iter()
+            .map(|e| e.parent)
+            .filter(|&parent| self.owner_of(parent).is_none())
+            .collect();
+        roots.sort_unstable();
+        roots.dedup();
+
+        roots
+    }
+
     pub fn display(&self) {
         println!("Key Table:");
         println!("==========");
@@ -97,16 +217,6 @@ impl KeyTable {
         println!("+--------+---------+--------+------+");
         println!();
     }
-
-    fn _display_tree(&self, parent: u32, indent: usize) {
-        for e in self.chunks_with_parent(parent) {
-            for _ in 0..indent {
-                print!("\t");
-            }
-            println!("{}", e.tag);
-            self._display_tree(e.id, indent + 1);
-        }
-    }
 }
 
 #[derive(Debug)]
@@ -130,7 +240,8 @@ impl<'a> Iterator for ChunksWithParent<'a> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct KeyTableEntry {
     id: u32,
     parent: u32,
@@ -2,13 +2,17 @@ use std::io::{Result, Seek, SeekFrom};
 
 use crate::{
     reader::{ReadBytesExt, Reader},
-    riff::tags::{self, Tag},
+    riff::{
+        Version,
+        tags::{self, Tag},
+    },
 };
 
 use super::Chunk;
 
 #[allow(dead_code)]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct LingoContext {
     unknown0: u32,
     unknown1: u32,
@@ -29,7 +33,7 @@ pub struct LingoContext {
 impl Chunk for LingoContext {
     const TAG: Tag = tags::TAG_Lctx;
 
-    fn read(r: &mut Reader, _id: u32) -> Result<Self> {
+    fn read(r: &mut Reader, _id: u32, _version: Version) -> Result<Self> {
         let unknown0 = r.read_be_u32()?;
         let unknown1 = r.read_be_u32()?;
         let entry_count = r.read_be_u32()?;
@@ -84,6 +88,7 @@ impl LingoContext {
 
 #[allow(dead_code)]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct LingoContextEntry {
     unknown0: u32,
     script_id: u32,
@@ -2,13 +2,17 @@ use std::io::{Result, Seek, SeekFrom};
 
 use crate::{
     reader::{ReadBytesExt, Reader},
-    riff::tags::{self, Tag},
+    riff::{
+        Version,
+        tags::{self, Tag},
+    },
 };
 
 use super::Chunk;
 
 #[allow(unused)]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct LingoNames {
     unknown0: u32,
     unknown1: u32,
@@ -22,7 +26,7 @@ pub struct LingoNames {
 impl Chunk for LingoNames {
     const TAG: Tag = tags::TAG_Lnam;
 
-    fn read(r: &mut Reader, _id: u32) -> Result<Self> {
+    fn read(r: &mut Reader, _id: u32, _version: Version) -> Result<Self> {
         let unknown0 = r.read_be_u32()?;
         let unknown1 = r.read_be_u32()?;
         let len1 = r.read_be_u32()?;
@@ -50,3 +54,13 @@ impl Chunk for LingoNames {
         Ok(lingo_names)
     }
 }
+
+impl LingoNames {
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn name(&self, id: u16) -> Option<&str> {
+        self.names.get(id as usize).map(String::as_str)
+    }
+}
@@ -1,14 +1,18 @@
-use std::io::{Result, Seek, SeekFrom};
+use std::io::{Read, Result, Seek, SeekFrom};
 
 use crate::{
     reader::{ReadBytesExt, Reader},
-    riff::tags::{self, Tag},
+    riff::{
+        Version,
+        tags::{self, Tag},
+    },
 };
 
 use super::Chunk;
 
 #[allow(dead_code)]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct LingoScript {
     total_length: u32,
     total_length2: u32,
@@ -33,12 +37,16 @@ pub struct LingoScript {
     literals_offset: u32,
     literals_data_count: u32,
     literals_data_offset: u32,
+    property_name_ids: Vec<u16>,
+    global_name_ids: Vec<u16>,
+    handlers: Vec<Handler>,
+    literals: Vec<Literal>,
 }
 
 impl Chunk for LingoScript {
     const TAG: Tag = tags::TAG_Lscr;
 
-    fn read(r: &mut Reader, _id: u32) -> Result<Self> {
+    fn read(r: &mut Reader, _id: u32, version: Version) -> Result<Self> {
         r.seek(SeekFrom::Start(8))?;
         let total_length = r.read_be_u32()?;
         let total_length2 = r.read_be_u32()?;
@@ -81,7 +89,35 @@ impl Chunk for LingoScript {
         let mut handlers = Vec::with_capacity(handlers_count as usize);
         r.seek(SeekFrom::Start(handlers_offset as u64))?;
         for _ in 0..handlers_count {
-            handlers.push(Handler::read(r));
+            handlers.push(Handler::read(r)?);
+        }
+
+        for handler in &mut handlers {
+            handler.read_data(r)?;
+        }
+
+        // Director 5 widened the literal type from 16 to 32 bits.
+        let mut literal_records = Vec::with_capacity(literals_count as usize);
+        r.seek(SeekFrom::Start(literals_offset as u64))?;
+        for _ in 0..literals_count {
+            let literal_type = if version.major() >= 5 {
+                r.read_be_u32()?
+            } else {
+                r.read_be_u16()? as u32
+            };
+            let offset = r.read_be_u32()?;
+
+            literal_records.push((literal_type, offset));
+        }
+
+        let mut literals = Vec::with_capacity(literals_count as usize);
+        for (literal_type, offset) in literal_records {
+            literals.push(Literal::read(
+                r,
+                literal_type,
+                literals_data_offset,
+                offset,
+            )?);
         }
 
         let script = LingoScript {
@@ -108,15 +144,46 @@ impl Chunk for LingoScript {
             literals_offset,
             literals_data_count,
             literals_data_offset,
+            property_name_ids,
+            global_name_ids,
+            handlers,
+            literals,
         };
 
         Ok(script)
     }
 }
 
+impl LingoScript {
+    pub fn script_number(&self) -> u16 {
+        self.script_number
+    }
+
+    pub fn cast_id(&self) -> u32 {
+        self.cast_id
+    }
+
+    pub fn property_name_ids(&self) -> &[u16] {
+        &self.property_name_ids
+    }
+
+    pub fn global_name_ids(&self) -> &[u16] {
+        &self.global_name_ids
+    }
+
+    pub fn handlers(&self) -> &[Handler] {
+        &self.handlers
+    }
+
+    pub fn literals(&self) -> &[Literal] {
+        &self.literals
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Debug)]
-struct Handler {
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Handler {
     name_id: u16,
     vector_pos: u16,
     compiled_len: u32,
@@ -131,6 +198,7 @@ struct Handler {
     unknown2: u16,
     line_count: u16,
     line_offset: u32,
+    #[cfg_attr(feature = "serde", serde(skip))]
     bytecode: Vec<u8>,
     argument_name_ids: Vec<u16>,
     local_name_ids: Vec<u16>,
@@ -179,4 +247,81 @@ impl Handler {
             global_name_ids,
         })
     }
+
+    fn read_data(&mut self, r: &mut Reader) -> Result<()> {
+        self.bytecode = vec![0; self.compiled_len as usize];
+        r.seek(SeekFrom::Start(self.compiled_offset as u64))?;
+        r.read_exact(&mut self.bytecode)?;
+
+        self.argument_name_ids = read_name_ids(r, self.argument_offset, self.argument_count)?;
+        self.local_name_ids = read_name_ids(r, self.locals_offset, self.locals_count)?;
+        self.global_name_ids = read_name_ids(r, self.globals_offset, self.globals_count)?;
+
+        Ok(())
+    }
+
+    pub fn name_id(&self) -> u16 {
+        self.name_id
+    }
+
+    pub fn bytecode(&self) -> &[u8] {
+        &self.bytecode
+    }
+
+    pub fn argument_name_ids(&self) -> &[u16] {
+        &self.argument_name_ids
+    }
+
+    pub fn local_name_ids(&self) -> &[u16] {
+        &self.local_name_ids
+    }
+
+    pub fn global_name_ids(&self) -> &[u16] {
+        &self.global_name_ids
+    }
+}
+
+fn read_name_ids(r: &mut Reader, offset: u32, count: u16) -> Result<Vec<u16>> {
+    let mut name_ids = Vec::with_capacity(count as usize);
+
+    r.seek(SeekFrom::Start(offset as u64))?;
+    for _ in 0..count {
+        name_ids.push(r.read_be_u16()?);
+    }
+
+    Ok(name_ids)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Literal {
+    String(String),
+    Int(i32),
+    Float(f64),
+    Unknown { literal_type: u32, data: Vec<u8> },
+}
+
+impl Literal {
+    fn read(r: &mut Reader, literal_type: u32, data_offset: u32, offset: u32) -> Result<Self> {
+        // Integers are stored inline in place of the offset
+        if literal_type == 4 {
+            return Ok(Literal::Int(offset as i32));
+        }
+
+        r.seek(SeekFrom::Start(data_offset as u64 + offset as u64))?;
+        let len = r.read_be_u32()? as usize;
+
+        let literal = match (literal_type, len) {
+            (1, _) => Literal::String(r.read_fixed_str(len)?),
+            (9, 8) => Literal::Float(f64::from_bits(r.read_be_u64()?)),
+            (9, 10) => Literal::Float(r.read_be_f80()?),
+            _ => {
+                let mut data = vec![0; len];
+                r.read_exact(&mut data)?;
+                Literal::Unknown { literal_type, data }
+            }
+        };
+
+        Ok(literal)
+    }
 }
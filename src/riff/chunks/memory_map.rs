@@ -1,16 +1,21 @@
-use std::io::Result;
+use std::{collections::HashSet, io::Result};
 
 use thousands::Separable;
 
 use crate::{
     reader::Reader,
-    riff::tags::{self, Tag},
+    riff::{
+        Version,
+        tags::{self, Tag},
+    },
+    writer::Writer,
 };
 
-use super::Chunk;
+use super::{Chunk, WriteChunk};
 
 #[allow(unused)]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MemoryMap {
     header_size: u16,
     entry_size: u16,
@@ -25,7 +30,7 @@ pub struct MemoryMap {
 impl Chunk for MemoryMap {
     const TAG: Tag = tags::TAG_mmap;
 
-    fn read(r: &mut Reader, _id: u32) -> Result<Self> {
+    fn read(r: &mut Reader, _id: u32, _version: Version) -> Result<Self> {
         let header_size = r.read_u16()?;
         let entry_size = r.read_u16()?;
         let chunk_count_max = r.read_u32()?;
@@ -54,7 +59,52 @@ impl Chunk for MemoryMap {
     }
 }
 
+impl WriteChunk for MemoryMap {
+    fn write(&self, w: &mut Writer, _version: Version) -> Result<()> {
+        let chunk_count_used = self.entries.len() as u32;
+        let chunk_count_max = self.chunk_count_max.max(chunk_count_used);
+
+        w.write_u16(self.header_size)?;
+        w.write_u16(self.entry_size)?;
+        w.write_u32(chunk_count_max)?;
+        w.write_u32(chunk_count_used)?;
+        w.write_u32(self.junk_head)?;
+        w.write_u32(self.junk_head2)?;
+        w.write_u32(self.free_head)?;
+        w.write_zeros((self.header_size as usize).saturating_sub(24));
+
+        for e in &self.entries {
+            w.write_i32(e.tag.0)?;
+            w.write_u32(e.len)?;
+            w.write_u32(e.pos)?;
+            w.write_u16(e.flags)?;
+            w.write_u16(e.unknown0)?;
+            w.write_u32(e.next)?;
+            w.write_zeros((self.entry_size as usize).saturating_sub(20));
+        }
+
+        let unused = (chunk_count_max - chunk_count_used) as usize;
+        w.write_zeros(unused * self.entry_size as usize);
+
+        Ok(())
+    }
+}
+
 impl MemoryMap {
+    /// The size of the chunk contents when written.
+    pub fn written_len(&self) -> u32 {
+        let count = self.chunk_count_max.max(self.entries.len() as u32);
+        self.header_size as u32 + count * self.entry_size as u32
+    }
+
+    pub fn entries(&self) -> &[MemoryMapEntry] {
+        &self.entries
+    }
+
+    pub(crate) fn entries_mut(&mut self) -> &mut Vec<MemoryMapEntry> {
+        &mut self.entries
+    }
+
     pub fn entry_by_index(&self, index: u32) -> Option<&MemoryMapEntry> {
         self.entries.get(index as usize)
     }
@@ -63,27 +113,215 @@ impl MemoryMap {
         self.entries.iter().find(|e| e.tag == tag)
     }
 
+    /// The entries of the free list, the ids Director can give to new chunks.
+    pub fn free_entries(&self) -> ListEntries<'_> {
+        ListEntries::new(self, self.free_head)
+    }
+
+    /// The entries of the junk list, deleted chunks whose space in the file
+    /// can be reclaimed.
+    pub fn junk_entries(&self) -> ListEntries<'_> {
+        ListEntries::new(self, self.junk_head)
+    }
+
+    /// Frees entry `id`, adding it to the front of the free list.
+    pub(crate) fn free_entry(&mut self, id: u32) {
+        let next = self.free_head;
+        let entry = &mut self.entries[id as usize];
+        entry.tag = tags::TAG_free;
+        entry.pos = 0;
+        entry.len = 0;
+        entry.next = next;
+        self.free_head = id;
+    }
+
+    /// Checks that the free and junk lists link every `free` and `junk` entry
+    /// once, and nothing else.
+    pub fn check_lists(&self) -> Vec<ListProblem> {
+        let mut problems = Vec::new();
+        let mut listed = HashSet::new();
+
+        let lists = [
+            ("free", self.free_head, EntryState::Free),
+            ("junk", self.junk_head, EntryState::Junk),
+        ];
+        for (name, head, state) in lists {
+            let mut previous = None;
+            let mut next = head;
+            while !is_list_end(next) {
+                let Some(entry) = self.entry_by_index(next) else {
+                    problems.push(ListProblem {
+                        entry_id: previous,
+                        message: format!(
+                            "The {name} list links entry {next}, which does not exist"
+                        ),
+                    });
+                    break;
+                };
+                if !listed.insert(entry.id) {
+                    problems.push(ListProblem {
+                        entry_id: Some(entry.id),
+                        message: format!("The {name} list links entry {} a second time", entry.id),
+                    });
+                    break;
+                }
+                if entry.state() != state {
+                    problems.push(ListProblem {
+                        entry_id: Some(entry.id),
+                        message: format!("The {name} list links a '{}' chunk", entry.tag),
+                    });
+                }
+
+                previous = Some(entry.id);
+                next = entry.next;
+            }
+        }
+
+        if !is_list_end(self.junk_head2)
+            && self
+                .entry_by_index(self.junk_head2)
+                .is_none_or(|entry| entry.state() != EntryState::Junk)
+        {
+            problems.push(ListProblem {
+                entry_id: None,
+                message: format!(
+                    "The second junk list head is entry {}, which is not a junk chunk",
+                    self.junk_head2
+                ),
+            });
+        }
+
+        for entry in &self.entries {
+            let name = match entry.state() {
+                EntryState::Free => "free",
+                EntryState::Junk => "junk",
+                EntryState::Live | EntryState::Unused => continue,
+            };
+            if !listed.contains(&entry.id) {
+                problems.push(ListProblem {
+                    entry_id: Some(entry.id),
+                    message: format!("A {name} chunk that is not on the {name} list"),
+                });
+            }
+        }
+
+        problems
+    }
+
     pub fn display(&self) {
         println!("Memory Map:");
-        println!("==============================================");
-        println!("|      id | tag  |      offset |      length |");
-        println!("+---------+------+-------------+-------------+");
+        println!("=======================================================");
+        println!("|      id | tag  |      offset |      length | state  |");
+        println!("+---------+------+-------------+-------------+--------+");
         for e in self.entries.iter() {
             println!(
-                "|  {:6} | {:4} | {:>11} | {:>11} |",
+                "|  {:6} | {:4} | {:>11} | {:>11} | {:6} |",
                 e.id,
                 e.tag,
                 e.pos.separate_with_commas(),
-                e.len.separate_with_commas()
+                e.len.separate_with_commas(),
+                e.state().name()
             );
         }
-        println!("+---------+------+-------------+-------------+");
+        println!("+---------+------+-------------+-------------+--------+");
+
+        let list = |entries: ListEntries| {
+            entries
+                .map(|e| e.id.to_string())
+                .intersperse(" -> ".to_string())
+                .collect::<String>()
+        };
+        println!("Free list: {}", list(self.free_entries()));
+        println!("Junk list: {}", list(self.junk_entries()));
+        for problem in self.check_lists() {
+            println!("Warning: {}", problem.message);
+        }
         println!();
     }
 }
 
+/// Whether `next` ends a list. Entry 0 is the `RIFX` chunk, which is never
+/// free, so a zero link also ends one.
+fn is_list_end(next: u32) -> bool {
+    next == 0 || next == u32::MAX
+}
+
+/// A free or junk list that does not link what it should, found by
+/// `MemoryMap::check_lists`.
+#[derive(Debug, Clone)]
+pub struct ListProblem {
+    /// The entry the problem is at, if it is at one.
+    pub entry_id: Option<u32>,
+    pub message: String,
+}
+
+/// Walks the free or junk list of a memory map, stopping at a link to a
+/// missing entry, and after as many steps as there are entries should the
+/// list loop.
+pub struct ListEntries<'a> {
+    mmap: &'a MemoryMap,
+    next: u32,
+    remaining: usize,
+}
+
+impl<'a> ListEntries<'a> {
+    fn new(mmap: &'a MemoryMap, head: u32) -> Self {
+        Self {
+            mmap,
+            next: head,
+            remaining: mmap.entries.len(),
+        }
+    }
+}
+
+impl<'a> Iterator for ListEntries<'a> {
+    type Item = &'a MemoryMapEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if is_list_end(self.next) || self.remaining == 0 {
+            return None;
+        }
+
+        let entry = self.mmap.entry_by_index(self.next)?;
+        self.next = entry.next;
+        self.remaining -= 1;
+
+        Some(entry)
+    }
+}
+
+/// What a memory map entry is used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum EntryState {
+    /// A chunk of the movie.
+    Live,
+    /// An id with no chunk, which Director can give to a new one.
+    Free,
+    /// A deleted chunk, whose space in the file can be reclaimed.
+    Junk,
+    /// An entry that was never used, with no tag.
+    Unused,
+}
+
+impl EntryState {
+    pub fn name(self) -> &'static str {
+        match self {
+            EntryState::Live => "live",
+            EntryState::Free => "free",
+            EntryState::Junk => "junk",
+            EntryState::Unused => "unused",
+        }
+    }
+}
+
 #[allow(unused)]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MemoryMapEntry {
     id: u32,
     tag: Tag,
@@ -95,6 +333,18 @@ pub struct MemoryMapEntry {
 }
 
 impl MemoryMapEntry {
+    pub(crate) fn new(id: u32, tag: Tag, len: u32, pos: u32) -> Self {
+        MemoryMapEntry {
+            id,
+            tag,
+            len,
+            pos,
+            flags: 0,
+            unknown0: 0,
+            next: 0,
+        }
+    }
+
     pub fn read(r: &mut Reader, id: u32) -> Result<Self> {
         Ok(MemoryMapEntry {
             id,
@@ -119,7 +369,161 @@ impl MemoryMapEntry {
         self.len
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
     pub fn pos(&self) -> u32 {
         self.pos
     }
+
+    pub fn flags(&self) -> u16 {
+        self.flags
+    }
+
+    /// The id of the next entry on the free or junk list this entry is on.
+    pub fn next(&self) -> u32 {
+        self.next
+    }
+
+    pub fn state(&self) -> EntryState {
+        match self.tag {
+            tags::TAG_free => EntryState::Free,
+            tags::TAG_junk => EntryState::Junk,
+            Tag(0) => EntryState::Unused,
+            _ => EntryState::Live,
+        }
+    }
+
+    pub(crate) fn set_location(&mut self, pos: u32, len: u32) {
+        self.pos = pos;
+        self.len = len;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const END: u32 = u32::MAX;
+
+    /// A memory map of the file's own chunks followed by entries with `tags`
+    /// and `next` links, starting at id 3.
+    fn mmap(tags: &[(Tag, u32)], free_head: u32, junk_head: u32) -> MemoryMap {
+        let mut entries = vec![
+            MemoryMapEntry::new(0, tags::TAG_RIFX, 0, 0),
+            MemoryMapEntry::new(1, tags::TAG_imap, 24, 12),
+            MemoryMapEntry::new(2, tags::TAG_mmap, 0, 44),
+        ];
+        for &(tag, next) in tags {
+            let mut entry = MemoryMapEntry::new(entries.len() as u32, tag, 0, 0);
+            entry.next = next;
+            entries.push(entry);
+        }
+
+        MemoryMap {
+            header_size: 24,
+            entry_size: 20,
+            chunk_count_max: entries.len() as u32,
+            chunk_count_used: entries.len() as u32,
+            junk_head,
+            junk_head2: END,
+            free_head,
+            entries,
+        }
+    }
+
+    fn ids(entries: ListEntries) -> Vec<u32> {
+        entries.map(|entry| entry.id()).collect()
+    }
+
+    fn problems(mmap: &MemoryMap) -> Vec<Option<u32>> {
+        mmap.check_lists()
+            .into_iter()
+            .map(|problem| problem.entry_id)
+            .collect()
+    }
+
+    #[test]
+    fn walks_well_formed_lists() {
+        let mmap = mmap(
+            &[
+                (tags::TAG_free, 5),
+                (tags::TAG_CASt, 0),
+                (tags::TAG_free, END),
+                (tags::TAG_junk, 0),
+                (Tag(0), 0),
+            ],
+            3,
+            6,
+        );
+
+        assert_eq!(ids(mmap.free_entries()), [3, 5]);
+        assert_eq!(ids(mmap.junk_entries()), [6]);
+        assert_eq!(problems(&mmap), []);
+
+        let states: Vec<_> = mmap.entries().iter().map(|e| e.state()).collect();
+        assert_eq!(
+            states[2..],
+            [
+                EntryState::Live,
+                EntryState::Free,
+                EntryState::Live,
+                EntryState::Free,
+                EntryState::Junk,
+                EntryState::Unused,
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_at_a_looping_list() {
+        let mmap = mmap(&[(tags::TAG_free, 4), (tags::TAG_free, 3)], 3, END);
+
+        assert_eq!(ids(mmap.free_entries()).len(), mmap.entries().len());
+        assert_eq!(problems(&mmap), [Some(3)]);
+        assert!(mmap.check_lists()[0].message.contains("second time"));
+    }
+
+    #[test]
+    fn stops_at_a_link_to_a_missing_entry() {
+        let mmap = mmap(&[(tags::TAG_free, 99)], 3, END);
+
+        assert_eq!(ids(mmap.free_entries()), [3]);
+        assert_eq!(problems(&mmap), [Some(3)]);
+        assert!(mmap.check_lists()[0].message.contains("does not exist"));
+    }
+
+    #[test]
+    fn reports_entries_on_the_wrong_list() {
+        let mmap = mmap(&[(tags::TAG_free, 4), (tags::TAG_CASt, END)], 3, END);
+
+        assert_eq!(problems(&mmap), [Some(4)]);
+        assert!(mmap.check_lists()[0].message.contains("'CASt'"));
+    }
+
+    #[test]
+    fn reports_free_entries_missing_from_the_list() {
+        let mmap = mmap(
+            &[
+                (tags::TAG_free, END),
+                (tags::TAG_free, END),
+                (tags::TAG_junk, 0),
+            ],
+            3,
+            END,
+        );
+
+        assert_eq!(problems(&mmap), [Some(4), Some(5)]);
+    }
+
+    #[test]
+    fn frees_entries_onto_the_free_list() {
+        let mut mmap = mmap(&[(tags::TAG_free, END), (tags::TAG_CASt, 0)], 3, END);
+        mmap.free_entry(4);
+
+        assert_eq!(mmap.entries()[4].state(), EntryState::Free);
+        assert_eq!(ids(mmap.free_entries()), [4, 3]);
+        assert_eq!(problems(&mmap), []);
+    }
 }
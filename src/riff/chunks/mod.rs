@@ -1,3 +1,4 @@
+mod any_chunk;
 mod bitmap_data;
 mod cast_table;
 mod color_lookup_table;
@@ -11,11 +12,16 @@ mod lingo_names;
 mod lingo_script;
 mod memory_map;
 mod score;
+mod sound_data;
 mod styled_text;
 mod thumbnail;
 
-use std::io::{Result, Seek};
+use std::{
+    fmt::Debug,
+    io::{Error, ErrorKind, Result, Seek},
+};
 
+pub use any_chunk::*;
 pub use bitmap_data::*;
 pub use cast_table::*;
 pub use color_lookup_table::*;
@@ -29,40 +35,79 @@ pub use lingo_names::*;
 pub use lingo_script::*;
 pub use memory_map::*;
 pub use score::*;
+pub use sound_data::*;
 pub use styled_text::*;
 pub use thumbnail::*;
 
-use crate::reader::Reader;
+use crate::{reader::Reader, writer::Writer};
 
-use super::tags::{Tag, TagAsHex};
+use super::{
+    Version,
+    tags::{Tag, TagAsHex},
+};
 
 pub trait Chunk: Sized {
     const TAG: Tag;
-    fn read(r: &mut Reader, id: u32) -> Result<Self>;
+    fn read(r: &mut Reader, id: u32, version: Version) -> Result<Self>;
 }
 
-pub fn read_chunk_from_reader<C: Chunk>(reader: &mut Reader, id: u32) -> Result<C> {
+/// A chunk that can be serialized back into a movie.
+pub trait WriteChunk: Chunk {
+    /// Writes the chunk contents, without the tag and length header.
+    fn write(&self, w: &mut Writer, version: Version) -> Result<()>;
+}
+
+/// Bytes a chunk keeps from the file so that the parts it does not parse are
+/// written back unchanged.
+#[derive(Default, Clone, PartialEq, Eq)]
+pub(crate) struct RawBytes(pub Vec<u8>);
+
+impl Debug for RawBytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RawBytes({} bytes)", self.0.len())
+    }
+}
+
+pub fn read_chunk_from_reader<C: Chunk>(
+    reader: &mut Reader,
+    id: u32,
+    version: Version,
+) -> Result<C> {
     let expected_tag = C::TAG;
-    read_chunk_from_reader_with_tag(reader, id, expected_tag)
+    read_chunk_from_reader_with_tag(reader, id, expected_tag, version)
 }
 
 pub fn read_chunk_from_reader_with_tag<C: Chunk>(
     reader: &mut Reader,
     id: u32,
     expected_tag: Tag,
+    version: Version,
 ) -> Result<C> {
+    let size = read_chunk_header(reader, expected_tag)?;
+
     let position = reader.stream_position()?;
-    let tag = Tag(reader.read_i32()?);
-    let mut size = reader.read_u32()? as u64;
+    let mut chunk_reader = reader
+        .subset(position as usize, size as usize)
+        .map_err(|err| {
+            Error::new(
+                err.kind(),
+                format!("Chunk '{expected_tag}' at 0x{:x}: {err}", position - 8),
+            )
+        })?;
 
-    // TODO: This should be more generous
-    if position + 8 == reader.stream_len()? && size != 0 {
-        size = 0;
-    }
+    C::read(&mut chunk_reader, id, version)
+}
+
+/// Reads the tag and size of the chunk at the reader's position, checking
+/// that it is an `expected_tag` chunk.
+pub(crate) fn read_chunk_header(reader: &mut Reader, expected_tag: Tag) -> Result<u32> {
+    let position = reader.stream_position()?;
+    let tag = Tag(reader.read_i32()?);
+    let size = reader.read_u32()?;
 
     if tag != expected_tag {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::InvalidData,
+        return Err(Error::new(
+            ErrorKind::InvalidData,
             format!(
                 "Expected tag '{}' (size 0x{:x}), found '{}' [{}] at 0x{:x}",
                 expected_tag,
@@ -74,8 +119,5 @@ pub fn read_chunk_from_reader_with_tag<C: Chunk>(
         ));
     }
 
-    let position = reader.stream_position()?;
-    let mut chunk_reader = reader.subset(position as usize, size as usize);
-
-    C::read(&mut chunk_reader, id)
+    Ok(size)
 }
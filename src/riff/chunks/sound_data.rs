@@ -0,0 +1,161 @@
+use std::{
+    fmt::Debug,
+    io::{Error, ErrorKind, Result, Seek, SeekFrom},
+};
+
+use crate::{
+    audio::Pcm,
+    reader::{ReadBytesExt, Reader},
+    riff::{
+        Version,
+        tags::{self, Tag},
+    },
+};
+
+use super::Chunk;
+
+/// A sound stored as a classic Mac OS `'snd '` resource.
+pub struct SoundData {
+    buf: Vec<u8>,
+}
+
+impl Chunk for SoundData {
+    const TAG: Tag = tags::TAG_snd_;
+
+    fn read(r: &mut Reader, _id: u32, _version: Version) -> Result<Self> {
+        let mut buf = Vec::new();
+        r.read_to_end(&mut buf)?;
+
+        Ok(Self { buf })
+    }
+}
+
+impl Debug for SoundData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SoundData").finish()
+    }
+}
+
+const BUFFER_CMD: u16 = 81;
+const SOUND_CMD: u16 = 80;
+
+const STANDARD_HEADER: u8 = 0x00;
+const EXTENDED_HEADER: u8 = 0xff;
+const COMPRESSED_HEADER: u8 = 0xfe;
+
+impl SoundData {
+    pub fn buf(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Decodes the sampled sound header the resource's sound or buffer
+    /// command points to.
+    pub fn pcm(&self) -> Result<Pcm> {
+        let mut r = Reader::new(&self.buf);
+
+        let format = r.read_be_u16()?;
+        match format {
+            1 => {
+                let modifier_count = r.read_be_u16()?;
+                r.seek_relative(6 * modifier_count as i64)?;
+            }
+            2 => {
+                let _ref_count = r.read_be_u16()?;
+            }
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Unknown 'snd ' resource format {format}"),
+                ));
+            }
+        }
+
+        let command_count = r.read_be_u16()?;
+        let mut header_offset = None;
+        for _ in 0..command_count {
+            let command = r.read_be_u16()?;
+            let _param1 = r.read_be_u16()?;
+            let param2 = r.read_be_u32()?;
+
+            // The high bit marks the parameter as an offset into the resource.
+            if matches!(command & 0x7fff, BUFFER_CMD | SOUND_CMD) {
+                header_offset = Some(param2);
+            }
+        }
+
+        let Some(header_offset) = header_offset else {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "'snd ' resource has no sampled sound",
+            ));
+        };
+
+        r.seek(SeekFrom::Start(header_offset as u64))?;
+        let _sample_ptr = r.read_be_u32()?;
+        let length_or_channels = r.read_be_u32()?;
+        let sample_rate = r.read_be_u32()? >> 16;
+        let _loop_start = r.read_be_u32()?;
+        let _loop_end = r.read_be_u32()?;
+        let encoding = r.read_u8()?;
+        let _base_frequency = r.read_u8()?;
+
+        match encoding {
+            STANDARD_HEADER => {
+                let samples = self.samples(r.stream_position()?, length_or_channels as usize)?;
+
+                Ok(Pcm {
+                    sample_rate,
+                    channels: 1,
+                    bits_per_sample: 8,
+                    samples: samples.to_vec(),
+                })
+            }
+            EXTENDED_HEADER => {
+                let channels = length_or_channels as u16;
+                let frame_count = r.read_be_u32()? as usize;
+                let _aiff_sample_rate = r.read_be_f80()?;
+                r.seek_relative(12)?;
+                let bits_per_sample = r.read_be_u16()?;
+                r.seek_relative(14)?;
+
+                let len = frame_count * channels as usize * bits_per_sample.div_ceil(8) as usize;
+                let samples = self.samples(r.stream_position()?, len)?;
+
+                // 8-bit samples are already unsigned, wider ones are signed
+                // big-endian.
+                if bits_per_sample == 8 {
+                    Ok(Pcm {
+                        sample_rate,
+                        channels,
+                        bits_per_sample,
+                        samples: samples.to_vec(),
+                    })
+                } else {
+                    Ok(Pcm::from_be_samples(
+                        sample_rate,
+                        channels,
+                        bits_per_sample,
+                        samples,
+                    ))
+                }
+            }
+            COMPRESSED_HEADER => Err(Error::new(
+                ErrorKind::Unsupported,
+                "Compressed 'snd ' resources are not supported",
+            )),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Unknown sampled sound encoding {encoding:#x}"),
+            )),
+        }
+    }
+
+    fn samples(&self, position: u64, len: usize) -> Result<&[u8]> {
+        let start = position as usize;
+        let end = (start + len).min(self.buf.len());
+
+        self.buf
+            .get(start..end)
+            .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "Sample data out of bounds"))
+    }
+}
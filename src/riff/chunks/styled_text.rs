@@ -1,54 +1,245 @@
-use std::io::{Read, Result, Seek};
+use std::io::{Error, ErrorKind, Read, Result, Write};
 
 use crate::{
-    reader::{ReadBytesExt, Reader},
-    riff::tags::{self, Tag},
+    gfx,
+    reader::{ReadBytesExt, Reader, TextEncoding},
+    riff::{
+        Version,
+        tags::{self, Tag},
+    },
+    writer::{WriteBytesExt, Writer},
 };
 
-use super::Chunk;
+use super::{Chunk, WriteChunk};
 
-#[allow(unused)]
 #[derive(Debug, Default)]
 pub struct StyledText {
     text: Vec<u8>,
     style_runs: Vec<StyleRun>,
+    encoding: TextEncoding,
 }
 
-#[allow(unused)]
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct StyleRun {
-    start_offset: i32,
+    pub start_offset: i32,
+    pub height: u16,
+    pub ascent: u16,
+    pub font_id: u16,
+    pub style: u8,
+    pub font_size: u16,
+    pub color: gfx::Rgb161616,
+}
+
+impl StyleRun {
+    pub const BOLD: u8 = 0x01;
+    pub const ITALIC: u8 = 0x02;
+    pub const UNDERLINE: u8 = 0x04;
+
+    fn read(r: &mut Reader) -> Result<Self> {
+        let start_offset = r.read_be_i32()?;
+        let height = r.read_be_u16()?;
+        let ascent = r.read_be_u16()?;
+        let font_id = r.read_be_u16()?;
+        let style = r.read_u8()?;
+        let _ = r.read_u8()?;
+        let font_size = r.read_be_u16()?;
+        let color = gfx::Rgb161616 {
+            r: r.read_be_u16()?,
+            g: r.read_be_u16()?,
+            b: r.read_be_u16()?,
+        };
+
+        Ok(StyleRun {
+            start_offset,
+            height,
+            ascent,
+            font_id,
+            style,
+            font_size,
+            color,
+        })
+    }
+
+    fn write(&self, w: &mut Writer) -> Result<()> {
+        w.write_be_i32(self.start_offset)?;
+        w.write_be_u16(self.height)?;
+        w.write_be_u16(self.ascent)?;
+        w.write_be_u16(self.font_id)?;
+        w.write_u8(self.style)?;
+        w.write_u8(0)?;
+        w.write_be_u16(self.font_size)?;
+        w.write_be_u16(self.color.r)?;
+        w.write_be_u16(self.color.g)?;
+        w.write_be_u16(self.color.b)
+    }
+
+    pub fn is_bold(&self) -> bool {
+        self.style & Self::BOLD != 0
+    }
+
+    pub fn is_italic(&self) -> bool {
+        self.style & Self::ITALIC != 0
+    }
+
+    pub fn is_underline(&self) -> bool {
+        self.style & Self::UNDERLINE != 0
+    }
 }
 
 impl Chunk for StyledText {
     const TAG: Tag = tags::TAG_STXT;
 
-    fn read(r: &mut Reader, _id: u32) -> Result<Self> {
+    fn read(r: &mut Reader, _id: u32, _version: Version) -> Result<Self> {
         let header_size = r.read_be_u32()?;
         let text_size = r.read_be_u32()? as usize;
         let style_size = r.read_be_u32()? as usize;
 
-        assert!(header_size == 12);
+        if header_size != 12 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Styled text header is {header_size} bytes long, expected 12"),
+            ));
+        }
 
         let mut text = vec![0; text_size];
         r.read_exact(&mut text)?;
 
         let style_run_count = r.read_be_u16()? as usize;
-        assert_eq!(style_size, 20 * style_run_count + 2);
+        if style_size != 20 * style_run_count + 2 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Styled text has {style_run_count} style runs in {style_size} bytes"),
+            ));
+        }
 
         let mut style_runs = Vec::with_capacity(style_run_count);
 
         for _ in 0..style_run_count {
-            let style_run = StyleRun {
-                start_offset: r.read_be_i32()?,
-            };
-            r.seek_relative(16)?;
+            style_runs.push(StyleRun::read(r)?);
+        }
+
+        Ok(Self {
+            text,
+            style_runs,
+            encoding: r.encoding(),
+        })
+    }
+}
+
+impl WriteChunk for StyledText {
+    fn write(&self, w: &mut Writer, _version: Version) -> Result<()> {
+        let style_size = 20 * self.style_runs.len() + 2;
 
-            style_runs.push(style_run);
+        w.write_be_u32(12)?;
+        w.write_be_u32(self.text.len() as u32)?;
+        w.write_be_u32(style_size as u32)?;
+        w.write_all(&self.text)?;
+
+        w.write_be_u16(self.style_runs.len() as u16)?;
+        for style_run in &self.style_runs {
+            style_run.write(w)?;
         }
 
-        Ok(Self { text, style_runs })
+        Ok(())
     }
 }
 
-impl StyledText {}
+#[cfg(feature = "serde")]
+impl serde::Serialize for StyledText {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("StyledText", 2)?;
+        state.serialize_field("text", &self.text())?;
+        state.serialize_field("style_runs", &self.style_runs)?;
+        state.end()
+    }
+}
+
+impl StyledText {
+    pub fn raw_text(&self) -> &[u8] {
+        &self.text
+    }
+
+    pub fn text(&self) -> String {
+        self.encoding.decode(&self.text)
+    }
+
+    /// The encoding of the raw text, which is that of the movie.
+    pub fn encoding(&self) -> TextEncoding {
+        self.encoding
+    }
+
+    pub fn style_runs(&self) -> &[StyleRun] {
+        &self.style_runs
+    }
+
+    /// Replaces the text, moving the style runs to the same relative
+    /// positions in the new text.
+    pub fn set_text(&mut self, text: &str) -> Result<()> {
+        // The offsets at which characters start, as multi-byte encodings
+        // cannot start a run in the middle of one.
+        let mut char_offsets = vec![0];
+        let mut buf = [0; 4];
+        for c in text.chars() {
+            let len = self.encoding.encode(c.encode_utf8(&mut buf))?.len();
+            char_offsets.push(char_offsets.last().unwrap() + len as i64);
+        }
+
+        let old_len = self.text.len().max(1) as i64;
+        let new_len = *char_offsets.last().unwrap();
+
+        self.set_text_with_mapping(text, |start_offset| {
+            let offset = start_offset as i64 * new_len / old_len;
+            let index = char_offsets.partition_point(|&o| o <= offset);
+            char_offsets[index.saturating_sub(1)] as i32
+        })
+    }
+
+    /// Replaces the text, moving each style run to the offset `map` returns
+    /// for its current start offset.
+    ///
+    /// The first run always starts the text. Runs that end up empty or past
+    /// the end of the text are dropped.
+    pub fn set_text_with_mapping(
+        &mut self,
+        text: &str,
+        mut map: impl FnMut(i32) -> i32,
+    ) -> Result<()> {
+        self.text = self.encoding.encode(text)?;
+
+        let text_len = self.text.len() as i32;
+        let mut style_runs: Vec<StyleRun> = Vec::with_capacity(self.style_runs.len());
+        for (i, style_run) in self.style_runs.iter().enumerate() {
+            let start_offset = if i == 0 {
+                0
+            } else {
+                map(style_run.start_offset).clamp(0, text_len)
+            };
+
+            if i > 0 && start_offset >= text_len {
+                break;
+            }
+
+            // Runs that start where this one does, or later, cover nothing.
+            while style_runs
+                .last()
+                .is_some_and(|previous| previous.start_offset >= start_offset)
+            {
+                style_runs.pop();
+            }
+
+            style_runs.push(StyleRun {
+                start_offset,
+                ..style_run.clone()
+            });
+        }
+        self.style_runs = style_runs;
+
+        Ok(())
+    }
+}
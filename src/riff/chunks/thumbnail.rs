@@ -2,7 +2,10 @@ use std::io::{Result, Seek};
 
 use crate::{
     reader::Reader,
-    riff::tags::{self, Tag},
+    riff::{
+        Version,
+        tags::{self, Tag},
+    },
 };
 
 use super::Chunk;
@@ -15,7 +18,7 @@ pub struct Thumbnail {
 impl Chunk for Thumbnail {
     const TAG: Tag = tags::TAG_THUM;
 
-    fn read(r: &mut Reader, _id: u32) -> Result<Self> {
+    fn read(r: &mut Reader, _id: u32, _version: Version) -> Result<Self> {
         let buf = if r.stream_len()? == 0 {
             None
         } else {
@@ -0,0 +1,297 @@
+use std::fmt::Write;
+
+use super::{
+    Version,
+    chunks::{Handler, LingoNames, LingoScript, Literal},
+};
+
+/// A single decoded Lingo bytecode instruction.
+#[derive(Debug, Clone, Copy)]
+pub struct Instruction {
+    pub offset: usize,
+    pub opcode: u8,
+    pub operand: Option<i32>,
+}
+
+impl Instruction {
+    /// The opcode with the operand size bits removed.
+    pub fn base_opcode(&self) -> u8 {
+        if self.opcode >= 0x40 {
+            0x40 + self.opcode % 0x40
+        } else {
+            self.opcode
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        opcode_name(self.base_opcode())
+    }
+}
+
+pub fn opcode_name(opcode: u8) -> &'static str {
+    match opcode {
+        0x01 => "ret",
+        0x02 => "retfactory",
+        0x03 => "pushzero",
+        0x04 => "mul",
+        0x05 => "add",
+        0x06 => "sub",
+        0x07 => "div",
+        0x08 => "mod",
+        0x09 => "inv",
+        0x0a => "joinstr",
+        0x0b => "joinpadstr",
+        0x0c => "lt",
+        0x0d => "lteq",
+        0x0e => "nteq",
+        0x0f => "eq",
+        0x10 => "gt",
+        0x11 => "gteq",
+        0x12 => "and",
+        0x13 => "or",
+        0x14 => "not",
+        0x15 => "containsstr",
+        0x16 => "contains0str",
+        0x17 => "getchunk",
+        0x18 => "hilitechunk",
+        0x19 => "ontospr",
+        0x1a => "intospr",
+        0x1b => "getfield",
+        0x1c => "starttell",
+        0x1d => "endtell",
+        0x1e => "pushlist",
+        0x1f => "pushproplist",
+        0x21 => "swap",
+        0x41 => "pushint8",
+        0x42 => "pusharglistnoret",
+        0x43 => "pusharglist",
+        0x44 => "pushcons",
+        0x45 => "pushsymb",
+        0x46 => "pushvarref",
+        0x48 => "getglobal2",
+        0x49 => "getglobal",
+        0x4a => "getprop",
+        0x4b => "getparam",
+        0x4c => "getlocal",
+        0x4d => "setglobal2",
+        0x4f => "setglobal",
+        0x50 => "setprop",
+        0x51 => "setparam",
+        0x52 => "setlocal",
+        0x53 => "jmp",
+        0x54 => "endrepeat",
+        0x55 => "jmpifz",
+        0x56 => "localcall",
+        0x57 => "extcall",
+        0x58 => "objcallv4",
+        0x59 => "put",
+        0x5a => "putchunk",
+        0x5b => "deletechunk",
+        0x5c => "get",
+        0x5d => "set",
+        0x5f => "getmovieprop",
+        0x60 => "setmovieprop",
+        0x61 => "getobjprop",
+        0x62 => "setobjprop",
+        0x63 => "tellcall",
+        0x64 => "peek",
+        0x65 => "pop",
+        0x66 => "thebuiltin",
+        0x67 => "objcall",
+        0x6d => "pushchunkvarref",
+        0x6e => "pushint16",
+        0x6f => "pushint32",
+        0x70 => "getchainedprop",
+        0x71 => "pushfloat32",
+        0x72 => "gettoplevelprop",
+        0x73 => "newobj",
+        _ => "unknown",
+    }
+}
+
+/// Splits bytecode into instructions. Opcodes from 0x40 up take an operand of
+/// one, two or four bytes depending on their top two bits.
+pub fn decode(bytecode: &[u8]) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    let mut pos = 0;
+
+    while pos < bytecode.len() {
+        let offset = pos;
+        let opcode = bytecode[pos];
+        pos += 1;
+
+        let operand_len = match opcode {
+            0x00..0x40 => 0,
+            0x40..0x80 => 1,
+            0x80..0xc0 => 2,
+            _ => 4,
+        };
+
+        let Some(bytes) = bytecode.get(pos..pos + operand_len) else {
+            break;
+        };
+        pos += operand_len;
+
+        let signed = matches!(0x40 + opcode % 0x40, 0x41 | 0x6e | 0x6f);
+        let operand = match (operand_len, signed) {
+            (0, _) => None,
+            (1, false) => Some(bytes[0] as i32),
+            (1, true) => Some(bytes[0] as i8 as i32),
+            (2, false) => Some(u16::from_be_bytes([bytes[0], bytes[1]]) as i32),
+            (2, true) => Some(i16::from_be_bytes([bytes[0], bytes[1]]) as i32),
+            _ => Some(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+        };
+
+        instructions.push(Instruction {
+            offset,
+            opcode,
+            operand,
+        });
+    }
+
+    instructions
+}
+
+/// Produces a commented listing of a compiled script, resolving names and
+/// literals where possible.
+pub fn disassemble(script: &LingoScript, names: Option<&LingoNames>, version: Version) -> String {
+    let disassembler = Disassembler {
+        script,
+        names,
+        variable_multiplier: if version.major() >= 5 { 8 } else { 6 },
+    };
+
+    let mut out = String::new();
+    disassembler.write_script(&mut out);
+    out
+}
+
+struct Disassembler<'a> {
+    script: &'a LingoScript,
+    names: Option<&'a LingoNames>,
+    variable_multiplier: i32,
+}
+
+impl Disassembler<'_> {
+    fn name(&self, id: u16) -> String {
+        self.names
+            .and_then(|names| names.name(id))
+            .map(str::to_owned)
+            .unwrap_or_else(|| format!("name_{id}"))
+    }
+
+    fn name_list(&self, ids: &[u16]) -> String {
+        ids.iter()
+            .map(|&id| self.name(id))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn write_script(&self, out: &mut String) {
+        if !self.script.property_name_ids().is_empty() {
+            let _ = writeln!(
+                out,
+                "property {}",
+                self.name_list(self.script.property_name_ids())
+            );
+        }
+
+        if !self.script.global_name_ids().is_empty() {
+            let _ = writeln!(
+                out,
+                "global {}",
+                self.name_list(self.script.global_name_ids())
+            );
+        }
+
+        for handler in self.script.handlers() {
+            out.push('\n');
+            self.write_handler(out, handler);
+        }
+    }
+
+    fn write_handler(&self, out: &mut String, handler: &Handler) {
+        let _ = write!(out, "on {}", self.name(handler.name_id()));
+        if !handler.argument_name_ids().is_empty() {
+            let _ = write!(out, " {}", self.name_list(handler.argument_name_ids()));
+        }
+        out.push('\n');
+
+        if !handler.global_name_ids().is_empty() {
+            let _ = writeln!(
+                out,
+                "  global {}",
+                self.name_list(handler.global_name_ids())
+            );
+        }
+
+        if !handler.local_name_ids().is_empty() {
+            let _ = writeln!(
+                out,
+                "  -- locals: {}",
+                self.name_list(handler.local_name_ids())
+            );
+        }
+
+        for instruction in decode(handler.bytecode()) {
+            let operand = instruction
+                .operand
+                .map(|operand| operand.to_string())
+                .unwrap_or_default();
+
+            let mut line = format!(
+                "  -- [{:4}] {:<16} {:>6}",
+                instruction.offset,
+                instruction.name(),
+                operand
+            );
+
+            if let Some(comment) = self.comment(handler, &instruction) {
+                let _ = write!(line, "  {comment}");
+            }
+
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+
+        out.push_str("end\n");
+    }
+
+    fn comment(&self, handler: &Handler, instruction: &Instruction) -> Option<String> {
+        let operand = instruction.operand?;
+
+        let comment = match instruction.base_opcode() {
+            0x44 => {
+                let index = (operand / self.variable_multiplier) as usize;
+                match self.script.literals().get(index)? {
+                    Literal::String(s) => format!("{s:?}"),
+                    Literal::Int(i) => i.to_string(),
+                    Literal::Float(f) => f.to_string(),
+                    Literal::Unknown { literal_type, .. } => {
+                        format!("<literal type {literal_type}>")
+                    }
+                }
+            }
+            0x4b | 0x51 => {
+                let index = (operand / self.variable_multiplier) as usize;
+                self.name(*handler.argument_name_ids().get(index)?)
+            }
+            0x4c | 0x52 => {
+                let index = (operand / self.variable_multiplier) as usize;
+                self.name(*handler.local_name_ids().get(index)?)
+            }
+            0x45 => format!("#{}", self.name(operand as u16)),
+            0x46 | 0x48 | 0x49 | 0x4a | 0x4d | 0x4f | 0x50 | 0x57 | 0x5f | 0x60 | 0x61 | 0x62
+            | 0x63 | 0x66 | 0x67 | 0x70 | 0x72 | 0x73 => self.name(operand as u16),
+            0x56 => {
+                let handler = self.script.handlers().get(operand as usize)?;
+                self.name(handler.name_id())
+            }
+            0x53 | 0x55 => format!("-> {}", instruction.offset as i32 + operand),
+            0x54 => format!("-> {}", instruction.offset as i32 - operand),
+            _ => return None,
+        };
+
+        Some(comment)
+    }
+}
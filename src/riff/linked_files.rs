@@ -0,0 +1,245 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Maps the paths of linked cast members onto a local directory tree.
+///
+/// Linked members record the path of their media as it was on the authoring
+/// machine, e.g. `HD:Movies:Art:logo.pict` or `C:\MOVIES\ART\LOGO.BMP`. The
+/// resolver matches the trailing components of such a path against `root`,
+/// longest suffix first, comparing names case-insensitively and falling back
+/// to DOS 8.3 short names.
+#[derive(Debug, Clone)]
+pub struct LinkedFileResolver {
+    root: PathBuf,
+}
+
+impl LinkedFileResolver {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Resolves a linked member's file path and file name to a local file.
+    /// Either may be empty, and the path may already end in the file name.
+    pub fn resolve(&self, file_path: &str, file_name: &str) -> Option<PathBuf> {
+        let mut components = split_path(file_path);
+
+        for name in split_path(file_name) {
+            if components
+                .last()
+                .is_none_or(|last| !last.eq_ignore_ascii_case(name))
+            {
+                components.push(name);
+            }
+        }
+
+        (0..components.len()).find_map(|start| self.resolve_components(&components[start..]))
+    }
+
+    fn resolve_components(&self, components: &[&str]) -> Option<PathBuf> {
+        let mut path = self.root.clone();
+
+        for component in components {
+            path = find_entry(&path, component)?;
+        }
+
+        path.is_file().then_some(path)
+    }
+}
+
+/// Splits a Mac (`:`), Windows (`\`) or Unix (`/`) path into its components.
+/// Parent directory components are dropped, so that a path recorded in a
+/// movie cannot lead out of the root.
+fn split_path(path: &str) -> Vec<&str> {
+    path.split([':', '\\', '/'])
+        .filter(|component| !matches!(*component, "" | "." | ".."))
+        .collect()
+}
+
+fn find_entry(dir: &Path, name: &str) -> Option<PathBuf> {
+    let exact = dir.join(name);
+    if exact.exists() {
+        return Some(exact);
+    }
+
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name())
+        .collect();
+    entries.sort();
+
+    let entry_names = entries.iter().filter_map(|entry| entry.to_str());
+
+    let found = entry_names
+        .clone()
+        .find(|entry| entry.to_lowercase() == name.to_lowercase())
+        .or_else(|| {
+            entry_names
+                .clone()
+                .find(|entry| short_names_match(entry, name))
+        })?;
+
+    Some(dir.join(found))
+}
+
+/// Returns true if one of the names is the DOS 8.3 form of the other.
+fn short_names_match(a: &str, b: &str) -> bool {
+    is_short_name_of(a, b) || is_short_name_of(b, a)
+}
+
+fn is_short_name_of(short: &str, long: &str) -> bool {
+    let (short_base, short_ext) = split_extension(short);
+    if short_base.is_empty() || short_base.len() > 8 || short_ext.len() > 3 {
+        return false;
+    }
+
+    let short_base = short_base.to_ascii_uppercase();
+    let short_ext = short_ext.to_ascii_uppercase();
+
+    let (long_base, long_ext) = split_extension(long);
+    let long_base = dos_characters(long_base);
+    let long_ext = dos_characters(long_ext);
+
+    if long_ext.chars().take(3).ne(short_ext.chars()) {
+        return false;
+    }
+
+    // Names that did not fit were shortened to a six character prefix and a
+    // numeric tail, e.g. `LONGFI~1.BMP`.
+    if let Some((prefix, tail)) = short_base.split_once('~')
+        && !tail.is_empty()
+        && tail.chars().all(|c| c.is_ascii_digit())
+    {
+        return !prefix.is_empty() && long_base.starts_with(prefix);
+    }
+
+    long_base.chars().take(8).eq(short_base.chars())
+}
+
+fn split_extension(name: &str) -> (&str, &str) {
+    match name.rsplit_once('.') {
+        Some((base, ext)) if !base.is_empty() => (base, ext),
+        _ => (name, ""),
+    }
+}
+
+/// Uppercases a name and drops the characters that are not allowed in DOS
+/// file names, the way Windows does when generating a short name.
+fn dos_characters(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace() && *c != '.')
+        .map(|c| match c {
+            '+' | ',' | ';' | '=' | '[' | ']' => '_',
+            c => c.to_ascii_uppercase(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    /// An empty directory for a test, with `files` created in it.
+    fn temp_dir(name: &str, files: &[&str]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("linked_files_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for file in files {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, file).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn resolves_paths_case_insensitively() {
+        let root = temp_dir("case", &["Art/Logo.PICT"]);
+        let resolver = LinkedFileResolver::new(&root);
+        let logo = Some(root.join("Art/Logo.PICT"));
+
+        assert_eq!(resolver.resolve("HD:Movies:ART:logo.pict", ""), logo);
+        assert_eq!(resolver.resolve(r"C:\MOVIES\art\LOGO.PICT", ""), logo);
+        assert_eq!(resolver.resolve("HD:Movies:Art:", "logo.pict"), logo);
+        assert_eq!(resolver.resolve("HD:Art:Logo.PICT", "Logo.PICT"), logo);
+        assert_eq!(resolver.resolve("", "Logo.PICT"), None);
+        assert_eq!(resolver.resolve("HD:Art:Missing.pict", ""), None);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn resolves_the_longest_suffix_first() {
+        let root = temp_dir("suffix", &["a/x.bmp", "b/x.bmp", "x.bmp"]);
+        let resolver = LinkedFileResolver::new(&root);
+
+        assert_eq!(
+            resolver.resolve("HD:b:x.bmp", ""),
+            Some(root.join("b/x.bmp"))
+        );
+        assert_eq!(
+            resolver.resolve("HD:A:x.bmp", ""),
+            Some(root.join("a/x.bmp"))
+        );
+        assert_eq!(resolver.resolve("HD:c:x.bmp", ""), Some(root.join("x.bmp")));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn resolves_short_names() {
+        let root = temp_dir(
+            "short",
+            &["Long File Name.bmp", "BACKGR~1.PCT", "INTRO.AIF"],
+        );
+        let resolver = LinkedFileResolver::new(&root);
+
+        assert_eq!(
+            resolver.resolve(r"C:\ART\LONGFI~1.BMP", ""),
+            Some(root.join("Long File Name.bmp"))
+        );
+        assert_eq!(
+            resolver.resolve("HD:Art:Background Art.pct", ""),
+            Some(root.join("BACKGR~1.PCT"))
+        );
+        assert_eq!(
+            resolver.resolve("HD:Sounds:Intro.aiff", ""),
+            Some(root.join("INTRO.AIF"))
+        );
+        assert_eq!(resolver.resolve(r"C:\ART\LONGER~1.BMP", ""), None);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn stays_under_the_root() {
+        let root = temp_dir("root", &["secret.txt", "movies/logo.bmp"]);
+        let resolver = LinkedFileResolver::new(root.join("movies"));
+
+        assert_eq!(resolver.resolve("../secret.txt", ""), None);
+        assert_eq!(resolver.resolve(r"..\..\secret.txt", ""), None);
+        assert_eq!(resolver.resolve("::secret.txt", ""), None);
+        assert_eq!(
+            resolver.resolve("../movies/logo.bmp", ""),
+            Some(root.join("movies/logo.bmp"))
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn matches_short_names() {
+        assert!(short_names_match("PROGRA~1", "Program Files"));
+        assert!(short_names_match("readme.txt", "README.TXT"));
+        assert!(short_names_match("My Sound.aiff", "MYSOUN~2.AIF"));
+        assert!(!short_names_match("MYSOUN~2.WAV", "My Sound.aiff"));
+        assert!(!short_names_match("Program Files", "Program Data"));
+    }
+}
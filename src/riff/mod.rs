@@ -1,24 +1,49 @@
-mod chunks;
+mod cache;
+mod linked_files;
+mod movie;
+mod movie_writer;
 mod projector;
+mod resource_movie;
 mod riff_file;
+mod validate;
 mod version;
 mod vlist;
 
 pub mod cast_members;
+pub mod chunks;
+pub mod lingo;
 pub mod tags;
 
-pub use projector::Projector;
-pub use riff_file::RiffFile;
+pub use cache::DEFAULT_CACHE_BUDGET;
+pub use linked_files::LinkedFileResolver;
+pub use movie::{Movie, MovieContent, NotAMovieError, OpenOptions};
+pub use movie_writer::MovieWriter;
+pub use projector::{Projector, ProjectorFile, ProjectorFileKind};
+pub use resource_movie::ResourceMovie;
+pub use riff_file::{AnyChunks, RiffFile};
+pub use validate::{Issue, IssueKind};
+pub use version::Version;
 
-use std::{error::Error, fmt};
+// Movies over shared, mapped or streamed data can be moved between threads.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Movie<'static>>();
+    assert_send_sync::<RiffFile<'static>>();
+    assert_send_sync::<ResourceMovie<'static>>();
+};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Tempo {
     None,
     WaitForMouse,
     WaitForSoundChannel1,
     WaitForSoundChannel2,
+    /// Waits up to 60 seconds before the next frame.
+    WaitSeconds(u8),
     FPS(u8),
+    /// A tempo the decoder does not know, with its raw value.
+    Other(i8),
 }
 
 impl Default for Tempo {
@@ -27,28 +52,30 @@ impl Default for Tempo {
     }
 }
 
-#[derive(Debug)]
-pub struct InvalidTempoError(i8);
-
-impl fmt::Display for InvalidTempoError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Invalid value for tempo: {}", self.0)
+impl From<i8> for Tempo {
+    fn from(value: i8) -> Self {
+        match value {
+            0 => Tempo::None,
+            1.. => Tempo::FPS(value as u8),
+            -60..=-1 => Tempo::WaitSeconds(value.unsigned_abs()),
+            -121 => Tempo::WaitForSoundChannel1,
+            -122 => Tempo::WaitForSoundChannel2,
+            -128 => Tempo::WaitForMouse,
+            _ => Tempo::Other(value),
+        }
     }
 }
 
-impl Error for InvalidTempoError {}
-
-impl TryFrom<i8> for Tempo {
-    type Error = InvalidTempoError;
-
-    fn try_from(value: i8) -> Result<Self, Self::Error> {
+impl From<Tempo> for i8 {
+    fn from(value: Tempo) -> Self {
         match value {
-            0 => Ok(Tempo::None),
-            1.. => Ok(Tempo::FPS(value as u8)),
-            -121 => Ok(Tempo::WaitForSoundChannel1),
-            -122 => Ok(Tempo::WaitForSoundChannel2),
-            -128 => Ok(Tempo::WaitForMouse),
-            _ => Err(InvalidTempoError(value)),
+            Tempo::None => 0,
+            Tempo::FPS(fps) => fps as i8,
+            Tempo::WaitForSoundChannel1 => -121,
+            Tempo::WaitForSoundChannel2 => -122,
+            Tempo::WaitForMouse => -128,
+            Tempo::WaitSeconds(seconds) => -(seconds as i8),
+            Tempo::Other(value) => value,
         }
     }
 }
@@ -0,0 +1,310 @@
+use std::{
+    fmt,
+    io::{Error, ErrorKind, Result},
+    path::Path,
+};
+
+use crate::{
+    mac::{MacFile, ResourceFork},
+    reader::{Reader, TextEncoding},
+};
+
+use super::{Projector, ResourceMovie, RiffFile, tags};
+
+/// Settings for opening a movie.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OpenOptions {
+    /// Overrides the encoding detected from the movie, e.g. for Japanese
+    /// movies, which use Shift-JIS.
+    pub encoding: Option<TextEncoding>,
+    /// Reads damaged movies as far as they go, with a warning for each chunk
+    /// that is truncated or disagrees with the memory map.
+    pub tolerant: bool,
+    /// Opens the movie without reading its key table, config, cast table,
+    /// score and the rest, e.g. to check a damaged movie with
+    /// `RiffFile::validate`.
+    pub headers_only: bool,
+}
+
+/// What opening a file that is not a movie fails with, inside an
+/// `ErrorKind::InvalidData` error. See `Movie::is_not_a_movie`.
+#[derive(Debug)]
+pub struct NotAMovieError(&'static str);
+
+impl fmt::Display for NotAMovieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl std::error::Error for NotAMovieError {}
+
+/// The movie itself, in whichever format it was stored.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum MovieContent<'a> {
+    /// A Director 4 or later movie.
+    Riff(RiffFile<'a>),
+    /// A Director 3 movie, stored as resources.
+    Resources(ResourceMovie<'a>),
+}
+
+/// A movie opened from a file of any of the formats movies are found in:
+/// plain RIFX movies, Windows and Mac projectors, Director 3 resource movies,
+/// and any of these wrapped in MacBinary, AppleSingle or AppleDouble files.
+///
+/// Opening a movie reads its key table, config, file info, cast table, frame
+/// labels and score. A missing config is reported in `warnings` rather than
+/// as an error, as are missing frame labels. Files that are not movies fail
+/// with a `NotAMovieError`.
+///
+/// Movies are `Send` and `Sync`, so that they can be read and used on other
+/// threads, as long as what they were read from is.
+pub struct Movie<'a> {
+    mac_file: Option<MacFile>,
+    resource_fork: Option<ResourceFork<'a>>,
+    projector: Option<Projector<'a>>,
+    content: MovieContent<'a>,
+    warnings: Vec<Error>,
+}
+
+impl Movie<'static> {
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::open_with(path, OpenOptions::default())
+    }
+
+    /// Opens the movie at `path`, picking up its resource fork from an
+    /// AppleDouble file next to it, if there is one. The file is mapped into
+    /// memory rather than read, unless it has to be unwrapped.
+    pub fn open_with(path: &Path, options: OpenOptions) -> Result<Self> {
+        let (reader, mac_file) = Self::unwrap(Reader::map_file(path)?)?;
+        let mac_file = match mac_file {
+            Some(mac_file) => mac_file,
+            None => MacFile::open_apple_double(path)?,
+        };
+        let mac_file = mac_file.has_resource_fork().then_some(mac_file);
+
+        Self::read(reader, mac_file, options)
+    }
+}
+
+impl<'a> Movie<'a> {
+    pub fn from_bytes(buf: &'a [u8]) -> Result<Self> {
+        Self::from_bytes_with(buf, OpenOptions::default())
+    }
+
+    pub fn from_bytes_with(buf: &'a [u8], options: OpenOptions) -> Result<Self> {
+        Self::from_reader_with(Reader::new(buf), options)
+    }
+
+    pub fn from_reader(reader: Reader<'a>) -> Result<Self> {
+        Self::from_reader_with(reader, OpenOptions::default())
+    }
+
+    /// Opens the movie `reader` reads from, which may be a stream from
+    /// `Reader::from_stream`.
+    pub fn from_reader_with(reader: Reader<'a>, options: OpenOptions) -> Result<Self> {
+        let (reader, mac_file) = Self::unwrap(reader)?;
+
+        Self::read(reader, mac_file, options)
+    }
+
+    /// Unwraps a MacBinary or AppleSingle file, returning a reader over its
+    /// data fork, or `reader` itself if it is neither.
+    fn unwrap(reader: Reader<'a>) -> Result<(Reader<'a>, Option<MacFile>)> {
+        // Both start with a zero byte, which movies and projectors do not,
+        // so other files are never loaded whole to find out.
+        if reader.peek(1)? != [0] {
+            return Ok((reader, None));
+        }
+
+        match MacFile::read(reader.as_slice()?)? {
+            Some(mac_file) => Ok((mac_file.data_fork_reader(), Some(mac_file))),
+            None => Ok((reader, None)),
+        }
+    }
+
+    fn read(reader: Reader<'a>, mac_file: Option<MacFile>, options: OpenOptions) -> Result<Self> {
+        let resource_fork = mac_file
+            .as_ref()
+            .map(|mac_file| mac_file.read_resource_fork())
+            .transpose()?;
+
+        let head = reader.peek(16)?;
+        let is_resource_riff = ResourceMovie::is_riff(&head);
+        let is_riff = head.starts_with(b"RIFX") || head.starts_with(b"XFIR");
+        let is_mac_projector = Projector::is_mac_projector(&head);
+        let is_empty = head.is_empty();
+
+        let mut projector = None;
+        let content = if is_resource_riff {
+            MovieContent::Resources(ResourceMovie::read_riff(reader)?)
+        } else if is_riff {
+            MovieContent::Riff(RiffFile::new(reader)?)
+        } else if is_mac_projector {
+            let mut mac_projector = Projector::read_mac(reader)?;
+            let riff = mac_projector.read_main_movie()?;
+            projector = Some(mac_projector);
+            MovieContent::Riff(riff)
+        } else if let Some(resource_fork) = &resource_fork
+            && resource_fork.first_with_tag(tags::TAG_VWSC).is_some()
+        {
+            MovieContent::Resources(ResourceMovie::new(resource_fork)?)
+        } else if Projector::is_windows_projector(&reader) {
+            let mut windows_projector = Projector::read(reader)?;
+            let riff = windows_projector.read_main_movie()?;
+            projector = Some(windows_projector);
+            MovieContent::Riff(riff)
+        } else if is_empty && mac_file.is_some() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                NotAMovieError(
+                    "The data fork is empty, open the file an AppleDouble header belongs to instead",
+                ),
+            ));
+        } else {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                NotAMovieError("Not a Director movie, projector or Mac file containing one"),
+            ));
+        };
+
+        let mut movie = Movie {
+            mac_file,
+            resource_fork,
+            projector,
+            content,
+            warnings: Vec::new(),
+        };
+        movie.load(options)?;
+
+        Ok(movie)
+    }
+
+    fn load(&mut self, options: OpenOptions) -> Result<()> {
+        match &mut self.content {
+            MovieContent::Riff(riff) => {
+                if let Some(encoding) = options.encoding {
+                    riff.set_encoding(encoding);
+                }
+                if options.tolerant {
+                    riff.set_tolerant(true);
+                    self.warnings.extend(riff.check_chunk_sizes());
+                }
+                if options.headers_only {
+                    return Ok(());
+                }
+
+                riff.read_key_table()?;
+                if let Err(err) = riff.read_config() {
+                    self.warnings.push(config_warning(err));
+                }
+                riff.read_file_info()?;
+                riff.read_cast_table()?;
+                if let Err(err) = riff.read_frame_labels() {
+                    self.warnings.push(frame_labels_warning(err)?);
+                }
+                riff.read_score()?;
+            }
+            MovieContent::Resources(movie) => {
+                if let Some(encoding) = options.encoding {
+                    movie.set_encoding(encoding);
+                }
+                if options.headers_only {
+                    return Ok(());
+                }
+
+                if let Err(err) = movie.read_config() {
+                    self.warnings.push(config_warning(err));
+                }
+                movie.read_file_info()?;
+                movie.read_cast_table()?;
+                if let Err(err) = movie.read_frame_labels() {
+                    self.warnings.push(frame_labels_warning(err)?);
+                }
+                movie.read_score()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The Mac file the movie was read from, if it has a resource fork.
+    pub fn mac_file(&self) -> Option<&MacFile> {
+        self.mac_file.as_ref()
+    }
+
+    pub fn resource_fork(&self) -> Option<&ResourceFork<'a>> {
+        self.resource_fork.as_ref()
+    }
+
+    /// The projector the movie was embedded in, if any.
+    pub fn projector(&self) -> Option<&Projector<'a>> {
+        self.projector.as_ref()
+    }
+
+    pub fn projector_mut(&mut self) -> Option<&mut Projector<'a>> {
+        self.projector.as_mut()
+    }
+
+    pub fn content(&self) -> &MovieContent<'a> {
+        &self.content
+    }
+
+    pub fn riff(&self) -> Option<&RiffFile<'a>> {
+        match &self.content {
+            MovieContent::Riff(riff) => Some(riff),
+            MovieContent::Resources(_) => None,
+        }
+    }
+
+    pub fn riff_mut(&mut self) -> Option<&mut RiffFile<'a>> {
+        match &mut self.content {
+            MovieContent::Riff(riff) => Some(riff),
+            MovieContent::Resources(_) => None,
+        }
+    }
+
+    pub fn into_content(self) -> MovieContent<'a> {
+        self.content
+    }
+
+    pub fn into_riff(self) -> Option<RiffFile<'a>> {
+        match self.content {
+            MovieContent::Riff(riff) => Some(riff),
+            MovieContent::Resources(_) => None,
+        }
+    }
+
+    pub fn resource_movie(&self) -> Option<&ResourceMovie<'a>> {
+        match &self.content {
+            MovieContent::Riff(_) => None,
+            MovieContent::Resources(movie) => Some(movie),
+        }
+    }
+
+    /// Problems that did not stop the movie from loading.
+    pub fn warnings(&self) -> &[Error] {
+        &self.warnings
+    }
+
+    /// Whether opening a file failed because it is not a movie, rather than
+    /// a movie that is damaged or not supported.
+    pub fn is_not_a_movie(err: &Error) -> bool {
+        err.get_ref()
+            .is_some_and(|inner| inner.is::<NotAMovieError>())
+    }
+}
+
+fn config_warning(err: Error) -> Error {
+    Error::new(err.kind(), format!("Unable to read the config: {err}"))
+}
+
+/// Movies without frame labels have no `VWLB` chunk, which is not an error.
+fn frame_labels_warning(err: Error) -> Result<Error> {
+    if err.kind() != ErrorKind::NotFound {
+        return Err(err);
+    }
+
+    Ok(Error::new(ErrorKind::NotFound, "No frame labels found"))
+}
@@ -0,0 +1,623 @@
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind, Result, Write},
+};
+
+use crate::{
+    reader::ByteOrder,
+    writer::{WriteBytesExt, Writer},
+};
+
+use super::{
+    RiffFile,
+    cast_members::{CastMember, CastMemberId, CastMemberKind},
+    chunks::{
+        BitmapData, CastTable, ColorLookupTable, Config, FrameLabels, KeyTable, MemoryMap,
+        MemoryMapEntry, Score, StyledText, WriteChunk,
+    },
+    tags::{self, Tag},
+};
+
+/// Writes a parsed movie back out as a RIFX (or XFIR) file.
+///
+/// Chunks keep their ids, so references between them stay valid. The
+/// container chunks (`imap`, `mmap` and `KEY*`) are rebuilt, the chunks this
+/// crate understands are written from their parsed form, and everything else
+/// is copied byte for byte.
+pub struct MovieWriter<'r, 'a> {
+    riff: &'r RiffFile<'a>,
+    mmap: MemoryMap,
+    key_table: KeyTable,
+    chunks: HashMap<u32, Vec<u8>>,
+}
+
+impl<'r, 'a> MovieWriter<'r, 'a> {
+    pub fn new(riff: &'r RiffFile<'a>) -> Result<Self> {
+        let key_table = match riff.mmap().first_entry_with_tag(tags::TAG_KEY_) {
+            Some(entry) => riff.read_chunk_by_id(entry.id())?,
+            None => KeyTable::default(),
+        };
+
+        Ok(MovieWriter {
+            riff,
+            mmap: riff.mmap().clone(),
+            key_table,
+            chunks: HashMap::new(),
+        })
+    }
+
+    pub fn key_table(&self) -> &KeyTable {
+        &self.key_table
+    }
+
+    /// Replaces the contents of chunk `id`.
+    pub fn replace_chunk<C: WriteChunk>(&mut self, id: u32, chunk: &C) -> Result<()> {
+        let entry = self.live_entry(id)?;
+        if entry.tag() != C::TAG {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Cannot replace chunk {id} '{}' with a '{}' chunk",
+                    entry.tag(),
+                    C::TAG
+                ),
+            ));
+        }
+
+        let data = self.serialize(chunk)?;
+        self.chunks.insert(id, data);
+
+        Ok(())
+    }
+
+    /// Replaces cast member `id`, along with the media chunks it has loaded.
+    /// Media the cast member did not have before is added. The `THUM` chunk
+    /// of a bitmap without a thumbnail is removed, so that Director does not
+    /// show the old image in the cast window.
+    pub fn replace_cast_member(
+        &mut self,
+        id: CastMemberId,
+        cast_member: &CastMember,
+    ) -> Result<()> {
+        let chunk_id = self
+            .riff
+            .cast_table()
+            .cast_member_chunk_id(id)
+            .ok_or_else(|| {
+                Error::new(ErrorKind::NotFound, format!("No cast member {}", id.id()))
+            })?;
+
+        self.replace_chunk(chunk_id, cast_member)?;
+
+        match &cast_member.kind {
+            CastMemberKind::Bitmap(bitmap) => {
+                if let Some(data) = &bitmap.data {
+                    self.replace_child_chunk(chunk_id, data)?;
+                }
+                if bitmap.thumbnail.is_none()
+                    && let Some(thumbnail_id) = self
+                        .key_table
+                        .find_id_of_chunk_with_parent(tags::TAG_THUM, chunk_id)
+                {
+                    self.remove_chunk(thumbnail_id)?;
+                }
+            }
+            CastMemberKind::Text(text) | CastMemberKind::Button(text) => {
+                if let Some(styled_text) = &text.styled_text {
+                    self.replace_child_chunk(chunk_id, styled_text)?;
+                }
+            }
+            CastMemberKind::Palette(palette) => {
+                if let Some(clut) = &palette.clut {
+                    self.replace_child_chunk(chunk_id, clut)?;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the child of `parent` with the tag of `chunk`, or adds one.
+    fn replace_child_chunk<C: WriteChunk>(&mut self, parent: u32, chunk: &C) -> Result<()> {
+        match self.key_table.find_id_of_chunk_with_parent(C::TAG, parent) {
+            Some(id) => self.replace_chunk(id, chunk),
+            None => self.add_chunk(Some(parent), chunk).map(|_| ()),
+        }
+    }
+
+    /// Replaces the contents of chunk `id` with raw bytes.
+    pub fn replace_chunk_data(&mut self, id: u32, data: Vec<u8>) -> Result<()> {
+        self.live_entry(id)?;
+        self.chunks.insert(id, data);
+
+        Ok(())
+    }
+
+    /// Adds a new chunk, linked to `parent` in the key table if given, and
+    /// returns its id.
+    pub fn add_chunk<C: WriteChunk>(&mut self, parent: Option<u32>, chunk: &C) -> Result<u32> {
+        let data = self.serialize(chunk)?;
+        Ok(self.add_chunk_data(C::TAG, parent, data))
+    }
+
+    pub fn add_chunk_data(&mut self, tag: Tag, parent: Option<u32>, data: Vec<u8>) -> u32 {
+        let entries = self.mmap.entries_mut();
+        let id = entries.len() as u32;
+        entries.push(MemoryMapEntry::new(id, tag, 0, 0));
+
+        if let Some(parent) = parent {
+            self.key_table.insert(id, parent, tag);
+        }
+
+        self.chunks.insert(id, data);
+        id
+    }
+
+    /// Frees chunk `id` and drops its key table links.
+    pub fn remove_chunk(&mut self, id: u32) -> Result<()> {
+        self.live_entry(id)?;
+
+        self.mmap.free_entry(id);
+        self.key_table.remove(id);
+        self.chunks.remove(&id);
+
+        Ok(())
+    }
+
+    pub fn write(&self) -> Result<Vec<u8>> {
+        let byte_order = self.riff.byte_order();
+
+        // Chunks are written in their original order, followed by new ones.
+        let mut order: Vec<&MemoryMapEntry> = self
+            .mmap
+            .entries()
+            .iter()
+            .filter(|e| e.id() != 0 && !is_container(e.tag()))
+            .collect();
+        order.sort_by_key(|e| (e.pos() == 0, e.pos(), e.id()));
+
+        let mut chunks = Vec::with_capacity(order.len());
+        for entry in order {
+            chunks.push((entry.id(), entry.tag(), self.chunk_data(entry)?));
+        }
+
+        let mut imap = self.riff.imap().clone();
+        let imap_len = self.serialize(&imap)?.len() as u32;
+        let mmap_pos = 12 + 8 + padded(imap_len);
+        let mmap_len = self.mmap.written_len();
+
+        let mut mmap = self.mmap.clone();
+        let mut pos = mmap_pos + 8 + padded(mmap_len);
+        let mut locations = HashMap::new();
+        for (id, _, data) in &chunks {
+            locations.insert(*id, (pos, data.len() as u32));
+            pos += 8 + padded(data.len() as u32);
+        }
+        let file_len = pos;
+
+        for entry in mmap.entries_mut() {
+            let (pos, len) = match entry.tag() {
+                // The file's own entry agrees with its header. Movies embedded
+                // in a projector record where they start in it, but the
+                // written file starts at zero.
+                _ if entry.id() == 0 => (0, file_len - 8),
+                tags::TAG_imap => (12, imap_len),
+                tags::TAG_mmap => (mmap_pos, mmap_len),
+                // The contents of free and junk chunks are not kept.
+                tags::TAG_free | tags::TAG_junk => (0, 0),
+                _ => locations[&entry.id()],
+            };
+            entry.set_location(pos, len);
+        }
+
+        imap.mmap_offset = mmap_pos;
+
+        let mut w = Writer::new(byte_order);
+        let riff_tag = match byte_order {
+            ByteOrder::BigEndian => tags::TAG_RIFX,
+            ByteOrder::LittleEndian => tags::TAG_XFIR,
+        };
+        w.write_be_i32(riff_tag.0)?;
+        w.write_u32(file_len - 8)?;
+        w.write_i32(self.riff.type_tag().0)?;
+
+        write_chunk(&mut w, tags::TAG_imap, &self.serialize(&imap)?)?;
+        write_chunk(&mut w, tags::TAG_mmap, &self.serialize(&mmap)?)?;
+        for (_, tag, data) in &chunks {
+            write_chunk(&mut w, *tag, data)?;
+        }
+
+        debug_assert_eq!(w.position() as u32, file_len);
+
+        Ok(w.into_inner())
+    }
+
+    fn live_entry(&self, id: u32) -> Result<&MemoryMapEntry> {
+        self.mmap
+            .entry_by_index(id)
+            .filter(|e| e.id() != 0 && !is_container(e.tag()))
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!("No chunk with id {id} can be replaced"),
+                )
+            })
+    }
+
+    fn serialize<C: WriteChunk>(&self, chunk: &C) -> Result<Vec<u8>> {
+        let mut w = Writer::new(self.riff.byte_order());
+        w.set_encoding(self.riff.encoding());
+        chunk.write(&mut w, self.riff.version())?;
+        Ok(w.into_inner())
+    }
+
+    fn chunk_data(&self, entry: &MemoryMapEntry) -> Result<Vec<u8>> {
+        if let Some(data) = self.chunks.get(&entry.id()) {
+            return Ok(data.clone());
+        }
+
+        if entry.tag() == tags::TAG_KEY_ {
+            return self.serialize(&self.key_table);
+        }
+
+        // Chunks that fail to parse are kept as they are.
+        let rewritten = match entry.tag() {
+            tags::TAG_CAS_ => self.rewrite::<CastTable>(entry.id()),
+            tags::TAG_CASt => self.rewrite::<CastMember>(entry.id()),
+            tags::TAG_BITD => self.rewrite::<BitmapData>(entry.id()),
+            tags::TAG_CLUT => self.rewrite::<ColorLookupTable>(entry.id()),
+            tags::TAG_STXT => self.rewrite::<StyledText>(entry.id()),
+            tags::TAG_VWSC => self.rewrite::<Score>(entry.id()),
+            tags::TAG_VWLB => self.rewrite::<FrameLabels>(entry.id()),
+            tags::TAG_VWCF => self.rewrite::<Config>(entry.id()),
+            _ => return self.riff.read_chunk_data(entry.id()),
+        };
+
+        rewritten.or_else(|_| self.riff.read_chunk_data(entry.id()))
+    }
+
+    fn rewrite<C: WriteChunk>(&self, id: u32) -> Result<Vec<u8>> {
+        let chunk: C = self.riff.read_chunk_by_id(id)?;
+        self.serialize(&chunk)
+    }
+}
+
+/// The chunks that describe the file rather than the movie.
+fn is_container(tag: Tag) -> bool {
+    matches!(
+        tag,
+        tags::TAG_imap | tags::TAG_mmap | tags::TAG_free | tags::TAG_junk
+    )
+}
+
+fn padded(len: u32) -> u32 {
+    len + len % 2
+}
+
+fn write_chunk(w: &mut Writer, tag: Tag, data: &[u8]) -> Result<()> {
+    w.write_i32(tag.0)?;
+    w.write_u32(data.len() as u32)?;
+    w.write_all(data)?;
+    w.align();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::riff::{Movie, chunks::EntryState, riff_file::GLOBAL_ID};
+
+    use super::*;
+
+    /// A small Director 5 movie with a bitmap, text, palette, sound and
+    /// script members.
+    const MOVIE: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/d5.dir"));
+
+    const TAG_TEST: Tag = Tag(i32::from_be_bytes(*b"Test"));
+
+    fn read(data: &[u8]) -> RiffFile<'_> {
+        Movie::from_bytes(data).unwrap().into_riff().unwrap()
+    }
+
+    fn key_links(riff: &RiffFile) -> Vec<(u32, u32, Tag)> {
+        let key_table: KeyTable = riff
+            .read_chunk_by_id(
+                riff.mmap()
+                    .first_entry_with_tag(tags::TAG_KEY_)
+                    .unwrap()
+                    .id(),
+            )
+            .unwrap();
+        let mut links: Vec<_> = key_table
+            .entries()
+            .iter()
+            .map(|entry| (entry.id(), entry.parent(), entry.tag()))
+            .collect();
+        links.sort();
+        links
+    }
+
+    fn chunk_data(riff: &RiffFile, tag: Tag) -> Vec<(u32, Vec<u8>)> {
+        riff.mmap()
+            .entries()
+            .iter()
+            .filter(|entry| entry.tag() == tag && entry.state() == EntryState::Live)
+            .map(|entry| (entry.id(), riff.read_chunk_data(entry.id()).unwrap()))
+            .collect()
+    }
+
+    /// The chunks of `movie` in the reverse order, after a gap, with the
+    /// memory map at the end and room in it for more entries, laid out by
+    /// hand rather than by the writer.
+    fn relaid(movie: &[u8]) -> Vec<u8> {
+        let riff = read(movie);
+        let be_u32 = |data: &[u8], pos: usize| {
+            u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as usize
+        };
+        let put_u32 = |data: &mut [u8], pos: usize, value: usize| {
+            data[pos..pos + 4].copy_from_slice(&(value as u32).to_be_bytes());
+        };
+        let chunk = |pos: usize| {
+            let len = 8 + be_u32(movie, pos + 4);
+            &movie[pos..pos + len + len % 2]
+        };
+
+        let imap = chunk(12);
+        let mut out = movie[..12].to_vec();
+        out.extend_from_slice(imap);
+        out.extend_from_slice(&[0; 10]);
+
+        let mut positions = vec![(0, 0); riff.mmap().entries().len()];
+        for entry in riff.mmap().entries().iter().rev() {
+            if entry.id() != 0 && !is_container(entry.tag()) {
+                positions[entry.id() as usize] = (out.len(), entry.len() as usize);
+                out.extend_from_slice(chunk(entry.pos() as usize));
+            }
+        }
+
+        let mmap_pos = be_u32(imap, 12);
+        let mut mmap = chunk(mmap_pos)[8..].to_vec();
+        let (header_len, entry_len) = (
+            u16::from_be_bytes([mmap[0], mmap[1]]) as usize,
+            u16::from_be_bytes([mmap[2], mmap[3]]) as usize,
+        );
+        let used = be_u32(&mmap, 8);
+        mmap.truncate(header_len + used * entry_len);
+        mmap.resize(header_len + (used + 4) * entry_len, 0);
+        put_u32(&mut mmap, 4, used + 4);
+
+        let new_mmap_pos = out.len();
+        let file_len = new_mmap_pos + 8 + mmap.len();
+        positions[0] = (0, file_len - 8);
+        positions[1] = (12, imap.len() - 8);
+        positions[2] = (new_mmap_pos, mmap.len());
+        for (id, (pos, len)) in positions.into_iter().enumerate() {
+            let entry = header_len + id * entry_len;
+            if pos != 0 || id == 0 {
+                put_u32(&mut mmap, entry + 4, len);
+                put_u32(&mut mmap, entry + 8, pos);
+            }
+        }
+
+        out.extend_from_slice(b"mmap");
+        out.extend_from_slice(&(mmap.len() as u32).to_be_bytes());
+        out.extend_from_slice(&mmap);
+        put_u32(&mut out, 4, file_len - 8);
+        put_u32(&mut out, 12 + 12, new_mmap_pos);
+
+        out
+    }
+
+    /// Checks that everything but the container chunks came through as it
+    /// was.
+    fn assert_same_movie(a: &RiffFile, b: &RiffFile) {
+        assert_eq!(key_links(a), key_links(b));
+
+        let tags = |riff: &RiffFile| {
+            let mut tags: Vec<_> = riff
+                .mmap()
+                .entries()
+                .iter()
+                .filter(|entry| entry.id() != 0 && !is_container(entry.tag()))
+                .map(|entry| entry.tag())
+                .collect();
+            tags.sort_by_key(|tag| tag.0);
+            tags.dedup();
+            tags
+        };
+        assert_eq!(tags(a), tags(b));
+        for tag in tags(a) {
+            assert_eq!(
+                chunk_data(a, tag),
+                chunk_data(b, tag),
+                "'{tag}' chunks differ"
+            );
+        }
+
+        let ids: Vec<_> = a.cast_table().cast_member_ids().collect();
+        assert_eq!(ids, b.cast_table().cast_member_ids().collect::<Vec<_>>());
+        for id in ids {
+            let (member_a, member_b) = (
+                a.load_cast_member(id).unwrap(),
+                b.load_cast_member(id).unwrap(),
+            );
+            assert_eq!(member_a.name(), member_b.name());
+            assert_eq!(member_a.cast_member_type(), member_b.cast_member_type());
+        }
+
+        assert_eq!(a.score().frames().len(), b.score().frames().len());
+    }
+
+    #[test]
+    fn round_trips() {
+        let movie = relaid(MOVIE);
+        let riff = read(&movie);
+        assert!(riff.validate().is_empty(), "{:?}", riff.validate());
+        // Chunks that fail to parse would be copied rather than rewritten.
+        for (entry, chunk) in riff.chunks() {
+            assert!(
+                chunk.is_ok(),
+                "'{}' chunk {} fails to parse",
+                entry.tag(),
+                entry.id()
+            );
+        }
+
+        let rewritten = MovieWriter::new(&riff).unwrap().write().unwrap();
+        assert_ne!(rewritten, movie);
+        assert_same_movie(&riff, &read(&rewritten));
+
+        let riff = read(&rewritten);
+        let entry = riff.mmap().entry_by_index(0).unwrap();
+        assert_eq!(entry.len(), riff.size());
+        assert_eq!(entry.len() as usize, rewritten.len() - 8);
+    }
+
+    #[test]
+    fn passes_unknown_chunks_through() {
+        let riff = read(MOVIE);
+        let mut writer = MovieWriter::new(&riff).unwrap();
+        let id = writer.add_chunk_data(TAG_TEST, Some(GLOBAL_ID), b"unknown data".to_vec());
+        let with_unknown = writer.write().unwrap();
+
+        let riff = read(&with_unknown);
+        assert_eq!(
+            chunk_data(&riff, TAG_TEST),
+            [(id, b"unknown data".to_vec())]
+        );
+
+        let rewritten = MovieWriter::new(&riff).unwrap().write().unwrap();
+        assert_same_movie(&riff, &read(&rewritten));
+    }
+
+    #[test]
+    fn rejects_a_final_chunk_cut_off_after_its_header() {
+        let riff = read(MOVIE);
+        let mut writer = MovieWriter::new(&riff).unwrap();
+        let id = writer.add_chunk_data(TAG_TEST, Some(GLOBAL_ID), b"unknown data".to_vec());
+        let with_unknown = writer.write().unwrap();
+
+        let riff = read(&with_unknown);
+        let end = riff.mmap().entry_by_index(id).unwrap().pos() as usize + 8;
+        assert_eq!(end + 12, with_unknown.len());
+
+        for cut in [end, end + 1] {
+            let mut riff = read(&with_unknown[..cut]);
+            let err = riff.read_chunk_data(id).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+            assert_eq!(riff.check_chunk_sizes().len(), 1);
+
+            // A tolerant file reads what is left of it.
+            riff.set_tolerant(true);
+            assert_eq!(
+                riff.read_chunk_data(id).unwrap(),
+                &b"unknown data"[..cut - end]
+            );
+        }
+    }
+
+    #[test]
+    fn removes_chunks() {
+        let riff = read(MOVIE);
+        let text_id = CastMemberId::new(2);
+        let chunk_id = riff.cast_table().cast_member_chunk_id(text_id).unwrap();
+        let stxt_id = riff
+            .key_table()
+            .find_id_of_chunk_with_parent(tags::TAG_STXT, chunk_id)
+            .unwrap();
+
+        let mut writer = MovieWriter::new(&riff).unwrap();
+        writer.remove_chunk(stxt_id).unwrap();
+        let rewritten = writer.write().unwrap();
+
+        let riff = read(&rewritten);
+        let entry = riff.mmap().entry_by_index(stxt_id).unwrap();
+        assert_eq!(entry.state(), EntryState::Free);
+        assert!(key_links(&riff).iter().all(|&(id, _, _)| id != stxt_id));
+        assert!(riff.validate().is_empty(), "{:?}", riff.validate());
+
+        let CastMemberKind::Text(text) = riff.load_cast_member(text_id).unwrap().kind else {
+            panic!("Cast member 2 is not a text member");
+        };
+        assert!(text.styled_text.is_none());
+    }
+
+    #[test]
+    fn replaces_cast_members() {
+        let riff = read(MOVIE);
+        let text_id = CastMemberId::new(2);
+        let mut cast_member = riff.load_cast_member(text_id).unwrap();
+        cast_member.info.name = Some("Farewell".to_owned());
+        let CastMemberKind::Text(text) = &mut cast_member.kind else {
+            panic!("Cast member 2 is not a text member");
+        };
+        text.set_text("Goodbye").unwrap();
+
+        let mut writer = MovieWriter::new(&riff).unwrap();
+        writer.replace_cast_member(text_id, &cast_member).unwrap();
+        let rewritten = writer.write().unwrap();
+
+        let rewritten = read(&rewritten);
+        let cast_member = rewritten.load_cast_member(text_id).unwrap();
+        assert_eq!(cast_member.name(), Some("Farewell"));
+        let CastMemberKind::Text(text) = &cast_member.kind else {
+            panic!("Cast member 2 is not a text member");
+        };
+        assert_eq!(text.text().as_deref(), Some("Goodbye"));
+
+        // Nothing else changed.
+        assert_eq!(key_links(&riff), key_links(&rewritten));
+        for id in riff
+            .cast_table()
+            .cast_member_ids()
+            .filter(|&id| id != text_id)
+        {
+            let chunk_id = riff.cast_table().cast_member_chunk_id(id).unwrap();
+            assert_eq!(
+                riff.read_chunk_data(chunk_id).unwrap(),
+                rewritten.read_chunk_data(chunk_id).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn drops_the_thumbnails_of_imported_bitmaps() {
+        let riff = read(MOVIE);
+        let bitmap_id = CastMemberId::new(1);
+        let chunk_id = riff.cast_table().cast_member_chunk_id(bitmap_id).unwrap();
+
+        let mut writer = MovieWriter::new(&riff).unwrap();
+        writer.add_chunk_data(tags::TAG_THUM, Some(chunk_id), vec![0; 16]);
+        let with_thumbnail = writer.write().unwrap();
+
+        let riff = read(&with_thumbnail);
+        let mut cast_member = riff.load_cast_member(bitmap_id).unwrap();
+        let bitmap = cast_member.as_bitmap_mut().unwrap();
+        assert!(bitmap.thumbnail.is_some());
+
+        let image = crate::gfx::ImageBuffer::new(4, 2, vec![0xffffff; 8]);
+        bitmap
+            .import(&image, &crate::gfx::Palette::mac_system(), 256, 8)
+            .unwrap();
+
+        let mut writer = MovieWriter::new(&riff).unwrap();
+        writer.replace_cast_member(bitmap_id, &cast_member).unwrap();
+        let rewritten = writer.write().unwrap();
+
+        let rewritten = read(&rewritten);
+        assert!(
+            rewritten
+                .key_table()
+                .find_id_of_chunk_with_parent(tags::TAG_THUM, chunk_id)
+                .is_none()
+        );
+        let cast_member = rewritten.load_cast_member(bitmap_id).unwrap();
+        assert!(cast_member.as_bitmap().unwrap().thumbnail.is_none());
+        assert!(
+            rewritten.validate().is_empty(),
+            "{:?}",
+            rewritten.validate()
+        );
+    }
+}
@@ -1,4 +1,8 @@
-use std::io::{Result, Seek, SeekFrom};
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{Error, ErrorKind, Read, Result, Seek, SeekFrom},
+};
 
 use thousands::Separable;
 
@@ -6,23 +10,118 @@ use crate::reader::{ReadBytesExt, Reader};
 
 use super::{
     riff_file::RiffFile,
-    tags::{TAG_PJ93, Tag},
+    tags::{self, Tag},
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectorFileKind {
+    Movie,
+    Cast,
+    Xtra,
+    Other,
+}
+
+impl ProjectorFileKind {
+    /// The extension a file of this kind is usually saved with.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ProjectorFileKind::Movie => "dir",
+            ProjectorFileKind::Cast => "cst",
+            ProjectorFileKind::Xtra => "x32",
+            ProjectorFileKind::Other => "bin",
+        }
+    }
+}
+
+impl fmt::Display for ProjectorFileKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ProjectorFileKind::Movie => "movie",
+            ProjectorFileKind::Cast => "cast",
+            ProjectorFileKind::Xtra => "xtra",
+            ProjectorFileKind::Other => "file",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A file embedded in a projector.
+#[derive(Debug, Clone)]
+pub struct ProjectorFile<'a> {
+    pub name: String,
+    pub kind: ProjectorFileKind,
+    reader: Reader<'a>,
+    len: u32,
+}
+
+impl<'a> ProjectorFile<'a> {
+    /// A reader over the whole projector, positioned at the start of the
+    /// file. Embedded movies locate their chunks relative to the projector,
+    /// so this is what `RiffFile::new` expects.
+    pub fn reader(&self) -> Reader<'a> {
+        self.reader.clone()
+    }
+
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The bytes of the file as they are stored in the projector.
+    pub fn read_data(&self) -> Result<Vec<u8>> {
+        let mut r = self.reader.clone();
+        let remain = r.stream_remain()?;
+
+        let mut data = vec![0; (self.len as u64).min(remain) as usize];
+        r.read_exact(&mut data)?;
+
+        Ok(data)
+    }
+}
+
 pub struct Projector<'a> {
     header: Header,
     reader: Reader<'a>,
 }
 
 struct Header {
+    tag: Tag,
     rifx_ofs: u32,
-    fmap_ofs: u32,
-    res1_ofs: u32,
-    res2_ofs: u32,
-    gfx_dll_ofs: u32,
-    snd_dll_ofs: u32,
-    rifx_ofs_alt: u32,
-    flags: u32,
+    fields: HeaderFields,
+}
+
+/// The fields following the RIFX offset, which differ between versions.
+enum HeaderFields {
+    /// Director 4.
+    Pj93 {
+        fmap_ofs: u32,
+        res1_ofs: u32,
+        res2_ofs: u32,
+        gfx_dll_ofs: u32,
+        snd_dll_ofs: u32,
+        rifx_ofs_alt: u32,
+        flags: u32,
+    },
+    /// Director 5 and 6.
+    Pj95 {
+        projector_flags: u32,
+        flags: u32,
+        x: u16,
+        y: u16,
+        screen_width: u16,
+        screen_height: u16,
+        component_count: u32,
+        driver_count: u32,
+        fmap_ofs: u32,
+    },
+    /// Director 7 and 8.
+    Pj00 { unknown: [u32; 4], dll_ofs: u32 },
+    /// Mac projectors, which keep their header at the start of the data fork
+    /// and the rest of their settings in resources.
+    Mac,
 }
 
 impl<'a> Projector<'a> {
@@ -33,54 +132,301 @@ impl<'a> Projector<'a> {
         let offset = reader.read_le_u32()?;
         reader.seek(SeekFrom::Start(offset as u64))?;
 
+        // Later projectors write their tag little-endian, like their XFIR
+        // bodies.
+        let tag = match Tag(reader.read_be_i32()?) {
+            tag if is_projector_tag(tag) => tag,
+            tag if is_projector_tag(Tag(tag.0.swap_bytes())) => Tag(tag.0.swap_bytes()),
+            tag => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Expected a projector tag (PJ93 to PJ01), found '{}'", tag),
+                ));
+            }
+        };
+
+        let rifx_ofs = reader.read_le_u32()?;
+
+        let fields = match tag {
+            tags::TAG_PJ93 => HeaderFields::Pj93 {
+                fmap_ofs: reader.read_le_u32()?,
+                res1_ofs: reader.read_le_u32()?,
+                res2_ofs: reader.read_le_u32()?,
+                gfx_dll_ofs: reader.read_le_u32()?,
+                snd_dll_ofs: reader.read_le_u32()?,
+                rifx_ofs_alt: reader.read_le_u32()?,
+                flags: reader.read_le_u32()?,
+            },
+            tags::TAG_PJ95 | tags::TAG_PJ97 => HeaderFields::Pj95 {
+                projector_flags: reader.read_le_u32()?,
+                flags: reader.read_le_u32()?,
+                x: reader.read_le_u16()?,
+                y: reader.read_le_u16()?,
+                screen_width: reader.read_le_u16()?,
+                screen_height: reader.read_le_u16()?,
+                component_count: reader.read_le_u32()?,
+                driver_count: reader.read_le_u32()?,
+                fmap_ofs: reader.read_le_u32()?,
+            },
+            _ => HeaderFields::Pj00 {
+                unknown: [
+                    reader.read_le_u32()?,
+                    reader.read_le_u32()?,
+                    reader.read_le_u32()?,
+                    reader.read_le_u32()?,
+                ],
+                dll_ofs: reader.read_le_u32()?,
+            },
+        };
+
+        let header = Header {
+            tag,
+            rifx_ofs,
+            fields,
+        };
+
+        Ok(Self { header, reader })
+    }
+
+    /// Reads the data fork of a Mac projector, which starts with the
+    /// big-endian projector tag and RIFX offset.
+    pub fn read_mac(reader: Reader<'a>) -> Result<Self> {
+        let mut reader = reader;
+        reader.seek(SeekFrom::Start(0))?;
+
         let tag = Tag(reader.read_be_i32()?);
-        if tag != TAG_PJ93 {
+        if !is_projector_tag(tag) {
             return Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
-                format!("Expected tag PJ93, found '{}'", tag),
+                format!("Expected a projector tag (PJ93 to PJ01), found '{}'", tag),
             ));
         }
 
         let header = Header {
-            rifx_ofs: reader.read_le_u32()?,
-            fmap_ofs: reader.read_le_u32()?,
-            res1_ofs: reader.read_le_u32()?,
-            res2_ofs: reader.read_le_u32()?,
-            gfx_dll_ofs: reader.read_le_u32()?,
-            snd_dll_ofs: reader.read_le_u32()?,
-            rifx_ofs_alt: reader.read_le_u32()?,
-            flags: reader.read_le_u32()?,
+            tag,
+            rifx_ofs: reader.read_be_u32()?,
+            fields: HeaderFields::Mac,
         };
 
         Ok(Self { header, reader })
     }
 
+    /// Whether `reader` is a Windows projector, which ends with the offset of
+    /// its projector header.
+    pub fn is_windows_projector(reader: &Reader) -> bool {
+        let mut r = reader.clone();
+        let tag = r
+            .seek(SeekFrom::End(-4))
+            .and_then(|_| r.read_le_u32())
+            .and_then(|offset| r.seek(SeekFrom::Start(offset as u64)))
+            .and_then(|_| r.read_be_i32());
+
+        tag.is_ok_and(|tag| is_projector_tag(Tag(tag)) || is_projector_tag(Tag(tag.swap_bytes())))
+    }
+
+    /// Whether `data_fork` is that of a Mac projector.
+    pub fn is_mac_projector(data_fork: &[u8]) -> bool {
+        data_fork
+            .first_chunk()
+            .is_some_and(|tag| is_projector_tag(Tag(i32::from_be_bytes(*tag))))
+    }
+
+    pub fn tag(&self) -> Tag {
+        self.header.tag
+    }
+
+    /// The major Director version that built the projector.
+    pub fn director_version(&self) -> u8 {
+        match self.header.tag {
+            tags::TAG_PJ93 => 4,
+            tags::TAG_PJ95 => 5,
+            tags::TAG_PJ97 => 6,
+            tags::TAG_PJ00 => 7,
+            _ => 8,
+        }
+    }
+
     pub fn display_header(&self) {
         let h = &self.header;
 
-        let rifx_ofs = h.rifx_ofs.separate_with_commas();
-        let fmap_ofs = h.fmap_ofs.separate_with_commas();
-        let res1_ofs = h.res1_ofs.separate_with_commas();
-        let res2_ofs = h.res2_ofs.separate_with_commas();
-        let gfx_dll_ofs = h.gfx_dll_ofs.separate_with_commas();
-        let snd_dll_ofs = h.snd_dll_ofs.separate_with_commas();
-        let rifx_ofs_alt = h.rifx_ofs_alt.separate_with_commas();
-        let flags = h.flags.separate_with_commas();
-
         println!();
-        println!("Projector Header:");
-        println!("=================");
-        println!("    rifx_ofs:     {:>11}", rifx_ofs);
-        println!("    fmap_ofs:     {:>11}", fmap_ofs);
-        println!("    res1_ofs:     {:>11}", res1_ofs);
-        println!("    res2_ofs:     {:>11}", res2_ofs);
-        println!("    gfx_dll_ofs:  {:>11}", gfx_dll_ofs);
-        println!("    snd_dll_ofs:  {:>11}", snd_dll_ofs);
-        println!("    rifx_ofs_alt: {:>11}", rifx_ofs_alt);
-        println!("    flags:        {:>11}", flags);
+        println!(
+            "Projector Header ({}, Director {}):",
+            h.tag,
+            self.director_version()
+        );
+        println!("=====================================");
+        println!(
+            "    rifx_ofs:        {:>11}",
+            h.rifx_ofs.separate_with_commas()
+        );
+
+        match &h.fields {
+            HeaderFields::Pj93 {
+                fmap_ofs,
+                res1_ofs,
+                res2_ofs,
+                gfx_dll_ofs,
+                snd_dll_ofs,
+                rifx_ofs_alt,
+                flags,
+            } => {
+                println!(
+                    "    fmap_ofs:        {:>11}",
+                    fmap_ofs.separate_with_commas()
+                );
+                println!(
+                    "    res1_ofs:        {:>11}",
+                    res1_ofs.separate_with_commas()
+                );
+                println!(
+                    "    res2_ofs:        {:>11}",
+                    res2_ofs.separate_with_commas()
+                );
+                println!(
+                    "    gfx_dll_ofs:     {:>11}",
+                    gfx_dll_ofs.separate_with_commas()
+                );
+                println!(
+                    "    snd_dll_ofs:     {:>11}",
+                    snd_dll_ofs.separate_with_commas()
+                );
+                println!(
+                    "    rifx_ofs_alt:    {:>11}",
+                    rifx_ofs_alt.separate_with_commas()
+                );
+                println!("    flags:           {:>11}", flags.separate_with_commas());
+            }
+            HeaderFields::Pj95 {
+                projector_flags,
+                flags,
+                x,
+                y,
+                screen_width,
+                screen_height,
+                component_count,
+                driver_count,
+                fmap_ofs,
+            } => {
+                println!(
+                    "    projector_flags: {:>11}",
+                    projector_flags.separate_with_commas()
+                );
+                println!("    flags:           {:>11}", flags.separate_with_commas());
+                println!("    position:        {:>11}", format!("{x}, {y}"));
+                println!(
+                    "    screen size:     {:>11}",
+                    format!("{screen_width}x{screen_height}")
+                );
+                println!("    components:      {:>11}", component_count);
+                println!("    drivers:         {:>11}", driver_count);
+                println!(
+                    "    fmap_ofs:        {:>11}",
+                    fmap_ofs.separate_with_commas()
+                );
+            }
+            HeaderFields::Pj00 { unknown, dll_ofs } => {
+                for (i, value) in unknown.iter().enumerate() {
+                    println!(
+                        "    unknown{i}:        {:>11}",
+                        value.separate_with_commas()
+                    );
+                }
+                println!(
+                    "    dll_ofs:         {:>11}",
+                    dll_ofs.separate_with_commas()
+                );
+            }
+            HeaderFields::Mac => {}
+        }
         println!();
     }
 
+    /// The files embedded in the projector: the `File` entries of its `APPL`
+    /// RIFF in order, or the initial RIFF itself if it is a movie.
+    ///
+    /// Files are named as they were when the projector was built, which
+    /// Director 5 and later record in the `Dict` and `List` chunks. Files
+    /// without a recorded name are named after their position and kind, e.g.
+    /// `02_cast.cst`.
+    pub fn files(&mut self) -> Result<Vec<ProjectorFile<'a>>> {
+        let riff = self.read_initial_riff()?;
+
+        let mut locations = Vec::new();
+        let mut names = HashMap::new();
+        if riff.type_tag() == tags::TAG_APPL {
+            for entry in riff.mmap().entries() {
+                if entry.tag() == tags::TAG_File {
+                    locations.push((Some(entry.id()), entry.pos(), entry.len()));
+                }
+            }
+            // The names only label the files, so damaged tables are ignored.
+            names = read_file_names(&riff).unwrap_or_default();
+        } else {
+            locations.push((None, self.header.rifx_ofs, riff.size() + 8));
+        }
+
+        let mut files: Vec<ProjectorFile<'a>> = Vec::with_capacity(locations.len());
+        for (index, (id, pos, len)) in locations.into_iter().enumerate() {
+            let mut reader = self.reader.clone();
+            reader.seek(SeekFrom::Start(pos as u64))?;
+
+            let kind = Self::detect_file_kind(reader.clone());
+
+            let name = id
+                .and_then(|id| names.remove(&id))
+                .filter(|name| files.iter().all(|file| file.name != *name))
+                .unwrap_or_else(|| format!("{:02}_{kind}.{}", index + 1, kind.extension()));
+
+            files.push(ProjectorFile {
+                name,
+                kind,
+                reader,
+                len,
+            });
+        }
+
+        Ok(files)
+    }
+
+    /// The first embedded movie, which is the one the projector plays.
+    pub fn read_main_movie(&mut self) -> Result<RiffFile<'a>> {
+        let file = self
+            .files()?
+            .into_iter()
+            .find(|file| file.kind == ProjectorFileKind::Movie)
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "The projector does not contain a movie",
+                )
+            })?;
+
+        RiffFile::new(file.reader())
+    }
+
+    fn detect_file_kind(reader: Reader<'a>) -> ProjectorFileKind {
+        let mut r = reader.clone();
+        if r.read_u8().ok() == Some(b'M') && r.read_u8().ok() == Some(b'Z') {
+            return ProjectorFileKind::Xtra;
+        }
+
+        let Ok(riff) = RiffFile::new(reader) else {
+            return ProjectorFileKind::Other;
+        };
+
+        // Casts share the movie format, minus the score. Other RIFFs, such
+        // as those of Xtras, are neither.
+        let mmap = riff.mmap();
+        match riff.type_tag() {
+            tags::TAG_MC95 => ProjectorFileKind::Cast,
+            tags::TAG_MV93 => ProjectorFileKind::Movie,
+            _ if mmap.first_entry_with_tag(tags::TAG_VWSC).is_some() => ProjectorFileKind::Movie,
+            _ if mmap.first_entry_with_tag(tags::TAG_CAS_).is_some() => ProjectorFileKind::Cast,
+            _ => ProjectorFileKind::Other,
+        }
+    }
+
     pub fn read_initial_riff(&mut self) -> Result<RiffFile<'a>> {
         self.reader
             .seek(std::io::SeekFrom::Start(self.header.rifx_ofs as u64))?;
@@ -90,3 +436,96 @@ impl<'a> Projector<'a> {
         Ok(riff)
     }
 }
+
+/// Reads the names of the `File` chunks of a Director 5 or later `APPL`
+/// RIFF, keyed by chunk id.
+///
+/// The `List` chunk lists the ids of the `File` chunks, and the `Dict` chunk
+/// maps each file name to a position in that list. Both start with the header
+/// of a Director list: the offset of the items, an unknown `u16`, the number
+/// of items used and allocated, and the size of an item. The `Dict` puts the
+/// offset and length of its names before that header; each of its items is
+/// the offset of a name from there, then a position in the `List`. Names are
+/// `u32`-prefixed strings and may be full paths, of which only the file name
+/// is kept.
+fn read_file_names(riff: &RiffFile) -> Result<HashMap<u32, String>> {
+    let read = |tag: Tag| -> Result<Vec<u8>> {
+        let entry = riff
+            .mmap()
+            .first_entry_with_tag(tag)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("No '{tag}' chunk")))?;
+        riff.read_chunk_data(entry.id())
+    };
+    let reader = |data| {
+        let mut r = Reader::new(data);
+        r.set_byte_order(riff.byte_order());
+        r.set_encoding(riff.encoding());
+        r
+    };
+
+    let list_data = read(tags::TAG_List)?;
+    let mut r = reader(&list_data);
+    let mut file_ids = Vec::new();
+    for position in list_item_positions(&mut r, 0)? {
+        r.seek(SeekFrom::Start(position))?;
+        file_ids.push(r.read_u32()?);
+    }
+
+    let dict_data = read(tags::TAG_Dict)?;
+    let mut r = reader(&dict_data);
+    let names_ofs = r.read_u32()? as u64;
+    let mut pairs = Vec::new();
+    for position in list_item_positions(&mut r, 8)? {
+        r.seek(SeekFrom::Start(position))?;
+        pairs.push((r.read_u32()?, r.read_u32()?));
+    }
+
+    let mut names = HashMap::new();
+    for (name_ofs, index) in pairs {
+        let Some(&id) = file_ids.get(index as usize) else {
+            continue;
+        };
+
+        r.seek(SeekFrom::Start(names_ofs + name_ofs as u64))?;
+        let len = r.read_u32()? as usize;
+        let path = r.read_fixed_str(len)?;
+        let name = path
+            .rsplit([':', '\\', '/'])
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches('\0');
+        if !matches!(name, "" | "." | "..") {
+            names.insert(id, name.to_string());
+        }
+    }
+
+    Ok(names)
+}
+
+/// Reads the header of a Director list at `header_ofs`, and returns the
+/// positions of its items.
+fn list_item_positions(r: &mut Reader, header_ofs: u64) -> Result<Vec<u64>> {
+    r.seek(SeekFrom::Start(header_ofs))?;
+    let items_ofs = r.read_u32()? as u64;
+    let _unknown = r.read_u16()?;
+    let used = r.read_u32()? as u64;
+    let _allocated = r.read_u32()?;
+    let item_len = r.read_u32()? as u64;
+
+    let start = header_ofs + items_ofs;
+    if item_len == 0 || start + used * item_len > r.len() as u64 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("The {used} items of {item_len} bytes do not fit in the list"),
+        ));
+    }
+
+    Ok((0..used).map(|i| start + i * item_len).collect())
+}
+
+fn is_projector_tag(tag: Tag) -> bool {
+    matches!(
+        tag,
+        tags::TAG_PJ93 | tags::TAG_PJ95 | tags::TAG_PJ97 | tags::TAG_PJ00 | tags::TAG_PJ01
+    )
+}
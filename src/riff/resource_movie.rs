@@ -0,0 +1,548 @@
+use std::io::{Error, ErrorKind, Result, Seek, SeekFrom};
+
+use crate::{
+    mac::{Resource, ResourceFork},
+    reader::{ByteOrder, ReadBytesExt, Reader, TextEncoding},
+};
+
+use super::{
+    Version,
+    cast_members::{CastMember, CastMemberId, CastMemberKind},
+    chunks::{CastTable, Chunk, Config, FileInfo, FrameLabels, Platform, Score},
+    tags::{self, Tag},
+};
+
+/// A record in the `VWCR` cast table.
+#[derive(Debug, Clone)]
+struct CastRecord<'a> {
+    id: i16,
+    cast_member_type_id: u8,
+    flags: Option<u8>,
+    data: Reader<'a>,
+}
+
+/// A Director 3 movie, which keeps its score, cast and config as resources
+/// rather than as chunks in a RIFX file: in the resource fork on the Mac, or
+/// in a `RIFF` file with a `CFTC` table of contents on Windows.
+///
+/// Cast members are stored inline in the `VWCR` cast table, with their media
+/// and info in resources numbered after the cast table, e.g. the `BITD` of
+/// member 3 is resource 1027 when the cast table is resource 1024.
+#[derive(Debug)]
+pub struct ResourceMovie<'a> {
+    resources: Vec<Resource<'a>>,
+    encoding: TextEncoding,
+    encoding_overridden: bool,
+    version: Version,
+    config: Config,
+    cast_table: CastTable,
+    cast_records: Vec<CastRecord<'a>>,
+    cast_id_offset: i16,
+    score: Score,
+    frame_labels: FrameLabels,
+    file_info: Option<FileInfo>,
+}
+
+impl<'a> ResourceMovie<'a> {
+    /// Reads a movie from the resource fork of a Mac movie.
+    pub fn new(resource_fork: &ResourceFork<'a>) -> Result<Self> {
+        Self::from_resources(resource_fork.resources().to_vec(), TextEncoding::MacRoman)
+    }
+
+    /// Whether `buf` is a Windows Director 3 movie, a little-endian `RIFF`
+    /// file that starts with a `CFTC` table of contents.
+    pub fn is_riff(buf: &[u8]) -> bool {
+        buf.len() >= 16
+            && Tag(i32::from_be_bytes(buf[0..4].try_into().unwrap())) == tags::TAG_RIFF
+            && Tag(i32::from_be_bytes(buf[12..16].try_into().unwrap())) == tags::TAG_CFTC
+    }
+
+    /// Reads a Windows Director 3 movie. Its table of contents lists the tag,
+    /// length, id and offset of every resource, and each resource is stored
+    /// at its offset behind its tag and length.
+    pub fn read_riff(reader: Reader<'a>) -> Result<Self> {
+        if !Self::is_riff(&reader.peek(16)?) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Expected a RIFF file with a CFTC table of contents".to_string(),
+            ));
+        }
+
+        let mut r = reader;
+        r.seek(SeekFrom::Start(0))?;
+        r.set_byte_order(ByteOrder::LittleEndian);
+
+        let _tag = r.read_be_i32()?;
+        let _size = r.read_le_u32()?;
+        let _type_tag = r.read_be_i32()?;
+        let _cftc_tag = r.read_be_i32()?;
+        let cftc_len = r.read_le_u32()? as usize;
+        let _unknown = r.read_le_u32()?;
+
+        let mut resources = Vec::new();
+        let end = 20 + cftc_len;
+        while 20 + 4 + (resources.len() + 1) * 16 <= end {
+            let tag = Tag(r.read_be_i32()?);
+            let len = r.read_le_u32()? as usize;
+            let id = r.read_le_u32()?;
+            let offset = r.read_le_u32()? as usize;
+
+            if tag.0 == 0 {
+                break;
+            }
+
+            if offset + 8 + len > r.len() {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Resource '{tag}' {id} extends past the end of the file"),
+                ));
+            }
+
+            let reader = r.subset(offset + 8, len)?;
+            resources.push(Resource::new(tag, id as i16, None, reader));
+        }
+
+        Self::from_resources(resources, TextEncoding::Windows1252)
+    }
+
+    fn from_resources(resources: Vec<Resource<'a>>, encoding: TextEncoding) -> Result<Self> {
+        if !resources
+            .iter()
+            .any(|resource| resource.tag == tags::TAG_VWSC)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "No 'VWSC' score resource found".to_string(),
+            ));
+        }
+
+        Ok(ResourceMovie {
+            resources,
+            encoding,
+            encoding_overridden: false,
+            version: Version::default(),
+            config: Config::default(),
+            cast_table: CastTable::default(),
+            cast_records: Vec::new(),
+            cast_id_offset: 0,
+            score: Score::default(),
+            frame_labels: FrameLabels::default(),
+            file_info: None,
+        })
+    }
+
+    pub fn resources(&self) -> &[Resource<'a>] {
+        &self.resources
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// The encoding of the movie's strings, which is detected from where the
+    /// movie was read and the platform in its config unless set with
+    /// `set_encoding`.
+    pub fn encoding(&self) -> TextEncoding {
+        self.encoding
+    }
+
+    pub fn set_encoding(&mut self, encoding: TextEncoding) {
+        self.encoding = encoding;
+        self.encoding_overridden = true;
+    }
+
+    fn resource(&self, tag: Tag, id: Option<i16>) -> Option<&Resource<'a>> {
+        self.resources
+            .iter()
+            .find(|resource| resource.tag == tag && id.is_none_or(|id| resource.id == id))
+    }
+
+    fn reader(&self, resource: &Resource<'a>) -> Reader<'a> {
+        let mut reader = resource.reader();
+        reader.set_byte_order(ByteOrder::BigEndian);
+        reader.set_encoding(self.encoding);
+        reader
+    }
+
+    fn try_read_resource<C: Chunk>(&self, id: Option<i16>) -> Result<Option<C>> {
+        let Some(resource) = self.resource(C::TAG, id) else {
+            return Ok(None);
+        };
+
+        C::read(&mut self.reader(resource), resource.id as u32, self.version).map(Some)
+    }
+
+    fn read_resource<C: Chunk>(&self) -> Result<C> {
+        self.try_read_resource(None)?
+            .ok_or_else(|| Error::from(ErrorKind::NotFound))
+    }
+
+    pub fn read_config(&mut self) -> Result<()> {
+        let config: Config = self.read_resource()?;
+
+        // Later versions write the version where Director 3 kept something
+        // else, so only versions that make sense here are taken.
+        if let Some(director_version) = config.director_version
+            && Version::new(director_version).major() == 3
+        {
+            self.version = Version::new(director_version);
+        }
+
+        if !self.encoding_overridden
+            && let Some(platform) = config.platform
+        {
+            self.encoding = match platform {
+                Platform::Mac => TextEncoding::MacRoman,
+                Platform::Windows => TextEncoding::Windows1252,
+            };
+        }
+
+        self.config = config;
+
+        Ok(())
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Reads the `VWCR` cast table, which holds a record of the type and
+    /// specific data of every member from the first to the last one.
+    pub fn read_cast_table(&mut self) -> Result<()> {
+        let resource = self
+            .resource(tags::TAG_VWCR, None)
+            .ok_or_else(|| Error::from(ErrorKind::NotFound))?
+            .clone();
+
+        let mut r = self.reader(&resource);
+        let first = r.read_be_i16()?;
+        let last = r.read_be_i16()?;
+
+        let mut cast_table = CastTable::default();
+        let mut cast_records = Vec::new();
+        for id in first..=last {
+            let Ok(len) = r.read_u8() else {
+                break;
+            };
+
+            if len == 0 {
+                continue;
+            }
+
+            let cast_member_type_id = r.read_u8()?;
+            let flags = if len > 1 { Some(r.read_u8()?) } else { None };
+
+            let data_len = (len as usize).saturating_sub(2);
+            let position = r.stream_position()? as usize;
+            if position + data_len > r.len() {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Cast record {id} extends past the end of the cast table"),
+                ));
+            }
+            let data = r.subset(position, data_len)?;
+            r.seek(SeekFrom::Current(data_len as i64))?;
+
+            cast_table.set_cast_member_chunk_id(
+                CastMemberId::new(id),
+                Some((resource.id as i32 + id as i32) as u32),
+            );
+            cast_records.push(CastRecord {
+                id,
+                cast_member_type_id,
+                flags,
+                data,
+            });
+        }
+
+        self.cast_table = cast_table;
+        self.cast_records = cast_records;
+        self.cast_id_offset = resource.id;
+
+        Ok(())
+    }
+
+    pub fn cast_table(&self) -> &CastTable {
+        &self.cast_table
+    }
+
+    pub fn load_cast_member(&self, id: CastMemberId) -> Result<CastMember> {
+        let record = self
+            .cast_records
+            .iter()
+            .find(|record| record.id == id.id())
+            .ok_or_else(|| Error::from(ErrorKind::NotFound))?;
+
+        let resource_id = self.cast_id_offset.wrapping_add(record.id);
+
+        let info_reader = self
+            .resource(tags::TAG_VWCI, Some(resource_id))
+            .map(|resource| self.reader(resource));
+
+        let mut data_reader = record.data.clone();
+        data_reader.set_encoding(self.encoding);
+
+        let mut cast_member = CastMember::read_record(
+            record.cast_member_type_id,
+            record.flags,
+            data_reader,
+            info_reader,
+            resource_id as u32,
+            self.version,
+        )
+        .map_err(|err| {
+            Error::new(
+                err.kind(),
+                format!("ResourceMovie::load_cast_member: Failed to read cast member {id}: {err}"),
+            )
+        })?;
+
+        match &mut cast_member.kind {
+            CastMemberKind::Bitmap(bitmap) => {
+                bitmap.data = self.try_read_resource(Some(resource_id))?;
+            }
+            CastMemberKind::Text(text) | CastMemberKind::Button(text) => {
+                text.styled_text = self.try_read_resource(Some(resource_id))?;
+            }
+            CastMemberKind::Palette(palette) => {
+                palette.clut = self.try_read_resource(Some(resource_id))?;
+            }
+            CastMemberKind::Sound(sound) => {
+                sound.data = self.try_read_resource(Some(resource_id))?;
+            }
+            _ => {}
+        }
+
+        Ok(cast_member)
+    }
+
+    pub fn read_score(&mut self) -> Result<()> {
+        self.score = self.read_resource()?;
+
+        Ok(())
+    }
+
+    pub fn score(&self) -> &Score {
+        &self.score
+    }
+
+    pub fn read_frame_labels(&mut self) -> Result<()> {
+        self.frame_labels = self.read_resource()?;
+
+        Ok(())
+    }
+
+    pub fn frame_labels(&self) -> &FrameLabels {
+        &self.frame_labels
+    }
+
+    pub fn read_file_info(&mut self) -> Result<()> {
+        self.file_info = self.try_read_resource(None)?;
+
+        Ok(())
+    }
+
+    pub fn file_info(&self) -> Option<&FileInfo> {
+        self.file_info.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::riff::{Movie, Tempo, cast_members::CastMemberType};
+
+    use super::*;
+
+    /// Lays `resources` out as a Windows Director 3 movie: a `CFTC` table of
+    /// contents, then each resource behind its tag and length.
+    fn riff_movie(resources: &[(Tag, u32, Vec<u8>)]) -> Vec<u8> {
+        let cftc_len = 4 + 16 * resources.len();
+        let mut offset = 20 + cftc_len;
+
+        let mut toc = Vec::new();
+        let mut data = Vec::new();
+        for (tag, id, resource) in resources {
+            toc.extend(tag.0.to_be_bytes());
+            toc.extend((resource.len() as u32).to_le_bytes());
+            toc.extend(id.to_le_bytes());
+            toc.extend((offset as u32).to_le_bytes());
+
+            data.extend(tag.0.to_be_bytes());
+            data.extend((resource.len() as u32).to_le_bytes());
+            data.extend(resource);
+            offset += 8 + resource.len();
+        }
+
+        let mut buf = Vec::new();
+        buf.extend(tags::TAG_RIFF.0.to_be_bytes());
+        buf.extend(((offset - 8) as u32).to_le_bytes());
+        buf.extend(i32::from_be_bytes(*b"MV93").to_be_bytes());
+        buf.extend(tags::TAG_CFTC.0.to_be_bytes());
+        buf.extend((cftc_len as u32).to_le_bytes());
+        buf.extend(0u32.to_le_bytes());
+        buf.extend(toc);
+        buf.extend(data);
+        buf
+    }
+
+    fn styled_text(text: &str) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend(12u32.to_be_bytes());
+        data.extend((text.len() as u32).to_be_bytes());
+        data.extend(22u32.to_be_bytes());
+        data.extend(text.as_bytes());
+        data.extend(1u16.to_be_bytes());
+        data.extend([0; 20]);
+        data
+    }
+
+    /// Members 1 and 3 of a cast table that starts at resource 1024, with
+    /// nothing in between.
+    fn cast_table() -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend(1i16.to_be_bytes());
+        data.extend(3i16.to_be_bytes());
+        data.extend([2, u8::from(CastMemberType::Text), 0]);
+        data.push(0);
+        data.extend([2, u8::from(CastMemberType::Button), 0]);
+        data
+    }
+
+    /// Two frames of a Director 3 score, whose runs are counted in words.
+    /// The first sets the tempo and puts member 1 in the first sprite
+    /// channel, and the second changes nothing.
+    fn score() -> Vec<u8> {
+        let sprite = [0, 1, 0, 0, 0, 8, 0, 1, 0, 20, 0, 30, 0, 40, 0, 50];
+
+        let mut frame = Vec::new();
+        frame.extend([1, 2, 10, 0]);
+        frame.extend([8, 16]);
+        frame.extend(sprite);
+
+        let mut frames = Vec::new();
+        frames.extend((2 + frame.len() as u16).to_be_bytes());
+        frames.extend(frame);
+        frames.extend(2u16.to_be_bytes());
+
+        let mut data = Vec::new();
+        data.extend((4 + frames.len() as u32).to_be_bytes());
+        data.extend(frames);
+        data
+    }
+
+    fn movie_data() -> Vec<u8> {
+        riff_movie(&[
+            (tags::TAG_VWSC, 1024, score()),
+            (tags::TAG_VWCR, 1024, cast_table()),
+            (tags::TAG_STXT, 1025, styled_text("Hello")),
+            (tags::TAG_STXT, 1027, styled_text("OK")),
+        ])
+    }
+
+    #[test]
+    fn reads_the_cast_table() {
+        let data = movie_data();
+        let movie = Movie::from_bytes(&data).unwrap();
+        let movie = movie.resource_movie().unwrap();
+
+        let ids: Vec<_> = movie
+            .cast_table()
+            .cast_member_ids()
+            .map(|id| id.id())
+            .collect();
+        assert_eq!(ids, [1, 3]);
+
+        let text = movie.load_cast_member(CastMemberId::new(1)).unwrap();
+        let CastMemberKind::Text(text) = text.kind else {
+            panic!("Expected a text member, found {:?}", text.kind);
+        };
+        assert_eq!(text.text().as_deref(), Some("Hello"));
+
+        let button = movie.load_cast_member(CastMemberId::new(3)).unwrap();
+        let CastMemberKind::Button(button) = button.kind else {
+            panic!("Expected a button, found {:?}", button.kind);
+        };
+        assert_eq!(button.text().as_deref(), Some("OK"));
+
+        let err = movie.load_cast_member(CastMemberId::new(2)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn reads_the_score() {
+        let data = movie_data();
+        let movie = Movie::from_bytes(&data).unwrap();
+        let frames = movie.resource_movie().unwrap().score().frames();
+
+        assert_eq!(frames.len(), 2);
+        for frame in frames {
+            assert_eq!(frame.tempo, Some(Tempo::FPS(10)));
+            let [(channel, sprite)] = frame.sprite_channels.as_slice() else {
+                panic!("Expected one sprite, found {:?}", frame.sprite_channels);
+            };
+            assert_eq!(*channel, 6);
+            assert_eq!(sprite.cast_member_id, Some(CastMemberId::new(1)));
+            assert_eq!(sprite.ink, 8);
+            assert_eq!((sprite.position.x, sprite.position.y), (30, 20));
+            assert_eq!((sprite.size.w, sprite.size.h), (50, 40));
+        }
+    }
+
+    #[test]
+    fn rejects_damaged_score_frames() {
+        // A frame shorter than its length field, one that ends inside a run
+        // header, and one whose run is longer than the frame.
+        for frame in [&[0, 1][..], &[0, 3, 0], &[0, 6, 2, 0, 1, 2]] {
+            let mut score = (4 + frame.len() as u32).to_be_bytes().to_vec();
+            score.extend(frame);
+
+            let data = riff_movie(&[
+                (tags::TAG_VWSC, 1024, score),
+                (tags::TAG_VWCR, 1024, cast_table()),
+            ]);
+            let Err(err) = Movie::from_bytes(&data) else {
+                panic!("Expected the score {frame:?} to be rejected");
+            };
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn rejects_damaged_styled_text() {
+        let mut bad_header = styled_text("Hello");
+        bad_header[3] = 16;
+        let mut bad_runs = styled_text("Hello");
+        bad_runs[11] = 23;
+
+        for styled_text in [bad_header, bad_runs] {
+            let data = riff_movie(&[
+                (tags::TAG_VWSC, 1024, score()),
+                (tags::TAG_VWCR, 1024, cast_table()),
+                (tags::TAG_STXT, 1025, styled_text),
+            ]);
+            let movie = Movie::from_bytes(&data).unwrap();
+            let err = movie
+                .resource_movie()
+                .unwrap()
+                .load_cast_member(CastMemberId::new(1))
+                .unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn rejects_a_cast_record_past_the_end_of_the_table() {
+        let mut cast_table = cast_table();
+        cast_table.extend([9, u8::from(CastMemberType::Text), 0]);
+        cast_table[2..4].copy_from_slice(&4i16.to_be_bytes());
+
+        let data = riff_movie(&[
+            (tags::TAG_VWSC, 1024, score()),
+            (tags::TAG_VWCR, 1024, cast_table),
+        ]);
+        let Err(err) = Movie::from_bytes(&data) else {
+            panic!("Expected the cast table to be rejected");
+        };
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -1,19 +1,25 @@
-use std::io::{Error, ErrorKind, Result, Seek};
+use std::{
+    io::{Error, ErrorKind, Result, Seek, SeekFrom},
+    sync::{Mutex, MutexGuard, PoisonError},
+};
 
-use crate::reader::{ByteOrder, ReadBytesExt, Reader};
+use crate::reader::{ByteOrder, ReadBytesExt, Reader, TextEncoding};
 
 use super::{
-    cast_members::{CastMember, CastMemberId},
+    DEFAULT_CACHE_BUDGET, LinkedFileResolver, Version,
+    cache::ChunkCache,
+    cast_members::{
+        BitmapInfo, CastMember, CastMemberId, CastMemberInfo, CastMemberKind, LinkedMedia,
+    },
     chunks::{
-        CastTable, Chunk, Config, FileInfo, FrameLabels, InitialMap, KeyTable, LingoContext,
-        LingoNames, LingoScript, MemoryMap, Score, read_chunk_from_reader,
-        read_chunk_from_reader_with_tag,
+        AnyChunk, CastTable, Chunk, ColorLookupTable, Config, EntryState, FileInfo, FrameLabels,
+        InitialMap, KeyTable, LingoContext, LingoNames, LingoScript, MemoryMap, MemoryMapEntry,
+        Platform, Score, read_chunk_from_reader, read_chunk_header,
     },
     tags::{self, Tag},
-    version::Version,
 };
 
-const GLOBAL_ID: u32 = 1024;
+pub(crate) const GLOBAL_ID: u32 = 1024;
 
 #[allow(unused)]
 #[derive(Debug)]
@@ -21,6 +27,8 @@ pub struct RiffFile<'a> {
     size: u32,
     type_tag: Tag,
     byte_order: ByteOrder,
+    encoding: TextEncoding,
+    encoding_overridden: bool,
     version: Version,
     imap: InitialMap,
     mmap: MemoryMap,
@@ -33,37 +41,53 @@ pub struct RiffFile<'a> {
     lingo_names: Option<LingoNames>,
     lingo_script: Option<LingoScript>,
     file_info: Option<FileInfo>,
+    linked_file_resolver: Option<LinkedFileResolver>,
+    tolerant: bool,
+    cache: Mutex<ChunkCache>,
     reader: Reader<'a>,
 }
 
 impl<'a> RiffFile<'a> {
     pub fn new(reader: Reader<'a>) -> Result<Self> {
         let mut reader = reader;
-        let riff_tag = Tag(reader.read_be_i32()?);
+        let (byte_order, size, type_tag) = Self::read_header(&mut reader)?;
+
+        if type_tag == tags::TAG_FGDM || type_tag == tags::TAG_FGDC {
+            let version = read_afterburner_version(&mut reader)?;
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                format!("Afterburner-compressed movies are not supported (Director {version})"),
+            ));
+        }
 
-        let byte_order = match riff_tag {
-            tags::TAG_XFIR => ByteOrder::LittleEndian,
-            tags::TAG_RIFX => ByteOrder::BigEndian,
-            _ => {
-                panic!("Invalid header '{}'", riff_tag);
-            }
+        // Until the config says otherwise, assume the platform the byte order
+        // is native to.
+        let encoding = match byte_order {
+            ByteOrder::BigEndian => TextEncoding::MacRoman,
+            ByteOrder::LittleEndian => TextEncoding::Windows1252,
         };
+        reader.set_encoding(encoding);
 
-        reader.set_byte_order(byte_order);
+        let imap = read_chunk_from_reader::<InitialMap>(&mut reader, 1, Version::default())?;
 
-        let size = reader.read_u32()?;
-        let type_tag = Tag(reader.read_i32()?);
-
-        let imap = read_chunk_from_reader::<InitialMap>(&mut reader, 1)?;
+        // Director 4 movies leave the version in the imap at zero, in which
+        // case it is picked up from the config in `read_config`. RIFX movies
+        // start with Director 4, so that is assumed until then.
+        let version = match imap.director_version {
+            0 => Version::new(0x45B),
+            v => Version::new(v as u16),
+        };
 
         reader.seek(std::io::SeekFrom::Start(imap.mmap_offset as u64))?;
-        let mmap = read_chunk_from_reader::<MemoryMap>(&mut reader, 2)?;
+        let mmap = read_chunk_from_reader::<MemoryMap>(&mut reader, 2, version)?;
 
         let riff = RiffFile {
             size,
             type_tag,
             byte_order,
-            version: Version::default(),
+            encoding,
+            encoding_overridden: false,
+            version,
             imap,
             mmap,
             key_table: KeyTable::default(),
@@ -75,12 +99,62 @@ impl<'a> RiffFile<'a> {
             lingo_names: None,
             lingo_script: None,
             file_info: None,
+            linked_file_resolver: None,
+            tolerant: false,
+            cache: Mutex::new(ChunkCache::new(DEFAULT_CACHE_BUDGET)),
             reader,
         };
 
         Ok(riff)
     }
 
+    fn read_header(reader: &mut Reader<'a>) -> Result<(ByteOrder, u32, Tag)> {
+        let riff_tag = Tag(reader.read_be_i32()?);
+
+        let byte_order = match riff_tag {
+            tags::TAG_XFIR => ByteOrder::LittleEndian,
+            tags::TAG_RIFX => ByteOrder::BigEndian,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Invalid header '{}'", riff_tag),
+                ));
+            }
+        };
+
+        reader.set_byte_order(byte_order);
+
+        let size = reader.read_u32()?;
+        let type_tag = Tag(reader.read_i32()?);
+
+        Ok((byte_order, size, type_tag))
+    }
+
+    /// Determines the Director version of a movie without loading its cast or
+    /// score. Unlike `new`, this also works for Afterburner-compressed movies.
+    pub fn detect_version(reader: Reader<'a>) -> Result<Version> {
+        let mut header_reader = reader.clone();
+        let (_, _, type_tag) = Self::read_header(&mut header_reader)?;
+
+        if type_tag == tags::TAG_FGDM || type_tag == tags::TAG_FGDC {
+            return read_afterburner_version(&mut header_reader);
+        }
+
+        let mut riff = RiffFile::new(reader)?;
+        riff.read_key_table()?;
+
+        // A missing config is not fatal, the imap version is used instead.
+        let _ = riff.read_config();
+
+        Ok(riff.version())
+    }
+
+    /// The size of the file as recorded in its header, which excludes the
+    /// header's tag and size.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
     pub fn type_tag(&self) -> Tag {
         self.type_tag
     }
@@ -89,6 +163,25 @@ impl<'a> RiffFile<'a> {
         self.version
     }
 
+    pub fn byte_order(&self) -> ByteOrder {
+        self.byte_order
+    }
+
+    /// The encoding of the movie's strings, which is detected from its byte
+    /// order and the platform in its config unless set with `set_encoding`.
+    pub fn encoding(&self) -> TextEncoding {
+        self.encoding
+    }
+
+    /// Overrides the detected encoding, e.g. for Japanese movies, which use
+    /// Shift-JIS. Chunks read before this are not affected.
+    pub fn set_encoding(&mut self, encoding: TextEncoding) {
+        self.encoding = encoding;
+        self.encoding_overridden = true;
+        self.reader.set_encoding(encoding);
+        self.clear_cache();
+    }
+
     pub fn imap(&self) -> &InitialMap {
         &self.imap
     }
@@ -101,16 +194,195 @@ impl<'a> RiffFile<'a> {
         self.file_info.as_ref()
     }
 
+    /// Lets chunks whose header disagrees with the memory map, or that run
+    /// past the end of the file, be read as far as they go rather than fail,
+    /// so that damaged files can still be opened. `check_chunk_sizes` lists
+    /// them.
+    pub fn set_tolerant(&mut self, tolerant: bool) {
+        self.tolerant = tolerant;
+        self.clear_cache();
+    }
+
+    pub fn tolerant(&self) -> bool {
+        self.tolerant
+    }
+
+    /// Checks the size of every chunk in the memory map against its header
+    /// and the end of the file.
+    pub fn check_chunk_sizes(&self) -> Vec<Error> {
+        let mut problems = Vec::new();
+
+        for entry in self.mmap.entries() {
+            // The file itself is checked when it is opened, and free and junk
+            // chunks have nothing left to read.
+            if matches!(
+                entry.tag(),
+                tags::TAG_RIFX | tags::TAG_XFIR | tags::TAG_free | tags::TAG_junk
+            ) {
+                continue;
+            }
+
+            if let Err(err) = self.check_chunk_in_file(entry) {
+                problems.push(err);
+                continue;
+            }
+
+            let mut reader = self.reader.clone();
+            let size = reader
+                .seek(SeekFrom::Start(entry.pos() as u64 + 4))
+                .and_then(|_| reader.read_u32());
+
+            match size.map(|size| self.chunk_len(entry, size)) {
+                Ok((_, Some(problem))) => {
+                    problems.push(Error::new(ErrorKind::InvalidData, problem))
+                }
+                Ok((_, None)) => {}
+                Err(err) => problems.push(err),
+            }
+        }
+
+        problems
+    }
+
+    pub(crate) fn cache(&self) -> MutexGuard<'_, ChunkCache> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn cache_mut(&mut self) -> &mut ChunkCache {
+        self.cache.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The length of the file the movie was read from, which may be a
+    /// projector it is embedded in.
+    pub(crate) fn file_len(&self) -> usize {
+        self.reader.len()
+    }
+
+    /// Reads the tag and size from the header of the chunk at `position`.
+    pub(crate) fn read_chunk_header_at(&self, position: u32) -> Result<(Tag, u32)> {
+        let mut reader = self.reader.clone();
+        reader.seek(SeekFrom::Start(position as u64))?;
+
+        Ok((Tag(reader.read_i32()?), reader.read_u32()?))
+    }
+
+    /// The tag and size in the header of chunk `id` as they are in the file,
+    /// which can disagree with its memory map entry in a damaged movie.
+    pub fn chunk_header(&self, id: u32) -> Result<(Tag, u32)> {
+        let entry = self
+            .mmap()
+            .entry_by_index(id)
+            .ok_or_else(|| Error::from(ErrorKind::NotFound))?;
+        self.check_chunk_in_file(entry)?;
+
+        self.read_chunk_header_at(entry.pos())
+    }
+
+    /// Checks that the header of the chunk of `entry` is in the file.
+    fn check_chunk_in_file(&self, entry: &MemoryMapEntry) -> Result<()> {
+        if entry.pos() as usize + 8 > self.reader.len() {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                format!(
+                    "Chunk {} '{}' at 0x{:x} is past the end of the file",
+                    entry.id(),
+                    entry.tag(),
+                    entry.pos()
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// How much of the chunk of `entry`, whose header gives its size as
+    /// `size`, can be read, and what is wrong with it if its size disagrees
+    /// with the memory map or the end of the file.
+    fn chunk_len(&self, entry: &MemoryMapEntry, size: u32) -> (usize, Option<String>) {
+        let available = self.reader.len().saturating_sub(entry.pos() as usize + 8);
+        let len = (size.min(entry.len()) as usize).min(available);
+
+        let problem = (size != entry.len() || size as usize > available).then(|| {
+            format!(
+                "Chunk {} '{}' at 0x{:x} is {size} bytes long in its header, {} in the memory map, and {available} bytes are left in the file",
+                entry.id(),
+                entry.tag(),
+                entry.pos(),
+                entry.len()
+            )
+        });
+
+        (len, problem)
+    }
+
+    /// A reader over the data of the chunk of `entry`, which must be an
+    /// `expected_tag` chunk whose size agrees with the memory map and the end
+    /// of the file, unless the file is tolerant.
+    fn chunk_reader(&self, entry: &MemoryMapEntry, expected_tag: Tag) -> Result<Reader<'a>> {
+        self.check_chunk_in_file(entry)?;
+
+        let mut reader = self.reader.clone();
+        reader.seek(SeekFrom::Start(entry.pos() as u64))?;
+
+        let size = read_chunk_header(&mut reader, expected_tag)?;
+        let (len, problem) = self.chunk_len(entry, size);
+        if let Some(problem) = problem
+            && !self.tolerant
+        {
+            return Err(Error::new(ErrorKind::InvalidData, problem));
+        }
+
+        reader.subset(entry.pos() as usize + 8, len)
+    }
+
+    fn read_entry<C: Chunk>(&self, entry: &MemoryMapEntry, expected_tag: Tag) -> Result<C> {
+        let mut reader = self.chunk_reader(entry, expected_tag)?;
+
+        C::read(&mut reader, entry.id(), self.version)
+    }
+
     pub fn try_read_chunk_by_id<C: Chunk>(&self, id: u32) -> Result<Option<C>> {
         let Some(entry) = self.mmap().entry_by_index(id) else {
             return Ok(None);
         };
 
-        let mut reader = self.reader.clone();
-        reader.seek(std::io::SeekFrom::Start(entry.pos() as u64))?;
+        self.read_entry(entry, C::TAG).map(Some)
+    }
+
+    /// Reads chunk `id` with the `Chunk` type its tag calls for, or as bytes
+    /// if there is none.
+    pub fn read_any_chunk(&self, id: u32) -> Result<AnyChunk> {
+        let entry = self
+            .mmap()
+            .entry_by_index(id)
+            .ok_or_else(|| Error::from(ErrorKind::NotFound))?;
+
+        let mut reader = self.chunk_reader(entry, entry.tag())?;
+        AnyChunk::read(&mut reader, entry.tag(), id, self.version)
+    }
+
+    /// The chunks of the movie in memory map order, each read when the
+    /// iterator gets to it. The entry of the file itself and `free` and
+    /// `junk` entries are skipped.
+    pub fn chunks(&self) -> AnyChunks<'_, 'a> {
+        AnyChunks {
+            riff: self,
+            entries: self.mmap.entries().iter(),
+        }
+    }
+
+    /// Reads the contents of chunk `id` without parsing them.
+    pub fn read_chunk_data(&self, id: u32) -> Result<Vec<u8>> {
+        let entry = self
+            .mmap()
+            .entry_by_index(id)
+            .ok_or_else(|| Error::from(ErrorKind::NotFound))?;
+
+        let mut data = Vec::new();
+        self.chunk_reader(entry, entry.tag())?
+            .read_to_end(&mut data)?;
 
-        let chunk = read_chunk_from_reader::<C>(&mut reader, entry.id())?;
-        Ok(Some(chunk))
+        Ok(data)
     }
 
     pub fn read_chunk_by_id<C: Chunk>(&self, id: u32) -> Result<C> {
@@ -121,17 +393,13 @@ impl<'a> RiffFile<'a> {
         Err(Error::from(std::io::ErrorKind::NotFound))
     }
 
-    fn read_chunk_by_tag<C: Chunk>(&self) -> Result<C> {
+    pub(crate) fn read_chunk_by_tag<C: Chunk>(&self) -> Result<C> {
         let entry = self
             .mmap()
             .first_entry_with_tag(C::TAG)
             .ok_or_else(|| Error::from(std::io::ErrorKind::NotFound))?;
 
-        let mut reader = self.reader.clone();
-        reader.seek(std::io::SeekFrom::Start(entry.pos() as u64))?;
-
-        let chunk = read_chunk_from_reader::<C>(&mut reader, entry.id())?;
-        Ok(chunk)
+        self.read_entry(entry, C::TAG)
     }
 
     fn try_read_chunk_by_parent<C: Chunk>(&self, parent: u32) -> Result<Option<C>> {
@@ -143,11 +411,7 @@ impl<'a> RiffFile<'a> {
             return Ok(None);
         };
 
-        let mut reader = self.reader.clone();
-        reader.seek(std::io::SeekFrom::Start(entry.pos() as u64))?;
-
-        let chunk = read_chunk_from_reader::<C>(&mut reader, entry.id())?;
-        Ok(Some(chunk))
+        self.read_entry(entry, C::TAG).map(Some)
     }
 
     fn read_chunk_by_parent<C: Chunk>(&self, parent: u32) -> Result<C> {
@@ -168,6 +432,152 @@ impl<'a> RiffFile<'a> {
         &self.key_table
     }
 
+    /// The id of the `CAS*` chunk the cast table was read from.
+    fn cast_table_id(&self) -> Option<u32> {
+        self.key_table
+            .find_id_of_chunk_with_parent(tags::TAG_CAS_, GLOBAL_ID)
+    }
+
+    /// The id of the `Lctx` chunk the Lingo context is read from.
+    fn lingo_context_id(&self) -> Option<u32> {
+        self.key_table
+            .find_id_of_chunk_with_parent(tags::TAG_Lctx, GLOBAL_ID)
+    }
+
+    /// The `Lnam` and `Lscr` chunks the `Lctx` chunk `id` lists, or none if
+    /// it cannot be read.
+    fn lingo_context_chunks(&self, id: u32) -> Vec<u32> {
+        let Ok(lingo_context) = self.read_chunk_by_id::<LingoContext>(id) else {
+            return Vec::new();
+        };
+
+        std::iter::once(lingo_context.names_chunk_id())
+            .chain(
+                lingo_context
+                    .entries()
+                    .iter()
+                    .filter_map(|entry| entry.script_id()),
+            )
+            .filter(|&id| self.mmap.entry_by_index(id).is_some())
+            .collect()
+    }
+
+    /// Whether `id` is the entry of the file itself, which owns the `imap`,
+    /// `mmap` and `KEY*` tables.
+    fn is_file_entry(&self, id: u32) -> bool {
+        id == 0
+            && self
+                .mmap
+                .entry_by_index(id)
+                .is_some_and(|entry| matches!(entry.tag(), tags::TAG_RIFX | tags::TAG_XFIR))
+    }
+
+    fn is_table(tag: Tag) -> bool {
+        matches!(tag, tags::TAG_imap | tags::TAG_mmap | tags::TAG_KEY_)
+    }
+
+    /// The owners of chunk `id`, nearest first. Besides what the key table
+    /// records, the `CASt` chunks of cast members are owned by the `CAS*`
+    /// cast table, which needs the cast table to have been read, the `Lscr`
+    /// and `Lnam` chunks by the `Lctx` chunk that lists them, and the tables
+    /// by the file's own entry.
+    pub fn chunk_owners(&self, id: u32) -> Vec<u32> {
+        let mut owners = self.key_table.owners(id);
+
+        let top = owners.last().copied().unwrap_or(id);
+        if let Some(cast_table_id) = self.cast_table_id()
+            && top != cast_table_id
+            && self.cast_table.cast_member_with_chunk_id(top).is_some()
+        {
+            owners.push(cast_table_id);
+            owners.extend(self.key_table.owners(cast_table_id));
+        }
+
+        let tag = self.mmap.entry_by_index(id).map(|entry| entry.tag());
+        if owners.is_empty()
+            && matches!(tag, Some(tags::TAG_Lscr | tags::TAG_Lnam))
+            && let Some(lingo_context_id) = self.lingo_context_id()
+            && self.lingo_context_chunks(lingo_context_id).contains(&id)
+        {
+            owners.push(lingo_context_id);
+            owners.extend(self.key_table.owners(lingo_context_id));
+        }
+
+        if owners.is_empty() && tag.is_some_and(Self::is_table) && self.is_file_entry(0) {
+            owners.push(0);
+        }
+
+        owners
+    }
+
+    /// The chunks chunk `id` owns, including those `chunk_owners` adds to
+    /// what the key table records.
+    pub fn chunk_children(&self, id: u32) -> Vec<u32> {
+        let mut children: Vec<u32> = self
+            .key_table
+            .chunks_with_parent(id)
+            .map(|entry| entry.id())
+            .collect();
+
+        if self.cast_table_id() == Some(id) {
+            children.extend(
+                self.cast_table
+                    .cast_member_ids()
+                    .filter_map(|member| self.cast_table.cast_member_chunk_id(member)),
+            );
+        }
+
+        if self.lingo_context_id() == Some(id) {
+            children.extend(
+                self.lingo_context_chunks(id)
+                    .into_iter()
+                    .filter(|&child| self.key_table.owners(child).is_empty()),
+            );
+        }
+
+        if self.is_file_entry(id) {
+            children.extend(
+                self.mmap
+                    .entries()
+                    .iter()
+                    .filter(|entry| Self::is_table(entry.tag()))
+                    .map(|entry| entry.id()),
+            );
+        }
+
+        children
+    }
+
+    /// The chunks at the top of the tree `chunk_children` makes: the file's
+    /// own entry, and the parents in the key table that nothing owns.
+    pub fn chunk_roots(&self) -> Vec<u32> {
+        let file = self.is_file_entry(0).then_some(0);
+        let roots = self.key_table.roots();
+
+        file.into_iter()
+            .chain(
+                roots
+                    .into_iter()
+                    .filter(|&root| self.chunk_owners(root).is_empty()),
+            )
+            .collect()
+    }
+
+    /// The chunks tagged `tag` anywhere under cast member `id`, e.g. the
+    /// `BITD` of a bitmap.
+    pub fn cast_member_chunks(&self, id: CastMemberId, tag: Tag) -> Vec<u32> {
+        let Some(chunk_id) = self.cast_table.cast_member_chunk_id(id) else {
+            return Vec::new();
+        };
+
+        self.key_table
+            .descendants(chunk_id)
+            .into_iter()
+            .filter(|entry| entry.tag() == tag)
+            .map(|entry| entry.id())
+            .collect()
+    }
+
     pub fn read_config(&mut self) -> Result<()> {
         let mut entry = self
             .key_table()
@@ -189,11 +599,25 @@ impl<'a> RiffFile<'a> {
             return Err(Error::from(std::io::ErrorKind::NotFound));
         };
 
-        let mut reader = self.reader.clone();
-        reader.seek(std::io::SeekFrom::Start(entry.pos() as u64))?;
-        let config = read_chunk_from_reader_with_tag::<Config>(&mut reader, entry.id(), tag)?;
+        let config: Config = self.read_entry(entry, tag)?;
+
+        if let Some(director_version) = config.director_version.filter(|&v| v != 0) {
+            self.version = Version::new(director_version);
+        }
+
+        if !self.encoding_overridden
+            && let Some(platform) = config.platform
+        {
+            self.encoding = match platform {
+                Platform::Mac => TextEncoding::MacRoman,
+                Platform::Windows => TextEncoding::Windows1252,
+            };
+            self.reader.set_encoding(self.encoding);
+        }
 
         self.config = config;
+        // The version and encoding chunks are decoded with may have changed.
+        self.clear_cache();
 
         Ok(())
     }
@@ -204,6 +628,7 @@ impl<'a> RiffFile<'a> {
 
     pub fn read_cast_table(&mut self) -> Result<()> {
         self.cast_table = self.read_chunk_by_parent::<CastTable>(GLOBAL_ID)?;
+        self.clear_cache();
 
         Ok(())
     }
@@ -212,6 +637,17 @@ impl<'a> RiffFile<'a> {
         &self.cast_table
     }
 
+    /// Sets the resolver used to load the media of linked cast members.
+    /// Without one, linked members are loaded without their media.
+    pub fn set_linked_file_resolver(&mut self, resolver: LinkedFileResolver) {
+        self.linked_file_resolver = Some(resolver);
+        self.clear_cache();
+    }
+
+    pub fn linked_file_resolver(&self) -> Option<&LinkedFileResolver> {
+        self.linked_file_resolver.as_ref()
+    }
+
     pub fn load_cast_member(&self, id: CastMemberId) -> Result<CastMember> {
         let chunk_id = self
             .cast_table
@@ -228,32 +664,77 @@ impl<'a> RiffFile<'a> {
             })?;
 
         let parent_id = chunk_id;
-        match &mut cast_member {
-            CastMember::Null => {}
-            CastMember::Bitmap(bitmap) => {
+        match &mut cast_member.kind {
+            CastMemberKind::Null => {}
+            CastMemberKind::Bitmap(bitmap) => {
                 bitmap.data = self.try_read_chunk_by_parent(parent_id)?;
                 bitmap.thumbnail = self.try_read_chunk_by_parent(parent_id)?;
+                bitmap.linked = self.try_load_linked_media(&cast_member.info)?;
             }
-            CastMember::FilmLoop => {}
-            CastMember::Text(text) => {
+            CastMemberKind::FilmLoop => {}
+            CastMemberKind::Text(text) | CastMemberKind::Button(text) => {
                 text.styled_text = self.try_read_chunk_by_parent(parent_id)?;
             }
-            CastMember::Palette(palette) => {
+            CastMemberKind::Palette(palette) => {
                 palette.clut = self.try_read_chunk_by_parent(parent_id)?;
             }
-            CastMember::Picture => {}
-            CastMember::Sound => {}
-            CastMember::Button => {}
-            CastMember::Shape => {}
-            CastMember::Movie => {}
-            CastMember::DigitalVideo => {}
-            CastMember::Script(_script) => {}
-            CastMember::RTE => {}
+            CastMemberKind::Picture => {}
+            CastMemberKind::Sound(sound) => {
+                sound.data = self.try_read_chunk_by_parent(parent_id)?;
+                sound.linked = self.try_load_linked_media(&cast_member.info)?;
+            }
+            CastMemberKind::Shape => {}
+            CastMemberKind::Movie => {}
+            CastMemberKind::DigitalVideo(video) => {
+                video.linked = self.try_load_linked_media(&cast_member.info)?;
+            }
+            CastMemberKind::Script(_script) => {}
+            CastMemberKind::RTE => {}
+            CastMemberKind::Transition => {}
+            CastMemberKind::Xtra => {}
         }
 
         Ok(cast_member)
     }
 
+    /// Loads the palette cast member a bitmap uses, if it uses one rather
+    /// than a built-in palette.
+    pub fn load_bitmap_clut(&self, info: &BitmapInfo) -> Option<ColorLookupTable> {
+        let palette_id = info.palette_id + 1;
+        if palette_id <= 0 {
+            return None;
+        }
+
+        match self
+            .load_cast_member(CastMemberId::new(palette_id))
+            .ok()?
+            .kind
+        {
+            CastMemberKind::Palette(palette) => palette.clut,
+            _ => None,
+        }
+    }
+
+    fn try_load_linked_media(&self, info: &CastMemberInfo) -> Result<Option<LinkedMedia>> {
+        if !info.is_linked() {
+            return Ok(None);
+        }
+
+        let Some(resolver) = self.linked_file_resolver.as_ref() else {
+            return Ok(None);
+        };
+
+        let file_path = info.file_path.as_deref().unwrap_or_default();
+        let file_name = info.file_name.as_deref().unwrap_or_default();
+
+        // Missing media is common for linked members and not an error.
+        let Some(path) = resolver.resolve(file_path, file_name) else {
+            return Ok(None);
+        };
+
+        LinkedMedia::load(&path, info.file_type.as_deref()).map(Some)
+    }
+
     // pub fn get_palette_by_cast_id(&self, cast_id: CastMemberId) -> Option<&cast_members::Palette> {
     //     let cast_member = self.cast_member(cast_id)?;
     //     let palette = match cast_member {
@@ -315,6 +796,25 @@ impl<'a> RiffFile<'a> {
         self.lingo_names.as_ref()
     }
 
+    /// Loads a compiled script by its 1-based number in the lingo context, as
+    /// referenced by `CastMemberInfo::script_id`.
+    pub fn load_lingo_script(&self, script_number: u32) -> Result<Option<LingoScript>> {
+        let Some(chunk_id) = self
+            .lingo_context
+            .as_ref()
+            .and_then(|lingo_context| {
+                lingo_context
+                    .entries()
+                    .get((script_number as usize).checked_sub(1)?)
+            })
+            .and_then(|entry| entry.script_id())
+        else {
+            return Ok(None);
+        };
+
+        self.try_read_chunk_by_id(chunk_id)
+    }
+
     pub fn read_lingo_script(&mut self) -> Result<()> {
         let chunk = self.read_chunk_by_parent(GLOBAL_ID);
 
@@ -345,3 +845,106 @@ impl<'a> RiffFile<'a> {
         Ok(())
     }
 }
+
+/// Reads the chunks of a movie one by one, yielding the memory map entry of
+/// each along with the chunk or the error reading it.
+pub struct AnyChunks<'r, 'a> {
+    riff: &'r RiffFile<'a>,
+    entries: std::slice::Iter<'r, MemoryMapEntry>,
+}
+
+impl<'r> Iterator for AnyChunks<'r, '_> {
+    type Item = (&'r MemoryMapEntry, Result<AnyChunk>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.entries.find(|entry| {
+            entry.state() == EntryState::Live
+                && !matches!(entry.tag(), tags::TAG_RIFX | tags::TAG_XFIR)
+        })?;
+
+        Some((entry, self.riff.read_any_chunk(entry.id())))
+    }
+}
+
+fn read_afterburner_version(reader: &mut Reader) -> Result<Version> {
+    let tag = Tag(reader.read_i32()?);
+    if tag != tags::TAG_Fver {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Expected tag 'Fver', found '{}'", tag),
+        ));
+    }
+
+    let _length = reader.read_var_u32()?;
+    let fver_version = reader.read_var_u32()?;
+
+    if fver_version < 0x401 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Fver version {fver_version:#x} does not record a Director version"),
+        ));
+    }
+
+    let _imap_version = reader.read_var_u32()?;
+    let director_version = reader.read_var_u32()?;
+
+    Ok(Version::new(director_version as u16))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::riff::Movie;
+
+    use super::*;
+
+    const MOVIE: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/d5.dir"));
+
+    fn tags_of(riff: &RiffFile, ids: Vec<u32>) -> Vec<(u32, Tag)> {
+        ids.into_iter()
+            .map(|id| (id, riff.mmap().entry_by_index(id).unwrap().tag()))
+            .collect()
+    }
+
+    #[test]
+    fn owns_every_chunk() {
+        let movie = Movie::from_bytes(MOVIE).unwrap();
+        let riff = movie.riff().unwrap();
+
+        assert_eq!(riff.chunk_roots(), [0, GLOBAL_ID]);
+        assert_eq!(
+            tags_of(riff, riff.chunk_children(0)),
+            [
+                (1, tags::TAG_imap),
+                (2, tags::TAG_mmap),
+                (23, tags::TAG_KEY_)
+            ]
+        );
+        assert_eq!(
+            tags_of(riff, riff.chunk_children(18)),
+            [(17, tags::TAG_Lnam), (16, tags::TAG_Lscr)]
+        );
+        assert_eq!(riff.chunk_owners(16), [18, GLOBAL_ID]);
+        assert_eq!(riff.chunk_owners(2), [0]);
+
+        // Every chunk but the free ones is in the tree once.
+        let mut seen = Vec::new();
+        let mut pending = riff.chunk_roots();
+        while let Some(id) = pending.pop() {
+            seen.push(id);
+            pending.extend(riff.chunk_children(id));
+        }
+        seen.retain(|&id| id != GLOBAL_ID);
+        seen.sort_unstable();
+        let all: Vec<_> = (0..riff.mmap().entries().len() as u32).collect();
+        assert_eq!(seen, all);
+    }
+
+    #[test]
+    fn reads_the_platform_from_the_config() {
+        let movie = Movie::from_bytes(MOVIE).unwrap();
+        let riff = movie.riff().unwrap();
+
+        assert_eq!(riff.config().platform, Some(Platform::Mac));
+        assert_eq!(riff.encoding(), TextEncoding::MacRoman);
+    }
+}
@@ -6,29 +6,44 @@ pub const TAG_APPL: Tag = Tag(0x4150504C); // "APPL"
 pub const TAG_BITD: Tag = Tag(0x42495444); // "BITD"
 pub const TAG_CAS_: Tag = Tag(0x4341532A); // "CAS*"
 pub const TAG_CASt: Tag = Tag(0x43415374); // "CASt"
+pub const TAG_CFTC: Tag = Tag(0x43465443); // "CFTC"
 pub const TAG_CLUT: Tag = Tag(0x434C5554); // "CLUT"
+pub const TAG_Dict: Tag = Tag(0x44696374); // "Dict"
 pub const TAG_DRCF: Tag = Tag(0x44524346); // "DRCF"
+pub const TAG_FGDC: Tag = Tag(0x46474443); // "FGDC"
+pub const TAG_FGDM: Tag = Tag(0x4647444D); // "FGDM"
 pub const TAG_File: Tag = Tag(0x46696C65); // "File"
 pub const TAG_free: Tag = Tag(0x66726565); // "free"
+pub const TAG_Fver: Tag = Tag(0x46766572); // "Fver"
 pub const TAG_imap: Tag = Tag(0x696D6170); // "imap"
 pub const TAG_junk: Tag = Tag(0x6A756E6B); // "junk"
 pub const TAG_KEY_: Tag = Tag(0x4B45592A); // "KEY*"
 pub const TAG_Lctx: Tag = Tag(0x4C637478); // "Lctx"
+pub const TAG_List: Tag = Tag(0x4C697374); // "List"
 pub const TAG_Lnam: Tag = Tag(0x4C6E616D); // "Lnam"
 pub const TAG_Lscr: Tag = Tag(0x4C736372); // "Lscr"
 pub const TAG_mmap: Tag = Tag(0x6D6D6170); // "mmap"
+pub const TAG_MC95: Tag = Tag(0x4D433935); // "MC95"
 pub const TAG_MV93: Tag = Tag(0x4D563933); // "MV93"
+pub const TAG_PJ00: Tag = Tag(0x504A3030); // "PJ00"
+pub const TAG_PJ01: Tag = Tag(0x504A3031); // "PJ01"
 pub const TAG_PJ93: Tag = Tag(0x504A3933); // "PJ93"
-pub const TAG_RIFX: Tag = Tag(0x52494648); // "RIFX"
+pub const TAG_PJ95: Tag = Tag(0x504A3935); // "PJ95"
+pub const TAG_PJ97: Tag = Tag(0x504A3937); // "PJ97"
+pub const TAG_RIFF: Tag = Tag(0x52494646); // "RIFF"
+pub const TAG_RIFX: Tag = Tag(0x52494658); // "RIFX"
+pub const TAG_snd_: Tag = Tag(0x736E6420); // "snd "
 pub const TAG_STXT: Tag = Tag(0x53545854); // "STXT"
 pub const TAG_THUM: Tag = Tag(0x5448554D); // "THUM"
 pub const TAG_VWCF: Tag = Tag(0x56574346); // "VWCF"
+pub const TAG_VWCI: Tag = Tag(0x56574349); // "VWCI"
+pub const TAG_VWCR: Tag = Tag(0x56574352); // "VWCR"
 pub const TAG_VWFI: Tag = Tag(0x56574649); // "VWFI"
 pub const TAG_VWLB: Tag = Tag(0x56574C42); // "VWLB"
 pub const TAG_VWSC: Tag = Tag(0x56575343); // "VWSC"
 pub const TAG_XFIR: Tag = Tag(0x58464952); // "XFIR"
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Tag(pub i32);
 
 impl From<i32> for Tag {
@@ -50,6 +65,13 @@ impl Debug for Tag {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for Tag {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 pub struct TagAsHex(pub Tag);
 
 impl Display for TagAsHex {
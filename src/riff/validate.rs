@@ -0,0 +1,614 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+};
+
+use super::{
+    RiffFile,
+    cast_members::CastMemberId,
+    chunks::{CastTable, Chunk, KeyTable, LingoContext, MemoryMapEntry, Score},
+    riff_file::GLOBAL_ID,
+    tags::{self, Tag},
+};
+
+/// What is wrong in an `Issue`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum IssueKind {
+    /// A chunk starts or ends past the end of the file.
+    ChunkOutOfFile,
+    /// Two chunks share bytes of the file.
+    ChunkOverlap,
+    /// A chunk's header has a different tag than its entry in the memory map
+    /// or the key table.
+    TagMismatch,
+    /// A chunk's header has a different size than its memory map entry.
+    SizeMismatch,
+    /// A key table entry links a chunk or parent the memory map does not have.
+    KeyToMissingChunk,
+    /// A key table entry links a `free` or `junk` chunk.
+    KeyToFreeChunk,
+    /// A chunk that nothing refers to.
+    OrphanChunk,
+    /// The free or junk list of the memory map links the wrong entries, or
+    /// misses a `free` or `junk` one.
+    BadFreeList,
+    /// A cast member whose `CASt` chunk fails to parse.
+    BadCastMember,
+    /// A chunk of another type the decoder knows that fails to parse.
+    BadChunk,
+    /// The score uses a cast member that is not in the cast table.
+    MissingCastMember,
+    /// A table the other checks need could not be read.
+    Unreadable,
+}
+
+impl fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            IssueKind::ChunkOutOfFile => "chunk_out_of_file",
+            IssueKind::ChunkOverlap => "chunk_overlap",
+            IssueKind::TagMismatch => "tag_mismatch",
+            IssueKind::SizeMismatch => "size_mismatch",
+            IssueKind::KeyToMissingChunk => "key_to_missing_chunk",
+            IssueKind::KeyToFreeChunk => "key_to_free_chunk",
+            IssueKind::OrphanChunk => "orphan_chunk",
+            IssueKind::BadFreeList => "bad_free_list",
+            IssueKind::BadCastMember => "bad_cast_member",
+            IssueKind::BadChunk => "bad_chunk",
+            IssueKind::MissingCastMember => "missing_cast_member",
+            IssueKind::Unreadable => "unreadable",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A problem `RiffFile::validate` found in a movie.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Issue {
+    pub kind: IssueKind,
+    /// The chunk the issue is about, if it is about one.
+    pub chunk_id: Option<u32>,
+    pub message: String,
+}
+
+impl Issue {
+    pub fn new(kind: IssueKind, chunk_id: Option<u32>, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            chunk_id,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.chunk_id {
+            Some(id) => write!(f, "{}\t{id}\t{}", self.kind, self.message),
+            None => write!(f, "{}\t-\t{}", self.kind, self.message),
+        }
+    }
+}
+
+impl RiffFile<'_> {
+    /// Checks the movie for damage: chunks that are out of the file, overlap,
+    /// or disagree with the memory map, broken free and junk lists, key table
+    /// entries that link missing or freed chunks, chunks nothing refers to,
+    /// chunks and cast members that fail to parse, and cast members the score
+    /// uses that do not exist.
+    ///
+    /// The key table, cast table and score are read anew, so this works on a
+    /// movie whose tables have not been read, or could not be.
+    pub fn validate(&self) -> Vec<Issue> {
+        let mut issues = Vec::new();
+
+        self.validate_chunks(&mut issues);
+        let damaged: HashSet<u32> = issues.iter().filter_map(|issue| issue.chunk_id).collect();
+        issues.extend(
+            self.mmap().check_lists().into_iter().map(|problem| {
+                Issue::new(IssueKind::BadFreeList, problem.entry_id, problem.message)
+            }),
+        );
+
+        let key_table = match self.read_chunk_by_tag::<KeyTable>() {
+            Ok(key_table) => key_table,
+            Err(err) => {
+                issues.push(Issue::new(
+                    IssueKind::Unreadable,
+                    None,
+                    format!("Unable to read the key table: {err}"),
+                ));
+                return issues;
+            }
+        };
+        self.validate_key_table(&key_table, &mut issues);
+
+        let cast_table = self.read_table::<CastTable>(&key_table, "cast table", &mut issues);
+        let lingo_context =
+            self.read_table::<LingoContext>(&key_table, "Lingo context", &mut issues);
+        self.validate_references(
+            &key_table,
+            cast_table.as_ref(),
+            lingo_context.as_ref(),
+            &mut issues,
+        );
+
+        self.validate_chunk_contents(cast_table.as_ref(), &damaged, &mut issues);
+
+        if let Some(cast_table) = &cast_table
+            && let Some(score) = self.read_table::<Score>(&key_table, "score", &mut issues)
+        {
+            validate_score(&score, cast_table, &mut issues);
+        }
+
+        issues
+    }
+
+    /// Reads a chunk of the movie that hangs off the global entry of
+    /// `key_table`, which it is fine to be without.
+    fn read_table<C: Chunk>(
+        &self,
+        key_table: &KeyTable,
+        name: &str,
+        issues: &mut Vec<Issue>,
+    ) -> Option<C> {
+        let id = key_table.find_id_of_chunk_with_parent(C::TAG, GLOBAL_ID)?;
+
+        match self.read_chunk_by_id::<C>(id) {
+            Ok(table) => Some(table),
+            Err(err) => {
+                issues.push(Issue::new(
+                    IssueKind::Unreadable,
+                    None,
+                    format!("Unable to read the {name}: {err}"),
+                ));
+                None
+            }
+        }
+    }
+
+    fn validate_chunks(&self, issues: &mut Vec<Issue>) {
+        let file_len = self.file_len() as u64;
+        let mut extents = Vec::new();
+
+        for entry in self.mmap().entries() {
+            if is_free(entry.tag()) {
+                continue;
+            }
+
+            let id = Some(entry.id());
+            let (tag, size) = match self.read_chunk_header_at(entry.pos()) {
+                Ok(header) => header,
+                Err(_) => {
+                    issues.push(Issue::new(
+                        IssueKind::ChunkOutOfFile,
+                        id,
+                        format!(
+                            "'{}' at 0x{:x} is past the end of the file",
+                            entry.tag(),
+                            entry.pos()
+                        ),
+                    ));
+                    continue;
+                }
+            };
+
+            if tag != entry.tag() {
+                issues.push(Issue::new(
+                    IssueKind::TagMismatch,
+                    id,
+                    format!(
+                        "The memory map says '{}' but the header at 0x{:x} says '{tag}'",
+                        entry.tag(),
+                        entry.pos()
+                    ),
+                ));
+            }
+
+            if size != entry.len() {
+                issues.push(Issue::new(
+                    IssueKind::SizeMismatch,
+                    id,
+                    format!(
+                        "'{}' is {size} bytes long in its header but {} in the memory map",
+                        entry.tag(),
+                        entry.len()
+                    ),
+                ));
+            }
+
+            let end = entry.pos() as u64 + 8 + size as u64;
+            if end > file_len {
+                issues.push(Issue::new(
+                    IssueKind::ChunkOutOfFile,
+                    id,
+                    format!(
+                        "'{}' at 0x{:x} ends at 0x{end:x}, past the end of the file at 0x{file_len:x}",
+                        entry.tag(),
+                        entry.pos()
+                    ),
+                ));
+            }
+
+            // The file's own entry spans every other chunk.
+            if !is_file_tag(entry.tag()) {
+                extents.push((entry.pos() as u64, end, entry));
+            }
+        }
+
+        // Each chunk is checked against the one that reaches furthest of
+        // those before it, so that a chunk spanning several others overlaps
+        // all of them.
+        extents.sort_by_key(|&(start, end, _)| (start, end));
+        let mut furthest: Option<(u64, &MemoryMapEntry)> = None;
+        for (start, end, entry) in extents {
+            if let Some((previous_end, previous)) = furthest
+                && start < previous_end
+            {
+                issues.push(Issue::new(
+                    IssueKind::ChunkOverlap,
+                    Some(entry.id()),
+                    format!(
+                        "'{}' at 0x{start:x} overlaps chunk {} '{}', which ends at 0x{previous_end:x}",
+                        entry.tag(),
+                        previous.id(),
+                        previous.tag()
+                    ),
+                ));
+            }
+            if furthest.is_none_or(|(previous_end, _)| end > previous_end) {
+                furthest = Some((end, entry));
+            }
+        }
+    }
+
+    fn validate_key_table(&self, key_table: &KeyTable, issues: &mut Vec<Issue>) {
+        for key in key_table.entries() {
+            let Some(entry) = self.mmap().entry_by_index(key.id()) else {
+                issues.push(Issue::new(
+                    IssueKind::KeyToMissingChunk,
+                    Some(key.id()),
+                    format!(
+                        "The key table links '{}' {} to parent {}, but there is no such chunk",
+                        key.tag(),
+                        key.id(),
+                        key.parent()
+                    ),
+                ));
+                continue;
+            };
+
+            if is_free(entry.tag()) {
+                issues.push(Issue::new(
+                    IssueKind::KeyToFreeChunk,
+                    Some(key.id()),
+                    format!(
+                        "The key table links '{}' {} to parent {}, but the chunk is '{}'",
+                        key.tag(),
+                        key.id(),
+                        key.parent(),
+                        entry.tag()
+                    ),
+                ));
+            } else if entry.tag() != key.tag() {
+                issues.push(Issue::new(
+                    IssueKind::TagMismatch,
+                    Some(key.id()),
+                    format!(
+                        "The key table says '{}' but the memory map says '{}'",
+                        key.tag(),
+                        entry.tag()
+                    ),
+                ));
+            }
+
+            if key.parent() != GLOBAL_ID && self.mmap().entry_by_index(key.parent()).is_none() {
+                issues.push(Issue::new(
+                    IssueKind::KeyToMissingChunk,
+                    Some(key.id()),
+                    format!(
+                        "The key table links '{}' {} to parent {}, but there is no such chunk",
+                        key.tag(),
+                        key.id(),
+                        key.parent()
+                    ),
+                ));
+            }
+        }
+    }
+
+    /// Reports chunks that neither the key table, the cast table nor the Lingo
+    /// context refer to, other than the movie's own tables.
+    fn validate_references(
+        &self,
+        key_table: &KeyTable,
+        cast_table: Option<&CastTable>,
+        lingo_context: Option<&LingoContext>,
+        issues: &mut Vec<Issue>,
+    ) {
+        let mut referenced: HashSet<u32> = key_table.entries().iter().map(|e| e.id()).collect();
+
+        if let Some(cast_table) = cast_table {
+            referenced.extend(
+                cast_table
+                    .cast_member_ids()
+                    .filter_map(|id| cast_table.cast_member_chunk_id(id)),
+            );
+        }
+
+        if let Some(lingo_context) = lingo_context {
+            referenced.insert(lingo_context.names_chunk_id());
+            referenced.extend(
+                lingo_context
+                    .entries()
+                    .iter()
+                    .filter_map(|entry| entry.script_id()),
+            );
+        }
+
+        for entry in self.mmap().entries() {
+            let tag = entry.tag();
+            if referenced.contains(&entry.id())
+                || is_free(tag)
+                || is_file_tag(tag)
+                || matches!(tag, tags::TAG_imap | tags::TAG_mmap | tags::TAG_KEY_)
+            {
+                continue;
+            }
+
+            issues.push(Issue::new(
+                IssueKind::OrphanChunk,
+                Some(entry.id()),
+                format!("Nothing refers to '{tag}' at 0x{:x}", entry.pos()),
+            ));
+        }
+    }
+
+    /// Parses every chunk of a type the decoder knows, other than those
+    /// `validate_chunks` found to be `damaged`.
+    fn validate_chunk_contents(
+        &self,
+        cast_table: Option<&CastTable>,
+        damaged: &HashSet<u32>,
+        issues: &mut Vec<Issue>,
+    ) {
+        for (entry, chunk) in self.chunks() {
+            let Err(err) = chunk else {
+                continue;
+            };
+            if damaged.contains(&entry.id()) {
+                continue;
+            }
+
+            let (kind, what) = if entry.tag() == tags::TAG_CASt {
+                let member = cast_table
+                    .and_then(|cast_table| cast_table.cast_member_with_chunk_id(entry.id()));
+                let what = match member {
+                    Some(member) => format!("Cast member {}", member.id()),
+                    None => "'CASt'".to_string(),
+                };
+                (IssueKind::BadCastMember, what)
+            } else {
+                (IssueKind::BadChunk, format!("'{}'", entry.tag()))
+            };
+
+            issues.push(Issue::new(
+                kind,
+                Some(entry.id()),
+                format!("{what} fails to parse: {err}"),
+            ));
+        }
+    }
+}
+
+/// Reports each cast member the score uses that is not in the cast table,
+/// once, with the first place it is used. Members of other casts are not
+/// checked.
+fn validate_score(score: &Score, cast_table: &CastTable, issues: &mut Vec<Issue>) {
+    let mut missing = BTreeMap::<i16, (String, usize)>::new();
+    let mut check = |id: Option<CastMemberId>, place: &dyn Fn() -> String| {
+        let Some(id) = id else {
+            return;
+        };
+        if id.id() <= 0
+            || id.cast().is_some_and(|cast| cast != 1)
+            || cast_table.cast_member_chunk_id(id).is_some()
+        {
+            return;
+        }
+
+        missing
+            .entry(id.id())
+            .and_modify(|(_, count)| *count += 1)
+            .or_insert_with(|| (place(), 1));
+    };
+
+    for frame in score.frames() {
+        check(frame.palette_id, &|| {
+            format!("as the palette of frame {}", frame.index)
+        });
+
+        for (channel, sprite) in &frame.sprite_channels {
+            let place = || format!("in channel {channel} of frame {}", frame.index);
+            check(sprite.cast_member_id, &place);
+            check(sprite.script_id, &place);
+        }
+    }
+
+    for (id, (place, count)) in missing {
+        let others = match count {
+            1 => String::new(),
+            2 => " and in 1 other place".to_string(),
+            _ => format!(" and in {} other places", count - 1),
+        };
+
+        issues.push(Issue::new(
+            IssueKind::MissingCastMember,
+            None,
+            format!("The score uses cast member {id} {place}{others}, but it does not exist"),
+        ));
+    }
+}
+
+fn is_free(tag: Tag) -> bool {
+    tag == tags::TAG_free || tag == tags::TAG_junk
+}
+
+fn is_file_tag(tag: Tag) -> bool {
+    tag == tags::TAG_RIFX || tag == tags::TAG_XFIR
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::riff::{Movie, MovieWriter, OpenOptions};
+
+    use super::*;
+
+    const MOVIE: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/d5.dir"));
+
+    fn read(data: &[u8]) -> RiffFile<'_> {
+        let options = OpenOptions {
+            headers_only: true,
+            ..OpenOptions::default()
+        };
+        Movie::from_bytes_with(data, options)
+            .unwrap()
+            .into_riff()
+            .unwrap()
+    }
+
+    fn issues(data: &[u8]) -> Vec<(IssueKind, Option<u32>)> {
+        read(data)
+            .validate()
+            .into_iter()
+            .map(|issue| (issue.kind, issue.chunk_id))
+            .collect()
+    }
+
+    fn chunk_pos(id: u32) -> usize {
+        read(MOVIE).mmap().entry_by_index(id).unwrap().pos() as usize
+    }
+
+    /// Where the memory map entry of chunk `id` is in the file.
+    fn entry_pos(id: u32) -> usize {
+        let mmap_pos = read_u32(MOVIE, 24) as usize;
+        let header_len = u16::from_be_bytes([MOVIE[mmap_pos + 8], MOVIE[mmap_pos + 9]]);
+        mmap_pos + 8 + header_len as usize + id as usize * 20
+    }
+
+    fn read_u32(data: &[u8], pos: usize) -> u32 {
+        u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap())
+    }
+
+    fn patch(data: &mut [u8], pos: usize, bytes: &[u8]) {
+        data[pos..pos + bytes.len()].copy_from_slice(bytes);
+    }
+
+    fn assert_has(issues: &[(IssueKind, Option<u32>)], kind: IssueKind, id: Option<u32>) {
+        assert!(
+            issues.contains(&(kind, id)),
+            "No {kind} for {id:?} in {issues:?}"
+        );
+    }
+
+    #[test]
+    fn accepts_an_intact_movie() {
+        assert_eq!(issues(MOVIE), []);
+    }
+
+    #[test]
+    fn reports_every_chunk_a_long_chunk_overlaps() {
+        // Chunk 4 grows to end inside chunk 6, spanning chunk 5.
+        let (pos_4, pos_5, pos_6) = (chunk_pos(4), chunk_pos(5), chunk_pos(6));
+        assert!(pos_4 < pos_5 && pos_5 < pos_6);
+
+        let mut movie = MOVIE.to_vec();
+        patch(
+            &mut movie,
+            pos_4 + 4,
+            &((pos_6 - pos_4) as u32).to_be_bytes(),
+        );
+
+        let issues = issues(&movie);
+        assert_has(&issues, IssueKind::SizeMismatch, Some(4));
+        assert_has(&issues, IssueKind::ChunkOverlap, Some(5));
+        assert_has(&issues, IssueKind::ChunkOverlap, Some(6));
+    }
+
+    #[test]
+    fn reports_chunks_past_the_end_of_the_file() {
+        let riff = read(MOVIE);
+        let last = riff
+            .mmap()
+            .entries()
+            .iter()
+            .max_by_key(|e| e.pos())
+            .unwrap();
+        let pos = last.pos() as usize;
+
+        let mut movie = MOVIE.to_vec();
+        patch(&mut movie, pos + 4, &(last.len() + 100).to_be_bytes());
+        assert_has(&issues(&movie), IssueKind::ChunkOutOfFile, Some(last.id()));
+
+        let mut movie = MOVIE.to_vec();
+        patch(
+            &mut movie,
+            entry_pos(7) + 8,
+            &(MOVIE.len() as u32).to_be_bytes(),
+        );
+        assert_has(&issues(&movie), IssueKind::ChunkOutOfFile, Some(7));
+    }
+
+    #[test]
+    fn reports_tag_and_size_mismatches() {
+        let mut movie = MOVIE.to_vec();
+        patch(&mut movie, chunk_pos(7), b"XXXX");
+        let len = read_u32(MOVIE, entry_pos(9) + 4);
+        patch(&mut movie, entry_pos(9) + 4, &(len + 2).to_be_bytes());
+
+        let issues = issues(&movie);
+        assert_has(&issues, IssueKind::TagMismatch, Some(7));
+        assert_has(&issues, IssueKind::SizeMismatch, Some(9));
+    }
+
+    #[test]
+    fn reports_keys_to_free_chunks() {
+        let mut movie = MOVIE.to_vec();
+        patch(&mut movie, entry_pos(7), b"free");
+
+        assert_has(&issues(&movie), IssueKind::KeyToFreeChunk, Some(7));
+    }
+
+    #[test]
+    fn reports_orphan_chunks() {
+        let riff = read(MOVIE);
+        let mut writer = MovieWriter::new(&riff).unwrap();
+        let id = writer.add_chunk_data(Tag(i32::from_be_bytes(*b"Test")), None, vec![1, 2]);
+        let movie = writer.write().unwrap();
+
+        assert_eq!(issues(&movie), [(IssueKind::OrphanChunk, Some(id))]);
+    }
+
+    #[test]
+    fn reports_missing_cast_members() {
+        let cast_table = read(MOVIE)
+            .mmap()
+            .first_entry_with_tag(tags::TAG_CAS_)
+            .unwrap()
+            .id();
+
+        // Cast member 1, which the score uses, is taken out of the cast table.
+        let mut movie = MOVIE.to_vec();
+        patch(&mut movie, chunk_pos(cast_table) + 8, &0u32.to_be_bytes());
+
+        let issues = issues(&movie);
+        assert_has(&issues, IssueKind::MissingCastMember, None);
+        assert_has(&issues, IssueKind::OrphanChunk, Some(4));
+    }
+}
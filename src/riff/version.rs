@@ -1,6 +1,6 @@
 use std::fmt;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Version {
     version: u16,
     human_version: u16,
@@ -37,6 +37,11 @@ impl Version {
         }
     }
 
+    /// The version number as stored in the file, e.g. `0x4C1`.
+    pub fn raw(self) -> u16 {
+        self.version
+    }
+
     pub fn major(self) -> u16 {
         self.human_version / 100
     }
@@ -52,6 +57,19 @@ impl Default for Version {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for Version {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut s = serializer.serialize_struct("Version", 3)?;
+        s.serialize_field("raw", &self.raw())?;
+        s.serialize_field("major", &self.major())?;
+        s.serialize_field("minor", &self.minor())?;
+        s.end()
+    }
+}
+
 impl fmt::Display for Version {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
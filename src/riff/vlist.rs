@@ -1,8 +1,11 @@
 #![allow(unused)]
 
-use std::io::{Error, ErrorKind, Result, Seek};
+use std::io::{Error, ErrorKind, Result, Seek, Write};
 
-use crate::reader::{ReadBytesExt, Reader};
+use crate::{
+    reader::{ReadBytesExt, Reader},
+    writer::WriteBytesExt,
+};
 
 pub struct VList<'a> {
     numbers: Vec<u32>,
@@ -87,6 +90,16 @@ impl<'a> VList<'a> {
             ));
         }
 
+        let entries_len = offsets.last().copied().unwrap_or_default() as u64;
+        if entry_start + entries_len > r.stream_len()? {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "VList entries of {entries_len} bytes extend past the end of the data at {entry_start}"
+                ),
+            ));
+        }
+
         Ok(VList {
             numbers,
             entry_count,
@@ -113,7 +126,17 @@ impl<'a> VList<'a> {
             return None;
         }
 
-        Some(self.reader.subset(start + position, size))
+        // The offsets are checked against the data when the list is read.
+        self.reader.subset(start + position, size).ok()
+    }
+
+    pub fn try_get_as_str(&self, index: usize) -> Result<Option<String>> {
+        self.get(index)
+            .map(|mut r| {
+                let len = r.stream_len()? as usize;
+                r.read_fixed_str(len)
+            })
+            .transpose()
     }
 
     pub fn try_get_as_pascal_str(&self, index: usize) -> Result<Option<String>> {
@@ -149,7 +172,75 @@ pub struct VListItem<'a> {
 }
 
 impl VListItem<'_> {
-    pub fn reader(&self) -> Reader {
+    pub fn reader(&self) -> Reader<'_> {
         self.r.clone()
     }
 }
+
+/// Builds a VList with 32-bit numbers, starting from an existing one so that
+/// entries which are not touched keep their bytes.
+#[derive(Debug, Default)]
+pub struct VListBuilder {
+    numbers: Vec<u32>,
+    entries: Vec<Vec<u8>>,
+}
+
+impl VListBuilder {
+    pub fn new(numbers: Vec<u32>) -> Self {
+        Self {
+            numbers,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn from_vlist(vlist: &VList) -> Result<Self> {
+        let mut entries = Vec::with_capacity(vlist.entry_count as usize);
+        for index in 0..vlist.entry_count as usize {
+            let mut entry = Vec::new();
+            if let Some(mut r) = vlist.get(index) {
+                r.read_to_end(&mut entry)?;
+            }
+            entries.push(entry);
+        }
+
+        Ok(Self {
+            numbers: vlist.numbers.clone(),
+            entries,
+        })
+    }
+
+    pub fn set_number(&mut self, index: usize, value: u32) {
+        if self.numbers.len() <= index {
+            self.numbers.resize(index + 1, 0);
+        }
+        self.numbers[index] = value;
+    }
+
+    pub fn set_entry(&mut self, index: usize, data: Vec<u8>) {
+        if self.entries.len() <= index {
+            self.entries.resize(index + 1, Vec::new());
+        }
+        self.entries[index] = data;
+    }
+
+    pub fn write_u32(&self, w: &mut impl Write) -> Result<()> {
+        w.write_be_u32(4 + 4 * self.numbers.len() as u32)?;
+        for &number in &self.numbers {
+            w.write_be_u32(number)?;
+        }
+
+        w.write_be_u16(self.entries.len() as u16)?;
+        let mut offset = 0;
+        w.write_be_u32(offset)?;
+        for entry in &self.entries {
+            offset += entry.len() as u32;
+            w.write_be_u32(offset)?;
+        }
+
+        for entry in &self.entries {
+            w.write_all(entry)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use std::io::{Error, ErrorKind, Result};
+
+use crate::reader::{ByteOrder, TextEncoding};
+
+#[allow(unused)]
+pub trait WriteBytesExt: std::io::Write {
+    #[inline]
+    fn write_u8(&mut self, value: u8) -> Result<()> {
+        self.write_all(&[value])
+    }
+
+    #[inline]
+    fn write_i8(&mut self, value: i8) -> Result<()> {
+        self.write_all(&[value as u8])
+    }
+
+    fn write_u16(&mut self, value: u16, byte_order: ByteOrder) -> Result<()> {
+        match byte_order {
+            ByteOrder::LittleEndian => self.write_le_u16(value),
+            ByteOrder::BigEndian => self.write_be_u16(value),
+        }
+    }
+
+    fn write_i16(&mut self, value: i16, byte_order: ByteOrder) -> Result<()> {
+        self.write_u16(value as u16, byte_order)
+    }
+
+    #[inline]
+    fn write_le_u16(&mut self, value: u16) -> Result<()> {
+        self.write_all(&value.to_le_bytes())
+    }
+
+    #[inline]
+    fn write_be_u16(&mut self, value: u16) -> Result<()> {
+        self.write_all(&value.to_be_bytes())
+    }
+
+    #[inline]
+    fn write_be_i16(&mut self, value: i16) -> Result<()> {
+        self.write_all(&value.to_be_bytes())
+    }
+
+    fn write_u32(&mut self, value: u32, byte_order: ByteOrder) -> Result<()> {
+        match byte_order {
+            ByteOrder::LittleEndian => self.write_le_u32(value),
+            ByteOrder::BigEndian => self.write_be_u32(value),
+        }
+    }
+
+    fn write_i32(&mut self, value: i32, byte_order: ByteOrder) -> Result<()> {
+        self.write_u32(value as u32, byte_order)
+    }
+
+    #[inline]
+    fn write_le_u32(&mut self, value: u32) -> Result<()> {
+        self.write_all(&value.to_le_bytes())
+    }
+
+    #[inline]
+    fn write_be_u32(&mut self, value: u32) -> Result<()> {
+        self.write_all(&value.to_be_bytes())
+    }
+
+    #[inline]
+    fn write_be_i32(&mut self, value: i32) -> Result<()> {
+        self.write_all(&value.to_be_bytes())
+    }
+
+    fn write_pascal_str(&mut self, s: &str, encoding: TextEncoding) -> Result<()> {
+        let bytes = encoding.encode(s)?;
+        let len = u8::try_from(bytes.len()).map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "String is too long for a Pascal string: {} bytes",
+                    bytes.len()
+                ),
+            )
+        })?;
+
+        self.write_u8(len)?;
+        self.write_all(&bytes)
+    }
+
+    fn write_str(&mut self, s: &str, encoding: TextEncoding) -> Result<()> {
+        self.write_all(&encoding.encode(s)?)
+    }
+}
+
+impl<W: std::io::Write> WriteBytesExt for W {}
@@ -0,0 +1,96 @@
+mod bytes_ext;
+
+pub use bytes_ext::WriteBytesExt;
+
+use std::io::{Result, Write};
+
+use crate::reader::{ByteOrder, TextEncoding};
+
+/// The counterpart of `Reader`: an in-memory buffer that knows the byte order
+/// and text encoding of the file being written.
+#[derive(Debug)]
+pub struct Writer {
+    buf: Vec<u8>,
+    byte_order: ByteOrder,
+    encoding: TextEncoding,
+}
+
+impl Writer {
+    pub fn new(byte_order: ByteOrder) -> Self {
+        Self {
+            buf: Vec::new(),
+            byte_order,
+            encoding: TextEncoding::default(),
+        }
+    }
+
+    pub fn byte_order(&self) -> ByteOrder {
+        self.byte_order
+    }
+
+    pub fn set_encoding(&mut self, encoding: TextEncoding) {
+        self.encoding = encoding;
+    }
+
+    pub fn encoding(&self) -> TextEncoding {
+        self.encoding
+    }
+
+    pub fn position(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.buf
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.buf
+    }
+
+    pub fn write_u16(&mut self, value: u16) -> Result<()> {
+        WriteBytesExt::write_u16(self, value, self.byte_order)
+    }
+
+    pub fn write_i16(&mut self, value: i16) -> Result<()> {
+        WriteBytesExt::write_i16(self, value, self.byte_order)
+    }
+
+    pub fn write_u32(&mut self, value: u32) -> Result<()> {
+        WriteBytesExt::write_u32(self, value, self.byte_order)
+    }
+
+    pub fn write_i32(&mut self, value: i32) -> Result<()> {
+        WriteBytesExt::write_i32(self, value, self.byte_order)
+    }
+
+    pub fn write_pascal_str(&mut self, s: &str) -> Result<()> {
+        WriteBytesExt::write_pascal_str(self, s, self.encoding)
+    }
+
+    pub fn write_str(&mut self, s: &str) -> Result<()> {
+        WriteBytesExt::write_str(self, s, self.encoding)
+    }
+
+    pub fn write_zeros(&mut self, len: usize) {
+        self.buf.resize(self.buf.len() + len, 0);
+    }
+
+    /// Pads the buffer to an even length, as chunks are word aligned.
+    pub fn align(&mut self) {
+        if !self.buf.len().is_multiple_of(2) {
+            self.buf.push(0);
+        }
+    }
+}
+
+impl Write for Writer {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}